@@ -0,0 +1,172 @@
+//! [`Arbitrary`] signatures and values conforming to them, for fuzzing.
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::signature::Signature;
+use crate::value::Value;
+
+/// How deep containers are nested in signatures.
+///
+/// Along with [`MAX_FIELDS`], this keeps signatures well within the D-Bus limits.
+const MAX_DEPTH: u32 = 4;
+const MAX_FIELDS: usize = 3;
+const MAX_ITEMS: usize = 4;
+/// How deep variants are nested in values.
+const MAX_VARIANT_DEPTH: u32 = 2;
+
+/// Valid D-Bus signatures of a single complete type.
+impl<'a> Arbitrary<'a> for Signature {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        signature_at(u, MAX_DEPTH)
+    }
+}
+
+/// An arbitrary value of type `signature`.
+///
+/// Unlike the signatures, values aren't restricted any further than their type requires, e.g.
+/// floating point numbers can be NaN and dictionaries can have duplicate keys.
+pub fn value(signature: &Signature, u: &mut Unstructured) -> Result<Value> {
+    value_at(signature, u, MAX_VARIANT_DEPTH)
+}
+
+fn signature_at(u: &mut Unstructured, depth: u32) -> Result<Signature> {
+    let basic = Signature::BASIC_TYPES.len();
+    let containers = if depth == 0 { 0 } else { 3 };
+    let choice = u.int_in_range(0..=basic + containers)?;
+    if choice < basic {
+        return Ok(Signature::BASIC_TYPES[choice].clone());
+    }
+
+    let signature = match choice - basic {
+        0 => Signature::Value,
+        1 => Signature::Array(signature_at(u, depth - 1)?.into()),
+        2 => Signature::Dict {
+            key: u.choose(Signature::BASIC_TYPES)?.clone().into(),
+            value: signature_at(u, depth - 1)?.into(),
+        },
+        _ => {
+            let len = u.int_in_range(1..=MAX_FIELDS)?;
+            let fields = (0..len)
+                .map(|_| signature_at(u, depth - 1))
+                .collect::<Result<Vec<_>>>()?;
+
+            Signature::Structure(fields.into())
+        }
+    };
+
+    Ok(signature)
+}
+
+fn value_at(signature: &Signature, u: &mut Unstructured, variant_depth: u32) -> Result<Value> {
+    let value = match signature {
+        Signature::Unit => Value::Unit,
+        Signature::U8 => Value::U8(u.arbitrary()?),
+        Signature::Bool => Value::Bool(u.arbitrary()?),
+        Signature::I16 => Value::I16(u.arbitrary()?),
+        Signature::U16 => Value::U16(u.arbitrary()?),
+        Signature::I32 => Value::I32(u.arbitrary()?),
+        Signature::U32 => Value::U32(u.arbitrary()?),
+        Signature::I64 => Value::I64(u.arbitrary()?),
+        Signature::U64 => Value::U64(u.arbitrary()?),
+        #[cfg(unix)]
+        Signature::Fd => Value::Fd(u.arbitrary()?),
+        Signature::F64 => Value::F64(u.arbitrary()?),
+        Signature::Str => Value::Str(String::arbitrary(u)?.replace('\0', "")),
+        Signature::ObjectPath => {
+            const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_";
+
+            let mut path = String::new();
+            for _ in 0..u.int_in_range(0..=3)? {
+                path.push('/');
+                for _ in 0..u.int_in_range(1..=8)? {
+                    path.push(*u.choose(CHARS)? as char);
+                }
+            }
+            if path.is_empty() {
+                path.push('/');
+            }
+
+            Value::ObjectPath(path)
+        }
+        Signature::Signature => Value::Signature(u.arbitrary()?),
+        Signature::Value => {
+            // Only basic types at the innermost level, so that nesting ends.
+            let signature = match variant_depth {
+                0 => u.choose(Signature::BASIC_TYPES)?.clone(),
+                _ => u.arbitrary()?,
+            };
+
+            Value::Variant(Box::new(value_at(
+                &signature,
+                u,
+                variant_depth.saturating_sub(1),
+            )?))
+        }
+        Signature::Array(element) => {
+            let len = u.int_in_range(0..=MAX_ITEMS)?;
+
+            Value::Array {
+                element: (**element).clone(),
+                items: (0..len)
+                    .map(|_| value_at(element, u, variant_depth))
+                    .collect::<Result<_>>()?,
+            }
+        }
+        Signature::Dict { key, value } => {
+            let len = u.int_in_range(0..=MAX_ITEMS)?;
+
+            Value::Dict {
+                key: (**key).clone(),
+                value: (**value).clone(),
+                entries: (0..len)
+                    .map(|_| {
+                        Ok((
+                            value_at(key, u, variant_depth)?,
+                            value_at(value, u, variant_depth)?,
+                        ))
+                    })
+                    .collect::<Result<_>>()?,
+            }
+        }
+        Signature::Structure(fields) => Value::Structure(
+            fields
+                .iter()
+                .map(|field| value_at(field, u, variant_depth))
+                .collect::<Result<_>>()?,
+        ),
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => Value::Maybe {
+            child: (**child).clone(),
+            value: match u.arbitrary()? {
+                true => Some(Box::new(value_at(child, u, variant_depth)?)),
+                false => None,
+            },
+        },
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic_type::DynamicType;
+    use crate::signature::Format;
+
+    #[test]
+    fn conforming() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let signature = Signature::arbitrary(&mut u).unwrap();
+            assert_eq!(signature.check_format(Format::DBus), vec![]);
+            assert_eq!(
+                signature.to_string().parse::<Signature>().unwrap(),
+                signature
+            );
+
+            let value = value(&signature, &mut u).unwrap();
+            assert_eq!(value.signature(), signature);
+        }
+    }
+}
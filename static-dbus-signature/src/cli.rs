@@ -0,0 +1,446 @@
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+use serde_json::{json, Value as Json};
+
+use static_dbus_signature::codegen::Generator;
+use static_dbus_signature::signature::{validate, Format, ParseError, PathSegment, Signature};
+
+const USAGE: &str = "\
+Usage: static-dbus-signature <COMMAND> [OPTIONS] [SIGNATURE...]
+
+Signatures are read from the arguments or, if none are given, from stdin (one per line).
+
+Commands:
+  validate             Check that signatures are valid
+  explain              Print a signature as a tree
+  layout               Print the alignment and fixed size of a signature
+  convert              Check that a signature is portable to a format
+  generate             Generate Rust types for signatures or introspection XML
+
+Options:
+  --json               Print machine-readable JSON
+  --format <FORMAT>    The format to use for `layout` and `convert` (dbus or gvariant).
+                       Defaults to all formats.
+  --name <NAME>        The name of the type generated by `generate`. Defaults to
+                       `Signature0`, `Signature1`, etc.
+  --xml <FILE>         Generate types for all members in an introspection XML file
+                       instead of signatures
+  --crate <PATH>       The path of the crate providing `Type` in generated code";
+
+struct Options {
+    json: bool,
+    formats: Vec<Format>,
+    signatures: Vec<String>,
+    name: Option<String>,
+    xml: Option<String>,
+    crate_path: Option<String>,
+}
+
+/// Run the command-line tool with the given arguments (excluding the program name).
+pub fn run(mut args: impl Iterator<Item = String>) -> ExitCode {
+    let command = match args.next() {
+        Some(command) if command != "--help" && command != "-h" => command,
+        _ => {
+            println!("{}", USAGE);
+
+            return ExitCode::SUCCESS;
+        }
+    };
+    let command: fn(&Options) -> bool = match command.as_str() {
+        "validate" => validate_command,
+        "explain" => explain_command,
+        "layout" => layout_command,
+        "convert" => convert_command,
+        "generate" => generate_command,
+        _ => return usage_error(&format!("unknown command `{}`", command)),
+    };
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => return usage_error(&e),
+    };
+
+    let success = command(&options);
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        json: false,
+        formats: Vec::new(),
+        signatures: Vec::new(),
+        name: None,
+        xml: None,
+        crate_path: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--format" => {
+                let format = args.next().ok_or("`--format` requires a value")?;
+                options.formats.push(parse_format(&format)?);
+            }
+            "--name" => options.name = Some(args.next().ok_or("`--name` requires a value")?),
+            "--xml" => options.xml = Some(args.next().ok_or("`--xml` requires a value")?),
+            "--crate" => {
+                options.crate_path = Some(args.next().ok_or("`--crate` requires a value")?)
+            }
+            "--" => options.signatures.extend(&mut args),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ => options.signatures.push(arg),
+        }
+    }
+
+    if options.formats.is_empty() {
+        options.formats = Format::ALL.to_vec();
+    }
+    if options.signatures.is_empty() && options.xml.is_none() {
+        for line in io::stdin().lock().lines() {
+            let line = line.map_err(|e| format!("failed to read stdin: {}", e))?;
+            let line = line.trim();
+            if !line.is_empty() {
+                options.signatures.push(line.to_string());
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_format(s: &str) -> Result<Format, String> {
+    match s {
+        "dbus" => Ok(Format::DBus),
+        #[cfg(feature = "gvariant")]
+        "gvariant" => Ok(Format::GVariant),
+        _ => Err(format!("unsupported format `{}`", s)),
+    }
+}
+
+fn format_id(format: Format) -> &'static str {
+    match format {
+        Format::DBus => "dbus",
+        #[cfg(feature = "gvariant")]
+        Format::GVariant => "gvariant",
+    }
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+
+    ExitCode::from(2)
+}
+
+fn print_json(value: &Json) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+/// Parse all signatures, in the order they were given.
+fn parse_all(options: &Options) -> Vec<(&str, Result<Signature, ParseError>)> {
+    options
+        .signatures
+        .iter()
+        .map(|s| (s.as_str(), s.parse()))
+        .collect()
+}
+
+/// Print an invalid signature to stderr, pointing at the error.
+fn print_error(s: &str, e: &ParseError) {
+    eprintln!("{}\n{:>width$} {}", s, "^", e, width = e.offset() + 1);
+}
+
+/// The JSON entry of an invalid signature, as every command reports them.
+fn error_json(s: &str, e: &ParseError) -> Json {
+    json!({
+        "signature": s,
+        "valid": false,
+        "error": { "offset": e.offset(), "message": e.to_string() },
+    })
+}
+
+fn validate_command(options: &Options) -> bool {
+    let mut results = Vec::with_capacity(options.signatures.len());
+    let mut success = true;
+    for s in &options.signatures {
+        let result = validate(s);
+        success &= result.is_ok();
+
+        if options.json {
+            results.push(match result {
+                Ok(()) => json!({ "signature": s, "valid": true }),
+                Err(e) => error_json(s, &e),
+            });
+        } else {
+            match result {
+                Ok(()) => println!("{}: valid", s),
+                Err(e) => print_error(s, &e),
+            }
+        }
+    }
+
+    if options.json {
+        print_json(&Json::Array(results));
+    }
+
+    success
+}
+
+fn children(signature: &Signature) -> Vec<(PathSegment, &Signature)> {
+    match signature {
+        Signature::Array(child) => vec![(PathSegment::Element, &**child)],
+        Signature::Dict { key, value } => {
+            vec![(PathSegment::Key, &**key), (PathSegment::Value, &**value)]
+        }
+        Signature::Structure(fields) => fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| (PathSegment::Field(i), field))
+            .collect(),
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => vec![(PathSegment::Maybe, &**child)],
+        _ => vec![],
+    }
+}
+
+fn segment_name(segment: PathSegment) -> String {
+    match segment {
+        PathSegment::Field(i) => format!("field {}", i),
+        PathSegment::Element => "element".to_string(),
+        PathSegment::Key => "key".to_string(),
+        PathSegment::Value => "value".to_string(),
+        #[cfg(feature = "gvariant")]
+        PathSegment::Maybe => "child".to_string(),
+        PathSegment::Variant => "variant contents".to_string(),
+    }
+}
+
+fn explain_json(signature: &Signature) -> Json {
+    let children: Vec<_> = children(signature)
+        .into_iter()
+        .map(|(segment, child)| {
+            let mut child = explain_json(child);
+            child["role"] = segment_name(segment).into();
+
+            child
+        })
+        .collect();
+
+    json!({
+        "signature": signature.to_string(),
+        "description": signature.describe(),
+        "rust_type": signature.rust_type(),
+        "children": children,
+    })
+}
+
+fn print_tree(signature: &Signature, prefix: &str) {
+    let children = children(signature);
+    let len = children.len();
+    for (i, (segment, child)) in children.into_iter().enumerate() {
+        let (branch, indent) = if i + 1 == len {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        println!(
+            "{}{}{} {}: {}",
+            prefix,
+            branch,
+            segment_name(segment),
+            child,
+            child.describe()
+        );
+        print_tree(child, &format!("{}{}", prefix, indent));
+    }
+}
+
+fn explain_command(options: &Options) -> bool {
+    let parsed = parse_all(options);
+
+    if options.json {
+        let results: Vec<_> = parsed
+            .iter()
+            .map(|(s, result)| match result {
+                Ok(signature) => explain_json(signature),
+                Err(e) => error_json(s, e),
+            })
+            .collect();
+        print_json(&Json::Array(results));
+    } else {
+        for (s, result) in &parsed {
+            match result {
+                Ok(signature) => {
+                    println!("{}: {}", s, signature.describe());
+                    println!("Rust type: {}", signature.rust_type());
+                    print_tree(signature, "");
+                }
+                Err(e) => print_error(s, e),
+            }
+        }
+    }
+
+    parsed.iter().all(|(_, result)| result.is_ok())
+}
+
+fn layout_command(options: &Options) -> bool {
+    let parsed = parse_all(options);
+
+    if options.json {
+        let results: Vec<_> = parsed
+            .iter()
+            .map(|(s, result)| {
+                let signature = match result {
+                    Ok(signature) => signature,
+                    Err(e) => return error_json(s, e),
+                };
+                let formats: serde_json::Map<_, _> = options
+                    .formats
+                    .iter()
+                    .map(|&format| {
+                        let layout = json!({
+                            "alignment": signature.alignment(format),
+                            "fixed_size": signature.fixed_size(format),
+                        });
+
+                        (format_id(format).to_string(), layout)
+                    })
+                    .collect();
+
+                json!({ "signature": s, "formats": formats })
+            })
+            .collect();
+        print_json(&Json::Array(results));
+    } else {
+        for (s, result) in &parsed {
+            let signature = match result {
+                Ok(signature) => signature,
+                Err(e) => {
+                    print_error(s, e);
+                    continue;
+                }
+            };
+            println!("{}", s);
+            for &format in &options.formats {
+                let size = match signature.fixed_size(format) {
+                    Some(size) => format!("fixed size {}", size),
+                    None => "variable size".to_string(),
+                };
+                println!(
+                    "  {}: alignment {}, {}",
+                    format,
+                    signature.alignment(format),
+                    size
+                );
+            }
+        }
+    }
+
+    parsed.iter().all(|(_, result)| result.is_ok())
+}
+
+fn convert_command(options: &Options) -> bool {
+    let parsed = parse_all(options);
+    let mut results = Vec::new();
+    let mut success = true;
+
+    for (s, result) in &parsed {
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                if options.json {
+                    results.push(error_json(s, e));
+                } else {
+                    print_error(s, e);
+                }
+                success = false;
+                continue;
+            }
+        };
+        for &format in &options.formats {
+            let issues = signature.check_format(format);
+            success &= issues.is_empty();
+
+            if options.json {
+                let issues: Vec<_> = issues
+                    .iter()
+                    .map(|issue| {
+                        json!({ "path": issue.path.to_string(), "message": issue.kind.to_string() })
+                    })
+                    .collect();
+                results.push(json!({
+                    "signature": s,
+                    "format": format_id(format),
+                    "portable": issues.is_empty(),
+                    "issues": issues,
+                }));
+            } else if issues.is_empty() {
+                println!("{}: portable to {}", s, format);
+            } else {
+                println!("{}: not portable to {}", s, format);
+                for issue in issues {
+                    println!("  {}", issue);
+                }
+            }
+        }
+    }
+
+    if options.json {
+        print_json(&Json::Array(results));
+    }
+
+    success
+}
+
+fn generate_command(options: &Options) -> bool {
+    let mut generator = Generator::new();
+    if let Some(path) = &options.crate_path {
+        generator = generator.crate_path(path);
+    }
+
+    let mut errors = Vec::new();
+    if let Some(path) = &options.xml {
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|xml| generator.add_introspection(&xml).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            if !options.json {
+                eprintln!("{}: {}", path, e);
+            }
+            errors.push(json!({ "xml": path, "error": { "message": e } }));
+        }
+    }
+
+    let parsed = parse_all(options);
+    for (i, (s, result)) in parsed.iter().enumerate() {
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                if !options.json {
+                    print_error(s, e);
+                }
+                errors.push(error_json(s, e));
+                continue;
+            }
+        };
+        let name = match (&options.name, parsed.len()) {
+            (Some(name), 1) => name.clone(),
+            (Some(name), _) => format!("{}{}", name, i),
+            (None, _) => format!("Signature{}", i),
+        };
+        generator.add_signature(&name, signature);
+    }
+
+    let code = generator.generate();
+    if options.json {
+        print_json(&json!({ "code": code, "errors": errors }));
+    } else {
+        print!("{}", code);
+    }
+
+    errors.is_empty()
+}
@@ -0,0 +1,486 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::introspection::{self, Arg, Node};
+use crate::signature::Signature;
+
+/// Generates Rust type definitions, with `Type` implementations, from signatures.
+///
+/// Structures become structs with one public field per structure field, nested structures
+/// included. Any other signature becomes a newtype around the type returned by
+/// [`Signature::rust_type`]. The `SIGNATURE` of every generated type is spelled out in full so
+/// that it's always equal to the signature it was generated from.
+///
+/// Dictionaries become `HashMap`s, except those with `d` or `g` keys: `f64` and `Signature` can't
+/// be hashed, so those become `Vec`s of key-value pairs instead.
+///
+/// The generated code imports everything it uses, from `std` and from the crate at
+/// [`Generator::crate_path`].
+#[derive(Debug, Clone)]
+pub struct Generator {
+    crate_path: String,
+    items: String,
+    names: HashSet<String>,
+    /// Whether any item uses `HashMap`.
+    uses_hash_map: bool,
+    /// The types used from the `signature` module.
+    signature_types: BTreeSet<&'static str>,
+    /// The types used from the `value` module.
+    value_types: BTreeSet<&'static str>,
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Self {
+            crate_path: "static_dbus_signature".to_string(),
+            items: String::new(),
+            names: HashSet::new(),
+            uses_hash_map: false,
+            signature_types: BTreeSet::from(["Signature"]),
+            value_types: BTreeSet::new(),
+        }
+    }
+
+    /// Set the path of the crate that provides `Type` and `Signature`.
+    ///
+    /// Defaults to `static_dbus_signature`.
+    pub fn crate_path(mut self, path: &str) -> Self {
+        self.crate_path = path.to_string();
+
+        self
+    }
+
+    /// Generate a type named `name` for `signature`.
+    ///
+    /// Returns the name actually used, which differs from `name` if it was already taken.
+    pub fn add_signature(&mut self, name: &str, signature: &Signature) -> String {
+        match signature {
+            Signature::Structure(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| (format!("field{}", i), field))
+                    .collect();
+
+                self.add_struct(name, &fields, signature)
+            }
+            _ => self.add_newtype(name, signature),
+        }
+    }
+
+    /// Generate the argument and reply types of all methods and the argument types of all signals
+    /// in the given introspection XML.
+    ///
+    /// Method arguments are named `<Method>Args`, method replies `<Method>Reply` and signal
+    /// arguments `<Signal>Args`. Members without any arguments (or reply) are skipped. Arguments
+    /// sharing a name get a numeric suffix, like type names do.
+    pub fn add_introspection(&mut self, xml: &str) -> Result<(), introspection::Error> {
+        let node = Node::from_xml(xml)?;
+        self.add_node(&node);
+
+        Ok(())
+    }
+
+    /// The generated code.
+    pub fn generate(&self) -> String {
+        let mut code = String::new();
+        writeln!(code, "// Generated by static-dbus-signature. Do not edit.").unwrap();
+        writeln!(code).unwrap();
+        if self.uses_hash_map {
+            writeln!(code, "use std::collections::HashMap;").unwrap();
+            writeln!(code).unwrap();
+        }
+        writeln!(code, "use {}::r#type::Type;", self.crate_path).unwrap();
+        write_use(
+            &mut code,
+            &self.crate_path,
+            "signature",
+            &self.signature_types,
+        );
+        write_use(&mut code, &self.crate_path, "value", &self.value_types);
+        code.push_str(&self.items);
+
+        code
+    }
+
+    fn add_node(&mut self, node: &Node) {
+        for interface in &node.interfaces {
+            for method in &interface.methods {
+                let args: Vec<_> = method.in_args().collect();
+                self.add_args(&format!("{}Args", method.name), &args);
+                let args: Vec<_> = method.out_args().collect();
+                self.add_args(&format!("{}Reply", method.name), &args);
+            }
+            for signal in &interface.signals {
+                let args: Vec<_> = signal.args.iter().collect();
+                self.add_args(&format!("{}Args", signal.name), &args);
+            }
+        }
+        for node in &node.nodes {
+            self.add_node(node);
+        }
+    }
+
+    fn add_args(&mut self, name: &str, args: &[&Arg]) {
+        match args {
+            [] => (),
+            [arg] => {
+                self.add_newtype(name, &arg.signature);
+            }
+            _ => {
+                let mut names = HashSet::new();
+                let fields: Vec<_> = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let field = match &arg.name {
+                            Some(name) => snake_case(name),
+                            None => format!("arg{}", i),
+                        };
+                        let field = unique_name(&mut names, &field);
+
+                        (field, &arg.signature)
+                    })
+                    .collect();
+                let signature = Signature::Structure(
+                    args.iter()
+                        .map(|arg| arg.signature.clone())
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+
+                self.add_struct(name, &fields, &signature);
+            }
+        }
+    }
+
+    fn add_struct(
+        &mut self,
+        name: &str,
+        fields: &[(String, &Signature)],
+        signature: &Signature,
+    ) -> String {
+        let name = self.unique_name(name);
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|(field, signature)| {
+                let ty = self.field_type(&name, field, signature);
+
+                (field, ty)
+            })
+            .collect();
+
+        let mut item = String::new();
+        writeln!(item).unwrap();
+        writeln!(item, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+        writeln!(item, "pub struct {} {{", name).unwrap();
+        for (field, ty) in fields {
+            writeln!(item, "    pub {}: {},", field, ty).unwrap();
+        }
+        writeln!(item, "}}").unwrap();
+        self.write_type_impl(&mut item, &name, signature);
+        self.items.push_str(&item);
+
+        name
+    }
+
+    fn add_newtype(&mut self, name: &str, signature: &Signature) -> String {
+        let name = self.unique_name(name);
+        let ty = self.field_type(&name, "0", signature);
+
+        let mut item = String::new();
+        writeln!(item).unwrap();
+        writeln!(item, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+        writeln!(item, "pub struct {}(pub {});", name, ty).unwrap();
+        self.write_type_impl(&mut item, &name, signature);
+        self.items.push_str(&item);
+
+        name
+    }
+
+    fn write_type_impl(&mut self, item: &mut String, name: &str, signature: &Signature) {
+        self.add_signature_types(signature);
+        writeln!(item).unwrap();
+        writeln!(item, "impl Type for {} {{", name).unwrap();
+        writeln!(
+            item,
+            "    const SIGNATURE: &'static Signature = &{};",
+            signature_expr(signature)
+        )
+        .unwrap();
+        writeln!(item, "}}").unwrap();
+    }
+
+    /// The Rust type of a field, generating types for any structures in it.
+    fn field_type(&mut self, parent: &str, field: &str, signature: &Signature) -> String {
+        match signature {
+            Signature::Structure(_) => {
+                let name = format!("{}{}", parent, camel_case(field.trim_start_matches("r#")));
+
+                self.add_signature(&name, signature)
+            }
+            Signature::Array(child) => {
+                let child = self.field_type(parent, &format!("{}_item", field), child);
+
+                format!("Vec<{}>", child)
+            }
+            Signature::Dict { key, value } => {
+                let key_ty = self.field_type(parent, &format!("{}_key", field), key);
+                let value_ty = self.field_type(parent, &format!("{}_value", field), value);
+
+                if matches!(**key, Signature::F64 | Signature::Signature) {
+                    format!("Vec<({}, {})>", key_ty, value_ty)
+                } else {
+                    self.uses_hash_map = true;
+
+                    format!("HashMap<{}, {}>", key_ty, value_ty)
+                }
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => {
+                let child = self.field_type(parent, field, child);
+
+                format!("Option<{}>", child)
+            }
+            _ => {
+                let ty = signature.rust_type();
+                match signature {
+                    Signature::ObjectPath => self.value_types.insert("ObjectPath"),
+                    Signature::Value => self.value_types.insert("Variant"),
+                    #[cfg(unix)]
+                    Signature::Fd => self.value_types.insert("Fd"),
+                    _ => false,
+                };
+
+                ty
+            }
+        }
+    }
+
+    /// Record the types used by the expression of `signature`.
+    fn add_signature_types(&mut self, signature: &Signature) {
+        match signature {
+            Signature::Array(child) => {
+                self.signature_types.insert("ChildSignature");
+                self.add_signature_types(child);
+            }
+            Signature::Dict { key, value } => {
+                self.signature_types.insert("ChildSignature");
+                self.add_signature_types(key);
+                self.add_signature_types(value);
+            }
+            Signature::Structure(fields) => {
+                self.signature_types.insert("FieldsSignatures");
+                for field in fields.iter() {
+                    self.add_signature_types(field);
+                }
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => {
+                self.signature_types.insert("ChildSignature");
+                self.add_signature_types(child);
+            }
+            _ => (),
+        }
+    }
+
+    fn unique_name(&mut self, name: &str) -> String {
+        unique_name(&mut self.names, name)
+    }
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add `name` to `names`, with a numeric suffix if it's already there, and return it.
+fn unique_name(names: &mut HashSet<String>, name: &str) -> String {
+    let mut unique = name.to_string();
+    let mut i = 1;
+    while !names.insert(unique.clone()) {
+        i += 1;
+        // Keywords are raw identifiers but with a suffix they no longer need to be.
+        unique = format!("{}{}", name.trim_start_matches("r#"), i);
+    }
+
+    unique
+}
+
+fn write_use(code: &mut String, crate_path: &str, module: &str, types: &BTreeSet<&str>) {
+    let types: Vec<_> = types.iter().copied().collect();
+    match types[..] {
+        [] => (),
+        [ty] => writeln!(code, "use {}::{}::{};", crate_path, module, ty).unwrap(),
+        _ => writeln!(
+            code,
+            "use {}::{}::{{{}}};",
+            crate_path,
+            module,
+            types.join(", ")
+        )
+        .unwrap(),
+    }
+}
+
+/// A constant expression that evaluates to `signature`.
+fn signature_expr(signature: &Signature) -> String {
+    match signature {
+        Signature::Unit => "Signature::Unit".to_string(),
+        Signature::U8 => "Signature::U8".to_string(),
+        Signature::Bool => "Signature::Bool".to_string(),
+        Signature::I16 => "Signature::I16".to_string(),
+        Signature::U16 => "Signature::U16".to_string(),
+        Signature::I32 => "Signature::I32".to_string(),
+        Signature::U32 => "Signature::U32".to_string(),
+        Signature::I64 => "Signature::I64".to_string(),
+        Signature::U64 => "Signature::U64".to_string(),
+        Signature::F64 => "Signature::F64".to_string(),
+        Signature::Str => "Signature::Str".to_string(),
+        Signature::Signature => "Signature::Signature".to_string(),
+        Signature::ObjectPath => "Signature::ObjectPath".to_string(),
+        Signature::Value => "Signature::Value".to_string(),
+        #[cfg(unix)]
+        Signature::Fd => "Signature::Fd".to_string(),
+        Signature::Array(child) => format!(
+            "Signature::Array(ChildSignature::Static {{ child: &{} }})",
+            signature_expr(child)
+        ),
+        Signature::Dict { key, value } => format!(
+            "Signature::Dict {{ key: ChildSignature::Static {{ child: &{} }}, \
+             value: ChildSignature::Static {{ child: &{} }} }}",
+            signature_expr(key),
+            signature_expr(value)
+        ),
+        Signature::Structure(fields) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|field| format!("&{}", signature_expr(field)))
+                .collect();
+
+            format!(
+                "Signature::Structure(FieldsSignatures::Static {{ fields: &[{}] }})",
+                fields.join(", ")
+            )
+        }
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => format!(
+            "Signature::Maybe(ChildSignature::Static {{ child: &{} }})",
+            signature_expr(child)
+        ),
+    }
+}
+
+fn camel_case(s: &str) -> String {
+    let mut camel = String::with_capacity(s.len());
+    let mut upper = true;
+    for c in s.chars() {
+        if c == '_' || c == '-' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+
+    camel
+}
+
+fn snake_case(s: &str) -> String {
+    let mut snake = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else if c == '-' {
+            snake.push('_');
+        } else {
+            snake.push(c);
+        }
+    }
+
+    if is_keyword(&snake) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    }
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "as" | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "static"
+            | "struct"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "async"
+            | "await"
+            | "dyn"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The generated code itself is compiled and checked in `tests/codegen.rs`.
+
+    #[test]
+    fn imports() {
+        let mut generator = Generator::new().crate_path("crate");
+        generator.add_signature("Names", &"as".parse().unwrap());
+        let code = generator.generate();
+
+        assert!(!code.contains("HashMap"));
+        assert!(code.contains(
+            "use crate::r#type::Type;\nuse crate::signature::{ChildSignature, Signature};\n"
+        ));
+        assert!(!code.contains("use crate::value"));
+
+        generator.add_signature("Key", &"(sv)".parse().unwrap());
+        assert!(generator
+            .generate()
+            .contains("use crate::value::Variant;\n"));
+    }
+
+    #[test]
+    fn invalid_introspection() {
+        let xml = r#"<node><interface name="a"><method name="M"><arg type="a{"/></method></interface></node>"#;
+        assert!(matches!(
+            Generator::new().add_introspection(xml),
+            Err(introspection::Error::InvalidSignature { .. })
+        ));
+    }
+}
@@ -0,0 +1,775 @@
+use std::str;
+
+use super::validate::{complete_types, is_valid_signature, max_depth};
+use super::{validate, Endian, Error, ErrorKind, MAX_DBUS_ARRAY_LEN};
+use crate::dynamic_type::DynamicType;
+use crate::signature::{Format, Signature, MAX_DBUS_ARRAY_DEPTH, MAX_DBUS_STRUCT_DEPTH};
+use crate::value::{is_valid_object_path, Value};
+
+/// Encode `value` in the D-Bus format.
+///
+/// The value is assumed to start at an 8-byte aligned offset, as message bodies do.
+pub fn to_bytes(value: &Value, endian: Endian) -> Result<Vec<u8>, Error> {
+    let mut encoder = Encoder {
+        bytes: Vec::new(),
+        endian,
+    };
+    encoder.value(value)?;
+
+    Ok(encoder.bytes)
+}
+
+/// Decode a value of type `signature` from D-Bus encoded `bytes`.
+///
+/// All of `bytes` must be used by the value.
+pub fn from_bytes(signature: &Signature, bytes: &[u8], endian: Endian) -> Result<Value, Error> {
+    let mut decoder = Decoder {
+        bytes,
+        pos: 0,
+        endian,
+    };
+    let value = decoder.value(signature)?;
+    if decoder.pos != bytes.len() {
+        return Err(Error::new(decoder.pos, ErrorKind::TrailingBytes));
+    }
+
+    Ok(value)
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+    endian: Endian,
+}
+
+impl Encoder {
+    fn pad(&mut self, alignment: usize) {
+        let len = self.bytes.len().next_multiple_of(alignment);
+        self.bytes.resize(len, 0);
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.pad(4);
+        self.bytes.extend(self.endian.u32_bytes(n));
+    }
+
+    fn string(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.bytes.extend(s.as_bytes());
+        self.bytes.push(0);
+    }
+
+    fn signature(&mut self, signature: &Signature) -> Result<(), Error> {
+        let s = signature.to_string();
+        if s.len() > u8::MAX as usize {
+            return Err(Error::new(self.bytes.len(), ErrorKind::InvalidSignature));
+        }
+        self.bytes.push(s.len() as u8);
+        self.bytes.extend(s.as_bytes());
+        self.bytes.push(0);
+
+        Ok(())
+    }
+
+    fn value(&mut self, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::Unit => (),
+            Value::U8(n) => self.bytes.push(*n),
+            Value::Bool(b) => self.u32(*b as u32),
+            Value::I16(n) => {
+                self.pad(2);
+                self.bytes.extend(self.endian.u16_bytes(*n as u16));
+            }
+            Value::U16(n) => {
+                self.pad(2);
+                self.bytes.extend(self.endian.u16_bytes(*n));
+            }
+            Value::I32(n) => self.u32(*n as u32),
+            Value::U32(n) => self.u32(*n),
+            #[cfg(unix)]
+            Value::Fd(n) => self.u32(*n),
+            Value::I64(n) => {
+                self.pad(8);
+                self.bytes.extend(self.endian.u64_bytes(*n as u64));
+            }
+            Value::U64(n) => {
+                self.pad(8);
+                self.bytes.extend(self.endian.u64_bytes(*n));
+            }
+            Value::F64(n) => {
+                self.pad(8);
+                self.bytes.extend(self.endian.u64_bytes(n.to_bits()));
+            }
+            Value::Str(s) => self.string(s),
+            Value::ObjectPath(path) => {
+                if !is_valid_object_path(path) {
+                    return Err(Error::new(self.bytes.len(), ErrorKind::InvalidObjectPath));
+                }
+                self.string(path)
+            }
+            Value::Signature(signature) => self.signature(signature)?,
+            Value::Variant(value) => {
+                self.signature(&value.signature())?;
+                self.value(value)?;
+            }
+            Value::Array { element, items } => {
+                self.array(element.alignment(Format::DBus), items.iter(), |e, item| {
+                    e.value(item)
+                })?
+            }
+            Value::Dict { entries, .. } => self.array(8, entries.iter(), |e, (key, value)| {
+                e.pad(8);
+                e.value(key)?;
+                e.value(value)
+            })?,
+            Value::Structure(fields) => {
+                self.pad(8);
+                for field in fields {
+                    self.value(field)?;
+                }
+            }
+            #[cfg(feature = "gvariant")]
+            Value::Maybe { .. } => {
+                return Err(Error::new(
+                    self.bytes.len(),
+                    ErrorKind::Unsupported("maybe type"),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn array<T>(
+        &mut self,
+        alignment: usize,
+        items: impl Iterator<Item = T>,
+        mut encode: impl FnMut(&mut Self, T) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.u32(0);
+        let len_pos = self.bytes.len() - 4;
+        // The padding after the length isn't part of the array length, even if it's empty.
+        self.pad(alignment);
+        let start = self.bytes.len();
+        for item in items {
+            encode(self, item)?;
+        }
+        let len = self.bytes.len() - start;
+        if len > MAX_DBUS_ARRAY_LEN {
+            return Err(Error::new(len_pos, ErrorKind::ArrayTooLong(len)));
+        }
+        self.bytes[len_pos..len_pos + 4].copy_from_slice(&self.endian.u32_bytes(len as u32));
+
+        Ok(())
+    }
+}
+
+pub(super) struct Decoder<'b> {
+    pub(super) bytes: &'b [u8],
+    pub(super) pos: usize,
+    pub(super) endian: Endian,
+}
+
+impl<'b> Decoder<'b> {
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(self.pos, kind)
+    }
+
+    pub(super) fn pad(&mut self, alignment: usize) -> Result<(), Error> {
+        let end = self.pos.next_multiple_of(alignment);
+        if end > self.bytes.len() {
+            return Err(self.error(ErrorKind::UnexpectedEnd));
+        }
+        while self.pos < end {
+            if self.bytes[self.pos] != 0 {
+                return Err(self.error(ErrorKind::NonZeroPadding));
+            }
+            self.pos += 1;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        self.pad(N)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))?;
+        self.pos += N;
+
+        Ok(bytes.try_into().unwrap())
+    }
+
+    pub(super) fn u32(&mut self) -> Result<u32, Error> {
+        self.take().map(|bytes| self.endian.read_u32(bytes))
+    }
+
+    pub(super) fn u64(&mut self) -> Result<u64, Error> {
+        self.take().map(|bytes| self.endian.read_u64(bytes))
+    }
+
+    /// Read `len` bytes of string data followed by a NUL byte.
+    pub(super) fn str(&mut self, len: usize) -> Result<&'b str, Error> {
+        let start = self.pos;
+        let end = start
+            .checked_add(len)
+            .filter(|&end| end < self.bytes.len())
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))?;
+        let bytes = &self.bytes[start..end];
+        if bytes.contains(&0) || self.bytes[end] != 0 {
+            return Err(self.error(ErrorKind::InvalidNul));
+        }
+        let s = str::from_utf8(bytes).map_err(|_| self.error(ErrorKind::InvalidUtf8))?;
+        self.pos = end + 1;
+
+        Ok(s)
+    }
+
+    pub(super) fn signature_str(&mut self) -> Result<&'b str, Error> {
+        let [len] = self.take()?;
+
+        self.str(len as usize)
+    }
+
+    fn signature(&mut self) -> Result<Signature, Error> {
+        let start = self.pos;
+
+        self.signature_str()?
+            .parse()
+            .map_err(|_| Error::new(start, ErrorKind::InvalidSignature))
+    }
+
+    pub(super) fn object_path(&mut self) -> Result<&'b str, Error> {
+        self.pad(4)?;
+        let start = self.pos;
+        let len = self.u32()?;
+        let path = self.str(len as usize)?;
+        if !is_valid_object_path(path) {
+            return Err(Error::new(start, ErrorKind::InvalidObjectPath));
+        }
+
+        Ok(path)
+    }
+
+    pub(super) fn value(&mut self, signature: &Signature) -> Result<Value, Error> {
+        self.value_at(signature, Depth::default())
+    }
+
+    /// Enter a container, as long as it's not nested deeper than D-Bus allows.
+    fn enter(&self, depth: Depth) -> Result<Depth, Error> {
+        if depth.is_allowed() {
+            Ok(depth)
+        } else {
+            Err(self.error(ErrorKind::TooDeep))
+        }
+    }
+
+    /// Decode a value of `signature`, nested `depth` deep.
+    fn value_at(&mut self, signature: &Signature, depth: Depth) -> Result<Value, Error> {
+        let value = match signature {
+            Signature::Unit => Value::Unit,
+            Signature::U8 => Value::U8(self.take::<1>()?[0]),
+            Signature::Bool => {
+                self.pad(4)?;
+                let start = self.pos;
+                match self.u32()? {
+                    0 => Value::Bool(false),
+                    1 => Value::Bool(true),
+                    b => return Err(Error::new(start, ErrorKind::InvalidBool(b))),
+                }
+            }
+            Signature::I16 => Value::I16(self.endian.read_u16(self.take()?) as i16),
+            Signature::U16 => Value::U16(self.endian.read_u16(self.take()?)),
+            Signature::I32 => Value::I32(self.u32()? as i32),
+            Signature::U32 => Value::U32(self.u32()?),
+            #[cfg(unix)]
+            Signature::Fd => Value::Fd(self.u32()?),
+            Signature::I64 => Value::I64(self.u64()? as i64),
+            Signature::U64 => Value::U64(self.u64()?),
+            Signature::F64 => Value::F64(f64::from_bits(self.u64()?)),
+            Signature::Str => {
+                let len = self.u32()?;
+                Value::Str(self.str(len as usize)?.to_string())
+            }
+            Signature::ObjectPath => Value::ObjectPath(self.object_path()?.to_string()),
+            Signature::Signature => Value::Signature(self.signature()?),
+            Signature::Value => {
+                let start = self.pos;
+                let signature = self.signature_str()?;
+                if !is_single_complete_type(signature) {
+                    return Err(Error::new(start, ErrorKind::InvalidSignature));
+                }
+                let signature = signature.parse().unwrap();
+                let depth = self.enter(depth.variant())?;
+
+                Value::Variant(Box::new(self.value_at(&signature, depth)?))
+            }
+            Signature::Array(element) => {
+                let depth = self.enter(depth.array())?;
+                let mut items = vec![];
+                self.array(element.alignment(Format::DBus), |d| {
+                    items.push(d.value_at(element, depth)?);
+
+                    Ok(())
+                })?;
+
+                Value::Array {
+                    element: (**element).clone(),
+                    items,
+                }
+            }
+            Signature::Dict { key, value } => {
+                let depth = self.enter(depth.array().structure())?;
+                let mut entries = vec![];
+                self.array(8, |d| {
+                    d.pad(8)?;
+                    let k = d.value_at(key, depth)?;
+                    let v = d.value_at(value, depth)?;
+                    entries.push((k, v));
+
+                    Ok(())
+                })?;
+
+                Value::Dict {
+                    key: (**key).clone(),
+                    value: (**value).clone(),
+                    entries,
+                }
+            }
+            Signature::Structure(fields) => {
+                let depth = self.enter(depth.structure())?;
+                self.pad(8)?;
+                let fields = fields
+                    .iter()
+                    .map(|field| self.value_at(field, depth))
+                    .collect::<Result<_, _>>()?;
+
+                Value::Structure(fields)
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(_) => return Err(self.error(ErrorKind::Unsupported("maybe type"))),
+        };
+
+        Ok(value)
+    }
+
+    /// Decode the elements of an array, calling `element` until the array length is used up.
+    fn array(
+        &mut self,
+        alignment: usize,
+        mut element: impl FnMut(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let (len_pos, end) = self.array_start(alignment)?;
+        while self.pos < end {
+            element(self)?;
+        }
+        if self.pos != end {
+            return Err(Error::new(len_pos, ErrorKind::ArrayLengthMismatch));
+        }
+
+        Ok(())
+    }
+
+    /// Read the length of an array and move to its first element.
+    ///
+    /// Returns the offset of the length and the end of the array.
+    pub(super) fn array_start(&mut self, alignment: usize) -> Result<(usize, usize), Error> {
+        self.pad(4)?;
+        let len_pos = self.pos;
+        let len = self.u32()? as usize;
+        if len > MAX_DBUS_ARRAY_LEN {
+            return Err(Error::new(len_pos, ErrorKind::ArrayTooLong(len)));
+        }
+        self.pad(alignment)?;
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err(self.error(ErrorKind::UnexpectedEnd));
+        }
+
+        Ok((len_pos, end))
+    }
+
+    /// Move past a value of `signature` without decoding it.
+    ///
+    /// Arrays are skipped as a whole, using their length.
+    pub(super) fn skip(&mut self, signature: &Signature) -> Result<(), Error> {
+        self.skip_at(signature, Depth::default())
+    }
+
+    fn skip_at(&mut self, signature: &Signature, depth: Depth) -> Result<(), Error> {
+        match signature {
+            Signature::Unit => (),
+            Signature::U8 => {
+                self.take::<1>()?;
+            }
+            Signature::I16 | Signature::U16 => {
+                self.take::<2>()?;
+            }
+            Signature::Bool | Signature::I32 | Signature::U32 => {
+                self.u32()?;
+            }
+            #[cfg(unix)]
+            Signature::Fd => {
+                self.u32()?;
+            }
+            Signature::I64 | Signature::U64 | Signature::F64 => {
+                self.u64()?;
+            }
+            Signature::Str | Signature::ObjectPath => {
+                let len = self.u32()?;
+                self.str(len as usize)?;
+            }
+            Signature::Signature => {
+                self.signature_str()?;
+            }
+            Signature::Value => {
+                let start = self.pos;
+                let signature = self.signature_str()?;
+                if !is_single_complete_type(signature) {
+                    return Err(Error::new(start, ErrorKind::InvalidSignature));
+                }
+                let depth = self.enter(depth.variant())?;
+                self.validate(signature.as_bytes(), u32::MAX, depth.total())?;
+            }
+            Signature::Array(element) => {
+                self.enter(depth.array())?;
+                let (_, end) = self.array_start(element.alignment(Format::DBus))?;
+                self.pos = end;
+            }
+            Signature::Dict { .. } => {
+                self.enter(depth.array().structure())?;
+                let (_, end) = self.array_start(8)?;
+                self.pos = end;
+            }
+            Signature::Structure(fields) => {
+                let depth = self.enter(depth.structure())?;
+                self.pad(8)?;
+                for field in fields.iter() {
+                    self.skip_at(field, depth)?;
+                }
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(_) => return Err(self.error(ErrorKind::Unsupported("maybe type"))),
+        }
+
+        Ok(())
+    }
+
+    /// Walk over a value of the single complete type `signature` without decoding it.
+    fn validate(&mut self, signature: &[u8], fds: u32, depth: usize) -> Result<(), Error> {
+        if depth > max_depth(Format::DBus) {
+            return Err(self.error(ErrorKind::TooDeep));
+        }
+
+        match signature[0] {
+            b'y' => {
+                self.take::<1>()?;
+            }
+            b'b' => {
+                self.pad(4)?;
+                let start = self.pos;
+                match self.u32()? {
+                    0 | 1 => (),
+                    b => return Err(Error::new(start, ErrorKind::InvalidBool(b))),
+                }
+            }
+            b'n' | b'q' => {
+                self.take::<2>()?;
+            }
+            b'i' | b'u' => {
+                self.u32()?;
+            }
+            b'x' | b't' | b'd' => {
+                self.u64()?;
+            }
+            b'h' => {
+                self.pad(4)?;
+                let start = self.pos;
+                let fd = self.u32()?;
+                if fd >= fds {
+                    return Err(Error::new(start, ErrorKind::FdOutOfRange(fd)));
+                }
+            }
+            b's' => {
+                let len = self.u32()?;
+                self.str(len as usize)?;
+            }
+            b'o' => {
+                self.object_path()?;
+            }
+            b'g' => {
+                let start = self.pos;
+                if !is_valid_signature(self.signature_str()?.as_bytes(), Format::DBus) {
+                    return Err(Error::new(start, ErrorKind::InvalidSignature));
+                }
+            }
+            b'v' => {
+                let start = self.pos;
+                let signature = self.signature_str()?;
+                if !is_single_complete_type(signature) {
+                    return Err(Error::new(start, ErrorKind::InvalidSignature));
+                }
+                self.validate(signature.as_bytes(), fds, depth + 1)?;
+            }
+            b'a' if signature[1] == b'{' => {
+                let key = &signature[2..3];
+                let value = &signature[3..signature.len() - 1];
+                self.array(8, |d| {
+                    d.pad(8)?;
+                    d.validate(key, fds, depth + 1)?;
+                    d.validate(value, fds, depth + 1)
+                })?;
+            }
+            b'a' => {
+                let element = &signature[1..];
+                self.array(alignment(element[0]), |d| {
+                    d.validate(element, fds, depth + 1)
+                })?;
+            }
+            b'(' => {
+                self.pad(8)?;
+                for field in complete_types(&signature[1..signature.len() - 1], Format::DBus) {
+                    self.validate(field, fds, depth + 1)?;
+                }
+            }
+            code => unreachable!("invalid type code `{}` in checked signature", code as char),
+        }
+
+        Ok(())
+    }
+}
+
+/// How deeply a value is nested in containers, variants included.
+#[derive(Debug, Clone, Copy, Default)]
+struct Depth {
+    arrays: usize,
+    structs: usize,
+    variants: usize,
+}
+
+impl Depth {
+    fn array(self) -> Depth {
+        Depth {
+            arrays: self.arrays + 1,
+            ..self
+        }
+    }
+
+    fn structure(self) -> Depth {
+        Depth {
+            structs: self.structs + 1,
+            ..self
+        }
+    }
+
+    fn variant(self) -> Depth {
+        Depth {
+            variants: self.variants + 1,
+            ..self
+        }
+    }
+
+    fn total(self) -> usize {
+        self.arrays + self.structs + self.variants
+    }
+
+    /// Whether D-Bus allows nesting this deep.
+    fn is_allowed(self) -> bool {
+        self.arrays <= MAX_DBUS_ARRAY_DEPTH
+            && self.structs <= MAX_DBUS_STRUCT_DEPTH
+            && self.total() <= max_depth(Format::DBus)
+    }
+}
+
+/// The alignment of the type starting with `code`.
+fn alignment(code: u8) -> usize {
+    match code {
+        b'n' | b'q' => 2,
+        b'b' | b'i' | b'u' | b'h' | b's' | b'o' | b'a' => 4,
+        b'x' | b't' | b'd' | b'(' | b'{' => 8,
+        _ => 1,
+    }
+}
+
+/// Whether `signature` is a single complete type, as required for variants.
+pub(crate) fn is_single_complete_type(signature: &str) -> bool {
+    validate::is_single_complete_type(signature.as_bytes(), Format::DBus)
+}
+
+/// Check that `bytes` encode a value of `signature`, see [`super::validate_bytes`].
+pub(super) fn validate(
+    signature: &[u8],
+    bytes: &[u8],
+    endian: Endian,
+    fds: u32,
+) -> Result<(), Error> {
+    let mut decoder = Decoder {
+        bytes,
+        pos: 0,
+        endian,
+    };
+    for signature in complete_types(signature, Format::DBus) {
+        decoder.validate(signature, fds, 0)?;
+    }
+    if decoder.pos != bytes.len() {
+        return Err(Error::new(decoder.pos, ErrorKind::TrailingBytes));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: Value, expected: &[u8]) {
+        let bytes = to_bytes(&value, Endian::Little).unwrap();
+        assert_eq!(bytes, expected);
+        let decoded = from_bytes(&value.signature(), &bytes, Endian::Little).unwrap();
+        assert_eq!(decoded, value);
+
+        let bytes = to_bytes(&value, Endian::Big).unwrap();
+        let decoded = from_bytes(&value.signature(), &bytes, Endian::Big).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn basic() {
+        round_trip(Value::U8(7), &[7]);
+        round_trip(Value::Bool(true), &[1, 0, 0, 0]);
+        round_trip(Value::I16(-2), &[0xfe, 0xff]);
+        round_trip(Value::U64(1), &[1, 0, 0, 0, 0, 0, 0, 0]);
+        round_trip(Value::Str("ab".to_string()), &[2, 0, 0, 0, b'a', b'b', 0]);
+        round_trip(Value::ObjectPath("/".to_string()), &[1, 0, 0, 0, b'/', 0]);
+        round_trip(Value::Signature("as".parse().unwrap()), &[2, b'a', b's', 0]);
+        round_trip(
+            Value::Variant(Box::new(Value::U32(3))),
+            &[1, b'u', 0, 0, 3, 0, 0, 0],
+        );
+    }
+
+    #[test]
+    fn containers() {
+        round_trip(
+            Value::Structure(vec![Value::U8(1), Value::U64(2)]),
+            &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0],
+        );
+        // Empty arrays still pad to their element alignment.
+        round_trip(
+            Value::Array {
+                element: Signature::U64,
+                items: vec![],
+            },
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+        );
+        round_trip(
+            Value::Dict {
+                key: Signature::Str,
+                value: Signature::Value,
+                entries: vec![(
+                    Value::Str("a".to_string()),
+                    Value::Variant(Box::new(Value::U8(9))),
+                )],
+            },
+            &[10, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, b'a', 0, 1, b'y', 0, 9],
+        );
+        round_trip(
+            Value::Array {
+                element: Signature::U16,
+                items: vec![Value::U16(1), Value::U16(2)],
+            },
+            &[4, 0, 0, 0, 1, 0, 2, 0],
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let sig = |s: &str| s.parse::<Signature>().unwrap();
+        let error = |signature: &str, bytes: &[u8]| {
+            from_bytes(&sig(signature), bytes, Endian::Little).unwrap_err()
+        };
+
+        assert_eq!(error("u", &[1, 0]).kind(), &ErrorKind::UnexpectedEnd);
+        let e = error("b", &[2, 0, 0, 0]);
+        assert_eq!((e.offset(), e.kind()), (0, &ErrorKind::InvalidBool(2)));
+        let e = error("(yu)", &[1, 1, 0, 0, 1, 0, 0, 0]);
+        assert_eq!((e.offset(), e.kind()), (1, &ErrorKind::NonZeroPadding));
+        let e = error("s", &[1, 0, 0, 0, b'a', 1]);
+        assert_eq!((e.offset(), e.kind()), (4, &ErrorKind::InvalidNul));
+        let e = error("s", &[1, 0, 0, 0, 0xff, 0]);
+        assert_eq!(e.kind(), &ErrorKind::InvalidUtf8);
+        let e = error("o", &[1, 0, 0, 0, b'a', 0]);
+        assert_eq!((e.offset(), e.kind()), (0, &ErrorKind::InvalidObjectPath));
+        let e = error("v", &[2, b's', b's', 0]);
+        assert_eq!((e.offset(), e.kind()), (0, &ErrorKind::InvalidSignature));
+        let e = error("ay", &[1, 0, 0, 0, 1, 2]);
+        assert_eq!((e.offset(), e.kind()), (5, &ErrorKind::TrailingBytes));
+        let e = error("aq", &[3, 0, 0, 0, 1, 0, 2, 0]);
+        assert_eq!((e.offset(), e.kind()), (0, &ErrorKind::ArrayLengthMismatch));
+
+        #[cfg(feature = "gvariant")]
+        assert!(to_bytes(
+            &Value::Maybe {
+                child: Signature::U8,
+                value: None
+            },
+            Endian::Little
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn too_deep() {
+        // Variants of variants, with a byte at the bottom.
+        let nested = |depth: usize| {
+            let mut bytes = [1, b'v', 0].repeat(depth);
+            bytes.extend([1, b'y', 0, 7]);
+            bytes
+        };
+
+        let mut value = Value::U8(7);
+        for _ in 0..64 {
+            value = Value::Variant(Box::new(value));
+        }
+        let signature = Signature::Value;
+        assert_eq!(
+            from_bytes(&signature, &nested(63), Endian::Little).unwrap(),
+            value
+        );
+        let e = from_bytes(&signature, &nested(64), Endian::Little).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::TooDeep);
+        // Deep enough to overflow the stack without a limit.
+        let e = from_bytes(&signature, &nested(200_000), Endian::Little).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::TooDeep);
+
+        let mut decoder = Decoder {
+            bytes: &nested(200_000),
+            pos: 0,
+            endian: Endian::Little,
+        };
+        assert_eq!(
+            decoder.skip(&signature).unwrap_err().kind(),
+            &ErrorKind::TooDeep
+        );
+
+        // Structures are limited to 32, even though 64 containers are allowed.
+        let nested = |depth: usize| -> Signature {
+            format!("{}y{}", "(".repeat(depth), ")".repeat(depth))
+                .parse()
+                .unwrap()
+        };
+        let e = from_bytes(&nested(33), &[7], Endian::Little).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::TooDeep);
+        assert!(from_bytes(&nested(32), &[7], Endian::Little).is_ok());
+    }
+
+    #[test]
+    fn single_complete_type() {
+        for signature in ["s", "(ss)", "a{sv}", "aas"] {
+            assert!(is_single_complete_type(signature), "{}", signature);
+        }
+        for signature in ["", "ss", "(s)(s)", "a", "z"] {
+            assert!(!is_single_complete_type(signature), "{}", signature);
+        }
+    }
+}
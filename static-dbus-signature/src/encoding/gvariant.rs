@@ -0,0 +1,319 @@
+use std::str;
+
+use super::validate::{complete_types, is_single_complete_type, is_valid_signature, max_depth};
+use super::{Endian, Error, ErrorKind};
+use crate::signature::Format;
+use crate::value::is_valid_object_path;
+
+/// Check that `bytes` encode a value of `signature`, see [`super::validate_bytes`].
+pub(super) fn validate(
+    signature: &[u8],
+    bytes: &[u8],
+    endian: Endian,
+    fds: u32,
+) -> Result<(), Error> {
+    // The unit type is the empty tuple.
+    let signature = match signature {
+        [] => &b"()"[..],
+        signature => signature,
+    };
+
+    Validator { bytes, endian, fds }.value(signature, 0, bytes.len(), 0)
+}
+
+struct Validator<'b> {
+    bytes: &'b [u8],
+    endian: Endian,
+    fds: u32,
+}
+
+impl Validator<'_> {
+    /// Walk over the value of the single complete type `signature` in `bytes[start..end]`.
+    fn value(&self, signature: &[u8], start: usize, end: usize, depth: usize) -> Result<(), Error> {
+        if depth > max_depth(Format::GVariant) {
+            return Err(Error::new(start, ErrorKind::TooDeep));
+        }
+        let data = &self.bytes[start..end];
+        if fixed_size(signature).is_some_and(|size| size != data.len()) {
+            return Err(Error::new(start, ErrorKind::InvalidSize));
+        }
+
+        match signature[0] {
+            b'y' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'd' => (),
+            b'b' => {
+                if data[0] > 1 {
+                    return Err(Error::new(start, ErrorKind::InvalidBool(data[0] as u32)));
+                }
+            }
+            b'h' => {
+                let fd = self.endian.read_u32(data.try_into().unwrap());
+                if fd >= self.fds {
+                    return Err(Error::new(start, ErrorKind::FdOutOfRange(fd)));
+                }
+            }
+            code @ (b's' | b'o' | b'g') => {
+                let s = string(data, start)?;
+                if code == b'o' && !is_valid_object_path(s) {
+                    return Err(Error::new(start, ErrorKind::InvalidObjectPath));
+                }
+                if code == b'g' && !is_valid_signature(s.as_bytes(), Format::GVariant) {
+                    return Err(Error::new(start, ErrorKind::InvalidSignature));
+                }
+            }
+            b'v' => {
+                // The value is followed by a NUL byte and its signature.
+                let nul = data
+                    .iter()
+                    .rposition(|&b| b == 0)
+                    .ok_or_else(|| Error::new(start, ErrorKind::InvalidSignature))?;
+                let child = &data[nul + 1..];
+                if !is_single_complete_type(child, Format::GVariant) {
+                    return Err(Error::new(start + nul + 1, ErrorKind::InvalidSignature));
+                }
+                self.value(child, start, start + nul, depth + 1)?;
+            }
+            b'm' => {
+                let child = &signature[1..];
+                match (data.len(), fixed_size(child)) {
+                    (0, _) => (),
+                    (_, Some(_)) => self.value(child, start, end, depth + 1)?,
+                    // Variable-size children are followed by a NUL byte.
+                    (len, None) => {
+                        if data[len - 1] != 0 {
+                            return Err(Error::new(end - 1, ErrorKind::NonZeroPadding));
+                        }
+                        self.value(child, start, end - 1, depth + 1)?;
+                    }
+                }
+            }
+            b'a' => self.array(&signature[1..], start, end, depth + 1)?,
+            b'(' | b'{' => {
+                let fields = &signature[1..signature.len() - 1];
+                let fixed = fixed_size(signature).is_some();
+                self.tuple(fields, fixed, start, end, depth + 1)?;
+            }
+            code => unreachable!("invalid type code `{}` in checked signature", code as char),
+        }
+
+        Ok(())
+    }
+
+    fn array(&self, element: &[u8], start: usize, end: usize, depth: usize) -> Result<(), Error> {
+        let len = end - start;
+        if let Some(size) = fixed_size(element) {
+            if !len.is_multiple_of(size) {
+                return Err(Error::new(start, ErrorKind::InvalidSize));
+            }
+            for item_start in (start..end).step_by(size) {
+                self.value(element, item_start, item_start + size, depth)?;
+            }
+
+            return Ok(());
+        }
+        if len == 0 {
+            return Ok(());
+        }
+
+        // The end of each element is stored in a table at the end, which starts where the last
+        // element ends.
+        let width = offset_size(len);
+        let last = end - width;
+        let table = start + self.read_offset(last, width);
+        if table > last || !(end - table).is_multiple_of(width) {
+            return Err(Error::new(last, ErrorKind::InvalidFramingOffset));
+        }
+
+        let alignment = alignment(element);
+        let mut pos = start;
+        for offset in (table..end).step_by(width) {
+            let item_start = pos.next_multiple_of(alignment);
+            let item_end = start + self.read_offset(offset, width);
+            if item_start > item_end || item_end > table {
+                return Err(Error::new(offset, ErrorKind::InvalidFramingOffset));
+            }
+            self.padding(pos, item_start)?;
+            self.value(element, item_start, item_end, depth)?;
+            pos = item_end;
+        }
+
+        Ok(())
+    }
+
+    fn tuple(
+        &self,
+        fields: &[u8],
+        fixed: bool,
+        start: usize,
+        end: usize,
+        depth: usize,
+    ) -> Result<(), Error> {
+        // The ends of variable-size fields, except the last one, are stored backwards from the end.
+        let width = offset_size(end - start);
+        let mut table = end;
+        let mut pos = start;
+        let mut fields = complete_types(fields, Format::GVariant).peekable();
+        while let Some(field) = fields.next() {
+            let field_start = pos.next_multiple_of(alignment(field));
+            let field_end = match fixed_size(field) {
+                Some(size) => field_start + size,
+                None if fields.peek().is_none() => table,
+                None => {
+                    if table < start + width {
+                        return Err(Error::new(start, ErrorKind::InvalidSize));
+                    }
+                    table -= width;
+                    start + self.read_offset(table, width)
+                }
+            };
+            if field_start > field_end || field_end > table {
+                return Err(Error::new(pos, ErrorKind::InvalidSize));
+            }
+            self.padding(pos, field_start)?;
+            self.value(field, field_start, field_end, depth)?;
+            pos = field_end;
+        }
+
+        // Fixed-size tuples are padded up to their size, others end with the offset table.
+        if fixed {
+            self.padding(pos, end)
+        } else if pos != table {
+            Err(Error::new(pos, ErrorKind::InvalidSize))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn padding(&self, start: usize, end: usize) -> Result<(), Error> {
+        match self.bytes[start..end].iter().position(|&b| b != 0) {
+            Some(i) => Err(Error::new(start + i, ErrorKind::NonZeroPadding)),
+            None => Ok(()),
+        }
+    }
+
+    /// Read a framing offset, which is always little-endian.
+    fn read_offset(&self, pos: usize, width: usize) -> usize {
+        self.bytes[pos..pos + width]
+            .iter()
+            .rev()
+            .fold(0, |n, &b| n << 8 | b as usize)
+    }
+}
+
+/// The contents of a string, which must be NUL-terminated, without any other NUL.
+fn string(data: &[u8], start: usize) -> Result<&str, Error> {
+    let (last, s) = data
+        .split_last()
+        .ok_or_else(|| Error::new(start, ErrorKind::InvalidSize))?;
+    if *last != 0 || s.contains(&0) {
+        return Err(Error::new(start, ErrorKind::InvalidNul));
+    }
+
+    str::from_utf8(s).map_err(|_| Error::new(start, ErrorKind::InvalidUtf8))
+}
+
+/// The size of framing offsets in a container of `len` bytes.
+fn offset_size(len: usize) -> usize {
+    match len {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    }
+}
+
+// These mirror `Signature::alignment` and `Signature::fixed_size` but work on signature strings,
+// to not allocate for variants.
+
+fn alignment(signature: &[u8]) -> usize {
+    match signature[0] {
+        b'n' | b'q' => 2,
+        b'i' | b'u' | b'h' => 4,
+        b'x' | b't' | b'd' | b'v' => 8,
+        b'a' | b'm' => alignment(&signature[1..]),
+        b'(' | b'{' => complete_types(&signature[1..signature.len() - 1], Format::GVariant)
+            .map(alignment)
+            .max()
+            .unwrap_or(1),
+        _ => 1,
+    }
+}
+
+fn fixed_size(signature: &[u8]) -> Option<usize> {
+    match signature[0] {
+        b'y' | b'b' => Some(1),
+        b'n' | b'q' => Some(2),
+        b'i' | b'u' | b'h' => Some(4),
+        b'x' | b't' | b'd' => Some(8),
+        b'(' | b'{' => {
+            let mut size = 0usize;
+            for field in complete_types(&signature[1..signature.len() - 1], Format::GVariant) {
+                size = size.next_multiple_of(alignment(field)) + fixed_size(field)?;
+            }
+
+            // The unit type takes a byte.
+            Some(size.max(1).next_multiple_of(alignment(signature)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::validate_bytes_with_fds;
+    use super::*;
+    use crate::signature::Signature;
+
+    fn validate(signature: &str, bytes: &[u8]) -> Result<(), (usize, ErrorKind)> {
+        let signature: Signature = signature.parse().unwrap();
+
+        validate_bytes_with_fds(&signature, bytes, Endian::Little, Format::GVariant, 3)
+            .map_err(|e| (e.offset(), e.kind().clone()))
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(validate("yu", &[1, 0, 0, 0, 2, 0, 0, 0]), Ok(()));
+        assert_eq!(validate("as", &[b'a', 0, b'b', b'c', 0, 2, 5]), Ok(()));
+        assert_eq!(validate("as", &[]), Ok(()));
+        assert_eq!(validate("ay", &[1, 2, 3]), Ok(()));
+        assert_eq!(
+            validate("(sv)", &[b'x', 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, b'u', 2]),
+            Ok(())
+        );
+        assert_eq!(validate("ms", &[b'a', 0, 0]), Ok(()));
+        assert_eq!(validate("ms", &[]), Ok(()));
+        assert_eq!(validate("mu", &[5, 0, 0, 0]), Ok(()));
+        assert_eq!(validate("a{sb}", &[b'k', 0, 1, 2, 4]), Ok(()));
+        assert_eq!(validate("", &[0]), Ok(()));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            validate("yu", &[1, 1, 0, 0, 2, 0, 0, 0]),
+            Err((1, ErrorKind::NonZeroPadding))
+        );
+        assert_eq!(validate("u", &[1, 0, 0]), Err((0, ErrorKind::InvalidSize)));
+        assert_eq!(validate("b", &[2]), Err((0, ErrorKind::InvalidBool(2))));
+        assert_eq!(validate("s", b"a"), Err((0, ErrorKind::InvalidNul)));
+        assert_eq!(
+            validate("o", &[b'a', 0]),
+            Err((0, ErrorKind::InvalidObjectPath))
+        );
+        assert_eq!(
+            validate("as", &[b'a', 0, 9]),
+            Err((2, ErrorKind::InvalidFramingOffset))
+        );
+        assert_eq!(validate("mu", &[5, 0]), Err((0, ErrorKind::InvalidSize)));
+        assert_eq!(
+            validate("v", &[1, 0, b'z']),
+            Err((2, ErrorKind::InvalidSignature))
+        );
+        assert_eq!(validate("", &[1]), Err((0, ErrorKind::NonZeroPadding)));
+        #[cfg(unix)]
+        assert_eq!(
+            validate("h", &[3, 0, 0, 0]),
+            Err((0, ErrorKind::FdOutOfRange(3)))
+        );
+    }
+}
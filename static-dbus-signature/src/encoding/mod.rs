@@ -0,0 +1,161 @@
+pub mod dbus;
+#[cfg(feature = "gvariant")]
+mod gvariant;
+mod validate;
+mod view;
+
+pub use validate::{validate_bytes, validate_bytes_with_fds};
+pub use view::{BodyView, Elements, Entries};
+
+use std::fmt::{self, Display, Formatter};
+
+/// The byte order of encoded data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Endian = Endian::Little;
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Endian = Endian::Big;
+
+    pub(crate) fn u16_bytes(self, n: u16) -> [u8; 2] {
+        match self {
+            Endian::Little => n.to_le_bytes(),
+            Endian::Big => n.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u32_bytes(self, n: u32) -> [u8; 4] {
+        match self {
+            Endian::Little => n.to_le_bytes(),
+            Endian::Big => n.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u64_bytes(self, n: u64) -> [u8; 8] {
+        match self {
+            Endian::Little => n.to_le_bytes(),
+            Endian::Big => n.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn read_u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn read_u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn read_u64(self, bytes: [u8; 8]) -> u64 {
+        match self {
+            Endian::Little => u64::from_le_bytes(bytes),
+            Endian::Big => u64::from_be_bytes(bytes),
+        }
+    }
+}
+
+/// The maximum length (in bytes) of an array in the D-Bus format.
+pub const MAX_DBUS_ARRAY_LEN: usize = 1 << 26;
+
+/// Error returned when encoding or decoding values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    offset: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The data ended before the value did.
+    UnexpectedEnd,
+    /// There are bytes left after the value.
+    TrailingBytes,
+    /// Padding bytes must be zero.
+    NonZeroPadding,
+    /// Booleans must be 0 or 1.
+    InvalidBool(u32),
+    /// Strings must be valid UTF-8.
+    InvalidUtf8,
+    /// Strings must be followed by a NUL byte and not contain any.
+    InvalidNul,
+    InvalidSignature,
+    InvalidObjectPath,
+    /// An array is longer than the format allows.
+    ArrayTooLong(usize),
+    /// The elements of an array didn't end where its length says they do.
+    ArrayLengthMismatch,
+    /// A file descriptor index is out of range.
+    FdOutOfRange(u32),
+    /// Containers are nested deeper than the format allows.
+    TooDeep,
+    /// The size of a GVariant value doesn't match its type.
+    InvalidSize,
+    /// A GVariant framing offset points outside of its container.
+    InvalidFramingOffset,
+    /// The value can't be represented in the format.
+    Unsupported(&'static str),
+    /// A [`BodyView`] was read as a different type than its signature.
+    WrongType {
+        expected: &'static str,
+    },
+    /// A structure has no field at this index.
+    NoSuchField(usize),
+}
+
+impl Error {
+    pub(crate) fn new(offset: usize, kind: ErrorKind) -> Self {
+        Self { offset, kind }
+    }
+
+    /// The byte offset at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of data"),
+            ErrorKind::TrailingBytes => write!(f, "trailing bytes after value"),
+            ErrorKind::NonZeroPadding => write!(f, "non-zero padding byte"),
+            ErrorKind::InvalidBool(b) => write!(f, "invalid boolean value {}", b),
+            ErrorKind::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            ErrorKind::InvalidNul => write!(f, "string is not NUL-terminated or contains NUL"),
+            ErrorKind::InvalidSignature => write!(f, "invalid signature"),
+            ErrorKind::InvalidObjectPath => write!(f, "invalid object path"),
+            ErrorKind::ArrayTooLong(len) => write!(f, "array of {} bytes is too long", len),
+            ErrorKind::ArrayLengthMismatch => write!(f, "array elements don't match its length"),
+            ErrorKind::FdOutOfRange(i) => write!(f, "file descriptor index {} out of range", i),
+            ErrorKind::TooDeep => write!(f, "containers nested too deep"),
+            ErrorKind::InvalidSize => write!(f, "value size doesn't match its type"),
+            ErrorKind::InvalidFramingOffset => write!(f, "framing offset out of range"),
+            ErrorKind::Unsupported(what) => write!(f, "{} is not supported", what),
+            ErrorKind::WrongType { expected } => write!(f, "value is not {}", expected),
+            ErrorKind::NoSuchField(i) => write!(f, "structure has no field {}", i),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "offset {}: {}", self.offset, self.kind)
+    }
+}
+
+impl std::error::Error for Error {}
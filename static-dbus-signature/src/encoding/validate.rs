@@ -0,0 +1,280 @@
+use std::fmt::{self, Write};
+
+use super::{dbus, Endian, Error, ErrorKind};
+use crate::signature::{Format, Signature, MAX_DBUS_LEN};
+
+/// Check that `bytes` are a valid encoding of a value of type `signature`, without decoding it.
+///
+/// The bytes are walked following the signature, checking alignment and padding, strings, array
+/// lengths, booleans and the signatures of nested variants. Nothing is allocated, so this is
+/// cheap enough to run on every message passing through.
+///
+/// File descriptor indices are only range-checked by [`validate_bytes_with_fds`]. Signatures
+/// longer than 255 bytes are rejected, in either format.
+pub fn validate_bytes(
+    signature: &Signature,
+    bytes: &[u8],
+    endian: Endian,
+    format: Format,
+) -> Result<(), Error> {
+    validate_bytes_with_fds(signature, bytes, endian, format, u32::MAX)
+}
+
+/// Like [`validate_bytes`] but also checks that file descriptor indices are below `fds`, the
+/// number of file descriptors passed along with the data.
+pub fn validate_bytes_with_fds(
+    signature: &Signature,
+    bytes: &[u8],
+    endian: Endian,
+    format: Format,
+    fds: u32,
+) -> Result<(), Error> {
+    let mut buf = SignatureBuf {
+        bytes: [0; MAX_DBUS_LEN],
+        len: 0,
+    };
+    write!(buf, "{}", signature).map_err(|_| Error::new(0, ErrorKind::InvalidSignature))?;
+    let signature = &buf.bytes[..buf.len];
+    if !is_valid_signature(signature, format) {
+        return Err(Error::new(0, ErrorKind::InvalidSignature));
+    }
+
+    match format {
+        Format::DBus => dbus::validate(signature, bytes, endian, fds),
+        #[cfg(feature = "gvariant")]
+        Format::GVariant => super::gvariant::validate(signature, bytes, endian, fds),
+    }
+}
+
+/// The deepest containers may be nested, including through variants.
+pub(super) fn max_depth(format: Format) -> usize {
+    match format {
+        Format::DBus => 64,
+        #[cfg(feature = "gvariant")]
+        Format::GVariant => 128,
+    }
+}
+
+/// The length of the single complete type at the start of `signature`, or `None` if there's no
+/// valid one.
+pub(super) fn complete_type_len(signature: &[u8], format: Format, depth: usize) -> Option<usize> {
+    if depth > max_depth(format) {
+        return None;
+    }
+
+    match *signature.first()? {
+        code if is_basic(code) || code == b'v' => Some(1),
+        b'a' if signature.get(1) == Some(&b'{') => {
+            if !is_basic(*signature.get(2)?) {
+                return None;
+            }
+            let value = complete_type_len(signature.get(3..)?, format, depth + 1)?;
+
+            (signature.get(3 + value) == Some(&b'}')).then_some(4 + value)
+        }
+        b'a' => Some(1 + complete_type_len(&signature[1..], format, depth + 1)?),
+        #[cfg(feature = "gvariant")]
+        b'm' if format == Format::GVariant => {
+            Some(1 + complete_type_len(&signature[1..], format, depth + 1)?)
+        }
+        b'(' => {
+            let mut len = 1;
+            while *signature.get(len)? != b')' {
+                len += complete_type_len(&signature[len..], format, depth + 1)?;
+            }
+            // Only GVariant has a unit type.
+            if len == 1 && format == Format::DBus {
+                return None;
+            }
+
+            Some(len + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `signature` is a single complete type, as variants require.
+pub(super) fn is_single_complete_type(signature: &[u8], format: Format) -> bool {
+    complete_type_len(signature, format, 0) == Some(signature.len())
+}
+
+/// Whether `signature` is a valid (possibly empty) sequence of complete types.
+pub(super) fn is_valid_signature(mut signature: &[u8], format: Format) -> bool {
+    while !signature.is_empty() {
+        match complete_type_len(signature, format, 0) {
+            Some(len) => signature = &signature[len..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// The complete types in `signature`, which must be valid.
+pub(super) fn complete_types(mut signature: &[u8], format: Format) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        let len = complete_type_len(signature, format, 0)?;
+        let (first, rest) = signature.split_at(len);
+        signature = rest;
+
+        Some(first)
+    })
+}
+
+fn is_basic(code: u8) -> bool {
+    match code {
+        b'y' | b'b' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'd' | b's' | b'o' | b'g' => true,
+        #[cfg(unix)]
+        b'h' => true,
+        _ => false,
+    }
+}
+
+/// A stack buffer for formatting signatures into, to avoid allocating.
+struct SignatureBuf {
+    bytes: [u8; MAX_DBUS_LEN],
+    len: usize,
+}
+
+impl Write for SignatureBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic_type::DynamicType;
+    use crate::value::Value;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn signatures() {
+        let valid = |s: &str| is_single_complete_type(s.as_bytes(), Format::DBus);
+        assert!(valid("a{sa(iv)}"));
+        assert!(!valid("a{vs}"));
+        assert!(!valid("()"));
+        assert!(!valid("a"));
+        assert!(!valid("ss"));
+        assert!(!valid(
+            &"a".repeat(70).chars().chain(['y']).collect::<String>()
+        ));
+        assert!(is_valid_signature(b"sa{sv}", Format::DBus));
+        assert!(is_valid_signature(b"", Format::DBus));
+        assert!(!is_valid_signature(b"s)", Format::DBus));
+    }
+
+    #[test]
+    fn dbus() {
+        let validate = |signature: &str, bytes: &[u8]| {
+            validate_bytes(&sig(signature), bytes, Endian::Little, Format::DBus)
+                .map_err(|e| (e.offset(), e.kind().clone()))
+        };
+
+        let value = Value::Structure(vec![
+            Value::Str("a".to_string()),
+            Value::Dict {
+                key: Signature::Str,
+                value: Signature::Value,
+                entries: vec![(
+                    Value::Str("k".to_string()),
+                    Value::Variant(Box::new(Value::Array {
+                        element: Signature::U64,
+                        items: vec![Value::U64(1)],
+                    })),
+                )],
+            },
+        ]);
+        let bytes = dbus::to_bytes(&value, Endian::Little).unwrap();
+        assert_eq!(validate("sa{sv}", &bytes), Ok(()));
+        assert_eq!(
+            validate("sa{sv}", &bytes[..bytes.len() - 1]).unwrap_err().1,
+            ErrorKind::UnexpectedEnd
+        );
+
+        assert_eq!(
+            validate("yu", &[1, 0, 1, 0, 2, 0, 0, 0]),
+            Err((2, ErrorKind::NonZeroPadding))
+        );
+        assert_eq!(
+            validate("b", &[2, 0, 0, 0]),
+            Err((0, ErrorKind::InvalidBool(2)))
+        );
+        assert_eq!(
+            validate("s", &[1, 0, 0, 0, b'a', 1]),
+            Err((4, ErrorKind::InvalidNul))
+        );
+        assert_eq!(
+            validate("s", &[1, 0, 0, 0, 0xc3, 0]),
+            Err((4, ErrorKind::InvalidUtf8))
+        );
+        assert_eq!(
+            validate("o", &[2, 0, 0, 0, b'/', b'/', 0]),
+            Err((0, ErrorKind::InvalidObjectPath))
+        );
+        assert_eq!(
+            validate("g", &[2, b'a', b'{', 0]),
+            Err((0, ErrorKind::InvalidSignature))
+        );
+        assert_eq!(
+            validate("v", &[2, b'u', b'u', 0]),
+            Err((0, ErrorKind::InvalidSignature))
+        );
+        assert_eq!(
+            validate("au", &[3, 0, 0, 0, 1, 0, 0, 0]),
+            Err((0, ErrorKind::ArrayLengthMismatch))
+        );
+        assert_eq!(validate("y", &[1, 2]), Err((1, ErrorKind::TrailingBytes)));
+
+        // A variant nested in itself until the stack would run out.
+        let mut deep = vec![];
+        for _ in 0..100 {
+            deep.extend([1, b'v', 0]);
+        }
+        assert_eq!(validate("v", &deep).unwrap_err().1, ErrorKind::TooDeep);
+
+        #[cfg(unix)]
+        {
+            let bytes = [3, 0, 0, 0];
+            let result =
+                validate_bytes_with_fds(&sig("h"), &bytes, Endian::Little, Format::DBus, 3);
+            assert_eq!(result.unwrap_err().kind(), &ErrorKind::FdOutOfRange(3));
+            assert!(
+                validate_bytes_with_fds(&sig("h"), &bytes, Endian::Little, Format::DBus, 4).is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn dbus_matches_decoding() {
+        let value = Value::Array {
+            element: sig("(qs)"),
+            items: vec![
+                Value::Structure(vec![Value::U16(1), Value::Str("x".to_string())]),
+                Value::Structure(vec![Value::U16(2), Value::Str("yz".to_string())]),
+            ],
+        };
+        for endian in [Endian::Little, Endian::Big] {
+            let bytes = dbus::to_bytes(&value, endian).unwrap();
+            assert!(validate_bytes(&value.signature(), &bytes, endian, Format::DBus).is_ok());
+            // Every truncation fails, just as decoding does.
+            for len in 0..bytes.len() {
+                let decoded = dbus::from_bytes(&value.signature(), &bytes[..len], endian);
+                let validated =
+                    validate_bytes(&value.signature(), &bytes[..len], endian, Format::DBus);
+                assert_eq!(validated.err(), decoded.err());
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use super::dbus::{is_single_complete_type, Decoder};
+use super::{Endian, Error, ErrorKind};
+use crate::signature::{Format, Signature};
+use crate::value::Value;
+
+/// A lazily decoded view of a D-Bus encoded value.
+///
+/// Navigating into containers only skips over the values before the one asked for, using array
+/// lengths to jump over whole arrays, and nothing is decoded until a leaf is read. This makes
+/// reading a single entry of a large `a{sv}` cheap.
+///
+/// Offsets are relative to the start of `bytes`, which must be 8-byte aligned like message bodies.
+#[derive(Debug, Clone)]
+pub struct BodyView<'b> {
+    signature: Signature,
+    bytes: &'b [u8],
+    offset: usize,
+    endian: Endian,
+}
+
+impl<'b> BodyView<'b> {
+    /// A view of the value of type `signature` at the start of `bytes`.
+    pub fn new(signature: Signature, bytes: &'b [u8], endian: Endian) -> Self {
+        Self {
+            signature,
+            bytes,
+            offset: 0,
+            endian,
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The offset of the value in the bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The field at `index` of a structure.
+    pub fn field(&self, index: usize) -> Result<BodyView<'b>, Error> {
+        let fields = match &self.signature {
+            Signature::Structure(fields) => fields,
+            _ => return Err(self.wrong_type("a structure")),
+        };
+        let mut decoder = self.decoder();
+        decoder.pad(8)?;
+        for (i, field) in fields.iter().enumerate() {
+            if i == index {
+                return Ok(self.child(field.clone(), decoder.pos));
+            }
+            decoder.skip(field)?;
+        }
+
+        Err(self.error(ErrorKind::NoSuchField(index)))
+    }
+
+    /// The elements of an array.
+    pub fn elements(&self) -> Result<Elements<'b>, Error> {
+        let element = match &self.signature {
+            Signature::Array(element) => (**element).clone(),
+            _ => return Err(self.wrong_type("an array")),
+        };
+        let mut decoder = self.decoder();
+        let alignment = element.alignment(Format::DBus);
+        let (len_pos, end) = decoder.array_start(alignment)?;
+
+        Ok(Elements {
+            view: self.child(element, decoder.pos),
+            alignment,
+            len_pos,
+            end,
+        })
+    }
+
+    /// The entries of a dictionary.
+    pub fn entries(&self) -> Result<Entries<'b>, Error> {
+        let (key, value) = match &self.signature {
+            Signature::Dict { key, value } => ((**key).clone(), (**value).clone()),
+            _ => return Err(self.wrong_type("a dictionary")),
+        };
+        let mut decoder = self.decoder();
+        let (len_pos, end) = decoder.array_start(8)?;
+
+        Ok(Entries {
+            key: self.child(key, decoder.pos),
+            value,
+            len_pos,
+            end,
+        })
+    }
+
+    /// The value of the first entry of a dictionary with the given key.
+    pub fn get(&self, key: &Value) -> Result<Option<BodyView<'b>>, Error> {
+        self.find(|k| Ok(k.decode()? == *key))
+    }
+
+    /// The value of the first entry of a dictionary with the given string, object path or
+    /// signature key.
+    pub fn get_str(&self, key: &str) -> Result<Option<BodyView<'b>>, Error> {
+        self.find(|k| Ok(k.as_str()? == key))
+    }
+
+    fn find(
+        &self,
+        mut matches: impl FnMut(&BodyView<'b>) -> Result<bool, Error>,
+    ) -> Result<Option<BodyView<'b>>, Error> {
+        for entry in self.entries()? {
+            let (key, value) = entry?;
+            if matches(&key)? {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The value inside a variant.
+    pub fn variant(&self) -> Result<BodyView<'b>, Error> {
+        if self.signature != Signature::Value {
+            return Err(self.wrong_type("a variant"));
+        }
+        let mut decoder = self.decoder();
+        let signature = decoder.signature_str()?;
+        if !is_single_complete_type(signature) {
+            return Err(self.error(ErrorKind::InvalidSignature));
+        }
+
+        Ok(self.child(signature.parse().unwrap(), decoder.pos))
+    }
+
+    /// Decode the whole value.
+    pub fn decode(&self) -> Result<Value, Error> {
+        self.decoder().value(&self.signature)
+    }
+
+    /// The contents of a string, object path or signature, without copying.
+    pub fn as_str(&self) -> Result<&'b str, Error> {
+        let mut decoder = self.decoder();
+        match self.signature {
+            Signature::Str => {
+                let len = decoder.u32()?;
+                decoder.str(len as usize)
+            }
+            Signature::ObjectPath => decoder.object_path(),
+            Signature::Signature => decoder.signature_str(),
+            _ => Err(self.wrong_type("a string")),
+        }
+    }
+
+    pub fn as_u8(&self) -> Result<u8, Error> {
+        self.leaf(Signature::U8, "a byte", |d| d.take().map(|[b]| b))
+    }
+
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        self.leaf(Signature::Bool, "a boolean", |d| match d.u32()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(Error::new(d.pos - 4, ErrorKind::InvalidBool(b))),
+        })
+    }
+
+    pub fn as_i16(&self) -> Result<i16, Error> {
+        self.leaf(Signature::I16, "an int16", |d| {
+            d.take().map(|b| d.endian.read_u16(b) as i16)
+        })
+    }
+
+    pub fn as_u16(&self) -> Result<u16, Error> {
+        self.leaf(Signature::U16, "a uint16", |d| {
+            d.take().map(|b| d.endian.read_u16(b))
+        })
+    }
+
+    pub fn as_i32(&self) -> Result<i32, Error> {
+        self.leaf(Signature::I32, "an int32", |d| d.u32().map(|n| n as i32))
+    }
+
+    pub fn as_u32(&self) -> Result<u32, Error> {
+        self.leaf(Signature::U32, "a uint32", Decoder::u32)
+    }
+
+    pub fn as_i64(&self) -> Result<i64, Error> {
+        self.leaf(Signature::I64, "an int64", |d| d.u64().map(|n| n as i64))
+    }
+
+    pub fn as_u64(&self) -> Result<u64, Error> {
+        self.leaf(Signature::U64, "a uint64", Decoder::u64)
+    }
+
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        self.leaf(Signature::F64, "a double", |d| d.u64().map(f64::from_bits))
+    }
+
+    fn leaf<T>(
+        &self,
+        signature: Signature,
+        expected: &'static str,
+        read: impl FnOnce(&mut Decoder<'b>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.signature != signature {
+            return Err(self.wrong_type(expected));
+        }
+
+        read(&mut self.decoder())
+    }
+
+    fn decoder(&self) -> Decoder<'b> {
+        Decoder {
+            bytes: self.bytes,
+            pos: self.offset,
+            endian: self.endian,
+        }
+    }
+
+    fn child(&self, signature: Signature, offset: usize) -> BodyView<'b> {
+        BodyView {
+            signature,
+            bytes: self.bytes,
+            offset,
+            endian: self.endian,
+        }
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(self.offset, kind)
+    }
+
+    fn wrong_type(&self, expected: &'static str) -> Error {
+        self.error(ErrorKind::WrongType { expected })
+    }
+}
+
+/// Iterator over the elements of an array, see [`BodyView::elements`].
+#[derive(Debug, Clone)]
+pub struct Elements<'b> {
+    /// The view of the next element, before its alignment padding.
+    view: BodyView<'b>,
+    alignment: usize,
+    len_pos: usize,
+    end: usize,
+}
+
+impl<'b> Iterator for Elements<'b> {
+    type Item = Result<BodyView<'b>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.view.offset >= self.end {
+            return None;
+        }
+
+        let mut decoder = self.view.decoder();
+        let element = decoder.pad(self.alignment).and_then(|_| {
+            let start = decoder.pos;
+            decoder.skip(&self.view.signature)?;
+            if decoder.pos > self.end {
+                return Err(Error::new(self.len_pos, ErrorKind::ArrayLengthMismatch));
+            }
+
+            Ok(self.view.child(self.view.signature.clone(), start))
+        });
+        // Stop after an error, as the following elements can't be found.
+        self.view.offset = match element {
+            Ok(_) => decoder.pos,
+            Err(_) => self.end,
+        };
+
+        Some(element)
+    }
+}
+
+/// Iterator over the `(key, value)` entries of a dictionary, see [`BodyView::entries`].
+#[derive(Debug, Clone)]
+pub struct Entries<'b> {
+    /// The view of the next key, before the entry's alignment padding.
+    key: BodyView<'b>,
+    value: Signature,
+    len_pos: usize,
+    end: usize,
+}
+
+impl<'b> Iterator for Entries<'b> {
+    type Item = Result<(BodyView<'b>, BodyView<'b>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.key.offset >= self.end {
+            return None;
+        }
+
+        let mut decoder = self.key.decoder();
+        let entry = decoder.pad(8).and_then(|_| {
+            let key = self.key.child(self.key.signature.clone(), decoder.pos);
+            decoder.skip(&key.signature)?;
+            let value = self.key.child(self.value.clone(), decoder.pos);
+            decoder.skip(&value.signature)?;
+            if decoder.pos > self.end {
+                return Err(Error::new(self.len_pos, ErrorKind::ArrayLengthMismatch));
+            }
+
+            Ok((key, value))
+        });
+        self.key.offset = match entry {
+            Ok(_) => decoder.pos,
+            Err(_) => self.end,
+        };
+
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::dbus;
+
+    fn properties() -> Value {
+        let entry =
+            |key: &str, value| (Value::Str(key.to_string()), Value::Variant(Box::new(value)));
+
+        Value::Structure(vec![
+            Value::Str("org.example.Iface".to_string()),
+            Value::Dict {
+                key: Signature::Str,
+                value: Signature::Value,
+                entries: vec![
+                    entry(
+                        "Big",
+                        Value::Array {
+                            element: Signature::U64,
+                            items: (0..1000).map(Value::U64).collect(),
+                        },
+                    ),
+                    entry("Name", Value::Str("foo".to_string())),
+                    entry("Count", Value::U16(7)),
+                ],
+            },
+            Value::Array {
+                element: "(yb)".parse().unwrap(),
+                items: vec![
+                    Value::Structure(vec![Value::U8(1), Value::Bool(true)]),
+                    Value::Structure(vec![Value::U8(2), Value::Bool(false)]),
+                ],
+            },
+        ])
+    }
+
+    #[test]
+    fn navigate() {
+        let value = properties();
+        let signature: Signature = "sa{sv}a(yb)".parse().unwrap();
+        let bytes = dbus::to_bytes(&value, Endian::Big).unwrap();
+        let view = BodyView::new(signature, &bytes, Endian::Big);
+
+        assert_eq!(
+            view.field(0).unwrap().as_str().unwrap(),
+            "org.example.Iface"
+        );
+        let properties = view.field(1).unwrap();
+        let name = properties
+            .get_str("Name")
+            .unwrap()
+            .unwrap()
+            .variant()
+            .unwrap();
+        assert_eq!(name.as_str().unwrap(), "foo");
+        let count = properties
+            .get(&Value::Str("Count".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(count.variant().unwrap().as_u16().unwrap(), 7);
+        assert!(properties.get_str("Missing").unwrap().is_none());
+
+        let big = properties
+            .get_str("Big")
+            .unwrap()
+            .unwrap()
+            .variant()
+            .unwrap();
+        let third = big.elements().unwrap().nth(2).unwrap().unwrap();
+        assert_eq!(third.as_u64().unwrap(), 2);
+        assert_eq!(big.elements().unwrap().count(), 1000);
+
+        let pairs: Vec<_> = view
+            .field(2)
+            .unwrap()
+            .elements()
+            .unwrap()
+            .map(|pair| {
+                let pair = pair.unwrap();
+                (
+                    pair.field(0).unwrap().as_u8().unwrap(),
+                    pair.field(1).unwrap().as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(pairs, [(1, true), (2, false)]);
+
+        assert_eq!(view.decode().unwrap(), value);
+        assert_eq!(
+            view.field(1).unwrap().decode().unwrap(),
+            match &value {
+                Value::Structure(fields) => fields[1].clone(),
+                _ => unreachable!(),
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = dbus::to_bytes(&properties(), Endian::Little).unwrap();
+        let view = BodyView::new("sa{sv}a(yb)".parse().unwrap(), &bytes, Endian::Little);
+
+        let e = view.field(3).unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::NoSuchField(3));
+        let e = view.field(0).unwrap().as_u32().unwrap_err();
+        assert_eq!(
+            e.kind(),
+            &ErrorKind::WrongType {
+                expected: "a uint32"
+            }
+        );
+        assert!(view.elements().is_err());
+
+        // A truncated body only fails once the missing part is touched.
+        let truncated = &bytes[..bytes.len() - 4];
+        let view = BodyView::new("sa{sv}a(yb)".parse().unwrap(), truncated, Endian::Little);
+        assert!(view.field(1).unwrap().get_str("Count").unwrap().is_some());
+        let e = view.field(2).unwrap().elements().unwrap_err();
+        assert_eq!(e.kind(), &ErrorKind::UnexpectedEnd);
+    }
+}
@@ -0,0 +1,846 @@
+//! The GVariant text format, as printed by `gdbus`, `gsettings` and `dconf`.
+//!
+//! e.g. `{'a': <int32 1>, 'b': <@as []>}`.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::dynamic_type::DynamicType;
+use crate::signature::{FieldsSignatures, Signature};
+use crate::value::{is_valid_object_path, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    UnknownKeyword(String),
+    InvalidNumber,
+    NumberOutOfRange,
+    InvalidEscape,
+    InvalidSignature,
+    InvalidObjectPath,
+    /// The value doesn't fit the expected type.
+    TypeMismatch {
+        expected: Signature,
+    },
+    /// A tuple has the wrong number of fields.
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+    /// The type of the value can't be inferred and needs an annotation, e.g. `@as []`.
+    CannotInfer,
+}
+
+/// An error parsing GVariant text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    offset: usize,
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// The byte offset in the text where the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of text"),
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected `{}`", c),
+            ErrorKind::UnknownKeyword(keyword) => write!(f, "unknown keyword `{}`", keyword),
+            ErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ErrorKind::NumberOutOfRange => write!(f, "number out of range"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            ErrorKind::InvalidSignature => write!(f, "invalid signature"),
+            ErrorKind::InvalidObjectPath => write!(f, "invalid object path"),
+            ErrorKind::TypeMismatch { expected } => {
+                write!(f, "expected a value of type `{}`", expected)
+            }
+            ErrorKind::WrongLength { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            ErrorKind::CannotInfer => write!(f, "cannot infer the type, add a type annotation"),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Parse GVariant `text` into a [`Value`].
+///
+/// Without an `expected` type, the type is inferred from the text: integers are `int32`, other
+/// numbers `double` and containers take the type of their first inferable child. Annotations
+/// such as `uint64 7` or `@as []` override the inference.
+pub fn parse(text: &str, expected: Option<&Signature>) -> Result<Value, Error> {
+    let mut parser = Parser { text, pos: 0 };
+    let node = parser.node()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(ErrorKind::UnexpectedChar(c)));
+    }
+
+    match expected {
+        Some(signature) => node.convert(signature),
+        None => node.convert(&node.infer()?),
+    }
+}
+
+/// Print `value` in the GVariant text format.
+///
+/// The output is annotated wherever the type couldn't be inferred otherwise, so that parsing it
+/// without an expected type gives back `value`.
+pub fn print(value: &Value) -> String {
+    let mut text = String::new();
+    write_annotated(&mut text, value);
+
+    text
+}
+
+/// The type keywords and the types they annotate.
+const KEYWORDS: &[(&str, Signature)] = &[
+    ("boolean", Signature::Bool),
+    ("byte", Signature::U8),
+    ("int16", Signature::I16),
+    ("uint16", Signature::U16),
+    ("int32", Signature::I32),
+    ("uint32", Signature::U32),
+    ("int64", Signature::I64),
+    ("uint64", Signature::U64),
+    #[cfg(unix)]
+    ("handle", Signature::Fd),
+    ("double", Signature::F64),
+    ("string", Signature::Str),
+    ("objectpath", Signature::ObjectPath),
+    ("signature", Signature::Signature),
+];
+
+#[derive(Debug)]
+struct Node {
+    offset: usize,
+    kind: NodeKind,
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    Bool(bool),
+    Number { text: String, float: bool },
+    Str(String),
+    Array(Vec<Node>),
+    Dict(Vec<(Node, Node)>),
+    Tuple(Vec<Node>),
+    Variant(Box<Node>),
+    Just(Box<Node>),
+    Nothing,
+    Typed(Signature, Box<Node>),
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error {
+            offset: self.pos,
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(ErrorKind::UnexpectedChar(c))),
+            None => Err(self.error(ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Consume `c` if it's next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn node(&mut self) -> Result<Node, Error> {
+        self.skip_whitespace();
+        let offset = self.pos;
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))?;
+        let kind = match c {
+            '[' => {
+                self.pos += 1;
+                NodeKind::Array(self.list(']')?)
+            }
+            '(' => {
+                self.pos += 1;
+                NodeKind::Tuple(self.list(')')?)
+            }
+            '{' => {
+                self.pos += 1;
+                let mut entries = vec![];
+                while !self.eat('}') {
+                    let key = self.node()?;
+                    self.expect(':')?;
+                    entries.push((key, self.node()?));
+                    if !self.eat(',') {
+                        self.expect('}')?;
+                        break;
+                    }
+                }
+                NodeKind::Dict(entries)
+            }
+            '<' => {
+                self.pos += 1;
+                let node = self.node()?;
+                self.expect('>')?;
+                NodeKind::Variant(Box::new(node))
+            }
+            '@' => {
+                self.pos += 1;
+                let signature = self.signature()?;
+                NodeKind::Typed(signature, Box::new(self.node()?))
+            }
+            '\'' | '"' => NodeKind::Str(self.string()?),
+            '0'..='9' | '-' | '+' | '.' => self.number()?,
+            c if c.is_ascii_alphabetic() => self.keyword()?,
+            c => return Err(self.error(ErrorKind::UnexpectedChar(c))),
+        };
+
+        Ok(Node { offset, kind })
+    }
+
+    /// Parse comma-separated nodes up to `end`.
+    fn list(&mut self, end: char) -> Result<Vec<Node>, Error> {
+        let mut nodes = vec![];
+        while !self.eat(end) {
+            nodes.push(self.node()?);
+            if !self.eat(',') {
+                self.expect(end)?;
+                break;
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /// Parse the signature of an `@` annotation, which ends at the first complete type.
+    fn signature(&mut self) -> Result<Signature, Error> {
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || !c.is_ascii_graphic() || "<['\"@".contains(c))
+            .unwrap_or(rest.len());
+        let signature: Signature = rest[..len]
+            .parse()
+            .map_err(|_| self.error(ErrorKind::InvalidSignature))?;
+        if signature == Signature::Unit || signature.string_len() != len {
+            return Err(self.error(ErrorKind::InvalidSignature));
+        }
+        self.pos += len;
+
+        Ok(signature)
+    }
+
+    fn keyword(&mut self) -> Result<NodeKind, Error> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+            self.pos += 1;
+        }
+        let word = &self.text[start..self.pos];
+        let kind = match word {
+            "true" => NodeKind::Bool(true),
+            "false" => NodeKind::Bool(false),
+            "nothing" => NodeKind::Nothing,
+            "just" => NodeKind::Just(Box::new(self.node()?)),
+            "inf" | "nan" => NodeKind::Number {
+                text: word.to_string(),
+                float: true,
+            },
+            _ => match KEYWORDS.iter().find(|(keyword, _)| *keyword == word) {
+                Some((_, signature)) => NodeKind::Typed(signature.clone(), Box::new(self.node()?)),
+                None => {
+                    self.pos = start;
+                    return Err(self.error(ErrorKind::UnknownKeyword(word.to_string())));
+                }
+            },
+        };
+
+        Ok(kind)
+    }
+
+    fn number(&mut self) -> Result<NodeKind, Error> {
+        let start = self.pos;
+        if matches!(self.peek(), Some('-' | '+')) {
+            self.pos += 1;
+        }
+        let rest = &self.text[self.pos..];
+        if rest.starts_with("inf") || rest.starts_with("nan") {
+            self.pos += 3;
+            return Ok(NodeKind::Number {
+                text: self.text[start..self.pos].to_string(),
+                float: true,
+            });
+        }
+
+        let mut float = false;
+        if rest.starts_with("0x") || rest.starts_with("0X") {
+            self.pos += 2;
+            while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                self.pos += 1;
+            }
+        } else {
+            let mut prev = ' ';
+            while let Some(c) = self.peek() {
+                let is_exponent_sign = matches!(c, '-' | '+') && matches!(prev, 'e' | 'E');
+                if !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E') || is_exponent_sign) {
+                    break;
+                }
+                float |= !c.is_ascii_digit();
+                prev = c;
+                self.pos += 1;
+            }
+        }
+
+        Ok(NodeKind::Number {
+            text: self.text[start..self.pos].to_string(),
+            float,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let quote = self.bump().unwrap();
+        let mut s = String::new();
+        loop {
+            let c = self
+                .bump()
+                .ok_or_else(|| self.error(ErrorKind::UnexpectedEnd))?;
+            match c {
+                c if c == quote => return Ok(s),
+                '\\' => {
+                    let escape_start = self.pos - 1;
+                    let invalid = |pos| Error {
+                        offset: pos,
+                        kind: ErrorKind::InvalidEscape,
+                    };
+                    let c = match self.bump().ok_or_else(|| invalid(escape_start))? {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'a' => '\x07',
+                        'b' => '\x08',
+                        'f' => '\x0c',
+                        'v' => '\x0b',
+                        c @ ('\\' | '\'' | '"') => c,
+                        c @ ('u' | 'U') => {
+                            let len = if c == 'u' { 4 } else { 8 };
+                            let digits = self
+                                .text
+                                .get(self.pos..self.pos + len)
+                                .ok_or_else(|| invalid(escape_start))?;
+                            self.pos += len;
+                            u32::from_str_radix(digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| invalid(escape_start))?
+                        }
+                        _ => return Err(invalid(escape_start)),
+                    };
+                    s.push(c);
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+impl Node {
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error {
+            offset: self.offset,
+            kind,
+        }
+    }
+
+    /// The type of this node when there's no expected type.
+    fn infer(&self) -> Result<Signature, Error> {
+        let signature = match &self.kind {
+            NodeKind::Bool(_) => Signature::Bool,
+            NodeKind::Number { float: false, .. } => Signature::I32,
+            NodeKind::Number { float: true, .. } => Signature::F64,
+            NodeKind::Str(_) => Signature::Str,
+            NodeKind::Variant(_) => Signature::Value,
+            NodeKind::Typed(signature, _) => signature.clone(),
+            NodeKind::Just(child) => Signature::Maybe(child.infer()?.into()),
+            NodeKind::Tuple(fields) if fields.is_empty() => Signature::Unit,
+            NodeKind::Tuple(fields) => Signature::Structure(FieldsSignatures::Dynamic {
+                fields: fields.iter().map(Node::infer).collect::<Result<_, _>>()?,
+            }),
+            NodeKind::Array(items) => Signature::Array(self.infer_all(items.iter())?.into()),
+            NodeKind::Dict(entries) => Signature::Dict {
+                key: self.infer_all(entries.iter().map(|(k, _)| k))?.into(),
+                value: self.infer_all(entries.iter().map(|(_, v)| v))?.into(),
+            },
+            NodeKind::Nothing => return Err(self.error(ErrorKind::CannotInfer)),
+        };
+
+        Ok(signature)
+    }
+
+    /// The common type of the children of a container.
+    ///
+    /// Unannotated numbers are `double` if any of them is, otherwise the type of the first
+    /// inferable child is used.
+    fn infer_all<'n>(
+        &self,
+        nodes: impl Iterator<Item = &'n Node> + Clone,
+    ) -> Result<Signature, Error> {
+        let numbers = nodes.clone().map(|node| match node.kind {
+            NodeKind::Number { float, .. } => Some(float),
+            _ => None,
+        });
+        if let Some(floats) = numbers.clone().collect::<Option<Vec<_>>>() {
+            if floats.contains(&true) {
+                return Ok(Signature::F64);
+            }
+        }
+
+        let mut first_error = None;
+        for node in nodes {
+            match node.infer() {
+                Ok(signature) => return Ok(signature),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(first_error.unwrap_or_else(|| self.error(ErrorKind::CannotInfer)))
+    }
+
+    fn convert(&self, signature: &Signature) -> Result<Value, Error> {
+        let mismatch = || {
+            self.error(ErrorKind::TypeMismatch {
+                expected: signature.clone(),
+            })
+        };
+
+        let value = match (&self.kind, signature) {
+            (NodeKind::Typed(annotation, node), _) => {
+                if annotation != signature {
+                    return Err(mismatch());
+                }
+                node.convert(signature)?
+            }
+            (NodeKind::Just(node), Signature::Maybe(child)) => Value::Maybe {
+                child: (**child).clone(),
+                value: Some(Box::new(node.convert(child)?)),
+            },
+            (NodeKind::Nothing, Signature::Maybe(child)) => Value::Maybe {
+                child: (**child).clone(),
+                value: None,
+            },
+            // `just` may be left out where it's unambiguous.
+            (_, Signature::Maybe(child)) => Value::Maybe {
+                child: (**child).clone(),
+                value: Some(Box::new(self.convert(child)?)),
+            },
+            (NodeKind::Bool(b), Signature::Bool) => Value::Bool(*b),
+            (NodeKind::Number { text, float }, _) => self.number(text, *float, signature)?,
+            (NodeKind::Str(s), Signature::Str) => Value::Str(s.clone()),
+            (NodeKind::Str(s), Signature::ObjectPath) => {
+                if !is_valid_object_path(s) {
+                    return Err(self.error(ErrorKind::InvalidObjectPath));
+                }
+                Value::ObjectPath(s.clone())
+            }
+            (NodeKind::Str(s), Signature::Signature) => Value::Signature(
+                s.parse()
+                    .map_err(|_| self.error(ErrorKind::InvalidSignature))?,
+            ),
+            (NodeKind::Variant(node), Signature::Value) => {
+                Value::Variant(Box::new(node.convert(&node.infer()?)?))
+            }
+            (NodeKind::Array(items), Signature::Array(element)) => Value::Array {
+                element: (**element).clone(),
+                items: items
+                    .iter()
+                    .map(|item| item.convert(element))
+                    .collect::<Result<_, _>>()?,
+            },
+            (NodeKind::Dict(entries), Signature::Dict { key, value }) => Value::Dict {
+                key: (**key).clone(),
+                value: (**value).clone(),
+                entries: entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.convert(key)?, v.convert(value)?)))
+                    .collect::<Result<_, _>>()?,
+            },
+            (NodeKind::Tuple(fields), Signature::Unit) if fields.is_empty() => Value::Unit,
+            (NodeKind::Tuple(nodes), Signature::Structure(fields)) => {
+                let fields: Vec<_> = fields.iter().collect();
+                if nodes.len() != fields.len() {
+                    return Err(self.error(ErrorKind::WrongLength {
+                        expected: fields.len(),
+                        found: nodes.len(),
+                    }));
+                }
+                Value::Structure(
+                    nodes
+                        .iter()
+                        .zip(fields)
+                        .map(|(node, field)| node.convert(field))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            _ => return Err(mismatch()),
+        };
+
+        Ok(value)
+    }
+
+    fn number(&self, text: &str, float: bool, signature: &Signature) -> Result<Value, Error> {
+        if *signature == Signature::F64 {
+            let n = match integer(text) {
+                Some(n) if !float => n as f64,
+                _ => text
+                    .parse()
+                    .map_err(|_| self.error(ErrorKind::InvalidNumber))?,
+            };
+
+            return Ok(Value::F64(n));
+        }
+
+        let n = || {
+            if float {
+                return Err(self.error(ErrorKind::TypeMismatch {
+                    expected: signature.clone(),
+                }));
+            }
+            integer(text).ok_or_else(|| self.error(ErrorKind::InvalidNumber))
+        };
+        let out_of_range = |_| self.error(ErrorKind::NumberOutOfRange);
+        let value = match signature {
+            Signature::U8 => Value::U8(n()?.try_into().map_err(out_of_range)?),
+            Signature::I16 => Value::I16(n()?.try_into().map_err(out_of_range)?),
+            Signature::U16 => Value::U16(n()?.try_into().map_err(out_of_range)?),
+            Signature::I32 => Value::I32(n()?.try_into().map_err(out_of_range)?),
+            Signature::U32 => Value::U32(n()?.try_into().map_err(out_of_range)?),
+            Signature::I64 => Value::I64(n()?.try_into().map_err(out_of_range)?),
+            Signature::U64 => Value::U64(n()?.try_into().map_err(out_of_range)?),
+            #[cfg(unix)]
+            Signature::Fd => Value::Fd(n()?.try_into().map_err(out_of_range)?),
+            _ => {
+                return Err(self.error(ErrorKind::TypeMismatch {
+                    expected: signature.clone(),
+                }))
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+/// Parse a decimal or hexadecimal integer literal.
+fn integer(text: &str) -> Option<i128> {
+    let (negative, digits) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let n = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+
+    Some(if negative { -n } else { n })
+}
+
+/// Write `value`, annotated if its type couldn't be inferred from the unannotated text.
+fn write_annotated(out: &mut String, value: &Value) {
+    let keyword = match value {
+        Value::U8(_) => Some("byte"),
+        Value::I16(_) => Some("int16"),
+        Value::U16(_) => Some("uint16"),
+        Value::U32(_) => Some("uint32"),
+        Value::I64(_) => Some("int64"),
+        Value::U64(_) => Some("uint64"),
+        #[cfg(unix)]
+        Value::Fd(_) => Some("handle"),
+        Value::ObjectPath(_) => Some("objectpath"),
+        Value::Signature(_) => Some("signature"),
+        _ => None,
+    };
+    if let Some(keyword) = keyword {
+        out.push_str(keyword);
+        out.push(' ');
+        write_value(out, value);
+
+        return;
+    }
+
+    let mut text = String::new();
+    write_value(&mut text, value);
+    let inferred = Parser {
+        text: &text,
+        pos: 0,
+    }
+    .node()
+    .and_then(|node| node.infer());
+    if inferred.ok() != Some(value.signature()) {
+        write!(out, "@{} ", value.signature()).unwrap();
+    }
+    out.push_str(&text);
+}
+
+/// Write `value` without an annotation of its own.
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Unit => out.push_str("()"),
+        Value::U8(n) => write!(out, "0x{:02x}", n).unwrap(),
+        Value::Bool(b) => write!(out, "{}", b).unwrap(),
+        Value::I16(n) => write!(out, "{}", n).unwrap(),
+        Value::U16(n) => write!(out, "{}", n).unwrap(),
+        Value::I32(n) => write!(out, "{}", n).unwrap(),
+        Value::U32(n) => write!(out, "{}", n).unwrap(),
+        Value::I64(n) => write!(out, "{}", n).unwrap(),
+        Value::U64(n) => write!(out, "{}", n).unwrap(),
+        #[cfg(unix)]
+        Value::Fd(n) => write!(out, "{}", n).unwrap(),
+        Value::F64(n) if n.is_nan() => out.push_str("nan"),
+        // Debug formatting always includes a `.` or an exponent, so it's not taken for an integer.
+        Value::F64(n) => write!(out, "{:?}", n).unwrap(),
+        Value::Str(s) | Value::ObjectPath(s) => write_string(out, s),
+        Value::Signature(signature) => write_string(out, &signature.to_string()),
+        Value::Variant(value) => {
+            out.push('<');
+            write_annotated(out, value);
+            out.push('>');
+        }
+        Value::Array { items, .. } => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Dict { entries, .. } => {
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, key);
+                out.push_str(": ");
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+        Value::Structure(fields) => {
+            out.push('(');
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, field);
+            }
+            if fields.len() == 1 {
+                out.push(',');
+            }
+            out.push(')');
+        }
+        Value::Maybe { value: None, .. } => out.push_str("nothing"),
+        Value::Maybe {
+            child,
+            value: Some(value),
+        } => {
+            // Without `just`, the value would be taken for the inner maybe.
+            if let Signature::Maybe(_) = child {
+                out.push_str("just ");
+            }
+            write_value(out, value);
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    fn round_trip(text: &str) {
+        let value = parse(text, None).unwrap();
+        assert_eq!(print(&value), text);
+    }
+
+    #[test]
+    fn parse_inferred() {
+        let value = parse("{'a': <int32 1>, 'b': <@as []>}", None).unwrap();
+        assert_eq!(value.signature(), "a{sv}");
+        assert_eq!(parse("[1, 2.5]", None).unwrap().signature(), "ad");
+        assert_eq!(parse("(1, 'x', true)", None).unwrap().signature(), "(isb)");
+        assert_eq!(
+            parse("[nothing, just byte 0x05]", None)
+                .unwrap()
+                .signature(),
+            "amy"
+        );
+        assert_eq!(parse("@a{sv} {}", None).unwrap().signature(), "a{sv}");
+        assert_eq!(parse("()", None).unwrap(), Value::Unit);
+        assert_eq!(
+            parse("\"it's\\n\\u00e9\"", None).unwrap(),
+            Value::Str("it's\né".to_string())
+        );
+        assert_eq!(parse("uint64 0xff", None).unwrap(), Value::U64(255));
+        assert_eq!(parse("-inf", None).unwrap(), Value::F64(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn parse_expected() {
+        assert_eq!(parse("7", Some(&sig("y"))).unwrap(), Value::U8(7));
+        assert_eq!(parse("7", Some(&sig("d"))).unwrap(), Value::F64(7.0));
+        assert_eq!(
+            parse("['/a', '/b']", Some(&sig("ao"))).unwrap(),
+            Value::Array {
+                element: Signature::ObjectPath,
+                items: vec![
+                    Value::ObjectPath("/a".to_string()),
+                    Value::ObjectPath("/b".to_string())
+                ],
+            }
+        );
+        assert_eq!(
+            parse("5", Some(&sig("mmi"))).unwrap(),
+            parse("just just 5", None).unwrap()
+        );
+        assert_eq!(
+            parse("just nothing", Some(&sig("mmi"))).unwrap(),
+            Value::Maybe {
+                child: sig("mi"),
+                value: Some(Box::new(Value::Maybe {
+                    child: Signature::I32,
+                    value: None
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str, expected: Option<&str>| {
+            let e = parse(text, expected.map(sig).as_ref()).unwrap_err();
+            (e.offset(), e.kind().clone())
+        };
+
+        assert_eq!(error("[]", None), (0, ErrorKind::CannotInfer));
+        assert_eq!(error("[1, 2", None), (5, ErrorKind::UnexpectedEnd));
+        assert_eq!(error("300", Some("y")), (0, ErrorKind::NumberOutOfRange));
+        assert_eq!(
+            error("(1, 'a')", Some("(ii)")),
+            (
+                4,
+                ErrorKind::TypeMismatch {
+                    expected: Signature::I32
+                }
+            )
+        );
+        assert_eq!(
+            error("(1,)", Some("(ii)")),
+            (
+                0,
+                ErrorKind::WrongLength {
+                    expected: 2,
+                    found: 1
+                }
+            )
+        );
+        assert_eq!(
+            error("objectpath 'a'", None),
+            (11, ErrorKind::InvalidObjectPath)
+        );
+        assert_eq!(
+            error("int8 1", None),
+            (0, ErrorKind::UnknownKeyword("int8".to_string()))
+        );
+        assert_eq!(error("@a{ 1", None), (1, ErrorKind::InvalidSignature));
+        assert_eq!(error("'\\q'", None), (1, ErrorKind::InvalidEscape));
+        assert_eq!(error("1 2", None), (2, ErrorKind::UnexpectedChar('2')));
+    }
+
+    #[test]
+    fn print_round_trip() {
+        round_trip("{'a': <1>, 'b': <@as []>}");
+        round_trip("@a{ub} {1: true}");
+        round_trip("@(y) (0x01,)");
+        round_trip("[1.5, 2.0]");
+        round_trip("<uint64 18446744073709551615>");
+        round_trip("@mms just nothing");
+        round_trip("@ms 'x'");
+        round_trip("@ao ['/a']");
+        round_trip("('it\\'s', <signature 'a{sv}'>, <objectpath '/'>)");
+        round_trip("(<()>, <[nan]>)");
+    }
+}
@@ -0,0 +1,196 @@
+use std::marker::PhantomData;
+
+use crate::r#type::Type;
+use crate::signature::{FieldsSignatures, Signature};
+
+use super::{Access, Annotation, Arg, Direction, Interface, Method, Property, Signal};
+
+/// Builds an [`Interface`] from the Rust types of its members.
+///
+/// The types of all arguments and properties come from their `Type::SIGNATURE`, so the resulting
+/// introspection data can't disagree with the types used by the implementation. Only the split
+/// into arguments happens at compile time: the interface holds the [`Signature`]s and the `type=`
+/// strings are rendered from them when the XML is written, by [`Node::to_xml`].
+///
+/// [`Node::to_xml`]: super::Node::to_xml
+///
+/// Arguments are declared as a single type: a tuple is a list of arguments, `()` means no
+/// arguments and any other type is a single argument. A single structure argument is therefore
+/// declared as a tuple of one tuple, e.g. `((String, u32),)`.
+#[derive(Debug, Clone)]
+pub struct InterfaceBuilder {
+    interface: Interface,
+}
+
+impl InterfaceBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            interface: Interface::new(name),
+        }
+    }
+
+    /// Declare a method taking `In` and returning `Out`.
+    ///
+    /// `in_names` and `out_names` name the arguments in order, or are empty to leave them unnamed.
+    ///
+    /// # Panics
+    ///
+    /// If `in_names` or `out_names` is neither empty nor as long as the arguments.
+    pub fn method<In, Out>(mut self, name: &str, in_names: &[&str], out_names: &[&str]) -> Self
+    where
+        In: Type + ?Sized,
+        Out: Type + ?Sized,
+    {
+        let mut method_args = args::<In>(name, in_names, Some(Direction::In));
+        method_args.extend(args::<Out>(name, out_names, Some(Direction::Out)));
+        self.interface.methods.push(Method {
+            name: name.to_string(),
+            args: method_args,
+            annotations: vec![],
+        });
+
+        self
+    }
+
+    /// Declare a signal carrying `Args`.
+    ///
+    /// # Panics
+    ///
+    /// If `names` is neither empty nor as long as the arguments.
+    pub fn signal<Args>(mut self, name: &str, names: &[&str]) -> Self
+    where
+        Args: Type + ?Sized,
+    {
+        self.interface.signals.push(Signal {
+            name: name.to_string(),
+            args: args::<Args>(name, names, None),
+            annotations: vec![],
+        });
+
+        self
+    }
+
+    /// Declare a property of type `T`.
+    pub fn property<T>(mut self, name: &str, access: Access) -> Self
+    where
+        T: Type + ?Sized,
+    {
+        self.interface.properties.push(Property {
+            name: name.to_string(),
+            signature: T::SIGNATURE.clone(),
+            access,
+            annotations: vec![],
+        });
+
+        self
+    }
+
+    /// Annotate the interface.
+    pub fn annotation(mut self, name: &str, value: &str) -> Self {
+        self.interface.annotations.push(Annotation {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+
+        self
+    }
+
+    pub fn build(self) -> Interface {
+        self.interface
+    }
+}
+
+/// The signatures of the arguments declared as `T`, split from `T::SIGNATURE` at compile time.
+struct Arguments<T: ?Sized>(PhantomData<T>);
+
+impl<T: Type + ?Sized> Arguments<T> {
+    const SIGNATURES: &'static [&'static Signature] = match T::SIGNATURE {
+        Signature::Unit => &[],
+        Signature::Structure(FieldsSignatures::Static { fields }) => fields,
+        // `Type` signatures are constants, which can't hold an `Arc`.
+        Signature::Structure(FieldsSignatures::Dynamic { .. }) => unreachable!(),
+        _ => Self::SINGLE,
+    };
+    const SINGLE: &'static [&'static Signature] = &[T::SIGNATURE];
+}
+
+/// The arguments declared as `T`, named after `names`.
+fn args<T: Type + ?Sized>(member: &str, names: &[&str], direction: Option<Direction>) -> Vec<Arg> {
+    let signatures = Arguments::<T>::SIGNATURES;
+    assert!(
+        names.is_empty() || names.len() == signatures.len(),
+        "{} names given for the {} arguments of `{}`",
+        names.len(),
+        signatures.len(),
+        member
+    );
+
+    signatures
+        .iter()
+        .enumerate()
+        .map(|(i, &signature)| Arg {
+            name: names.get(i).map(|name| name.to_string()),
+            signature: signature.clone(),
+            direction,
+            annotations: vec![],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::introspection::Node;
+
+    #[test]
+    fn build() {
+        let interface = InterfaceBuilder::new("org.example.Foo")
+            .method::<(String, u32), Vec<String>>("Frob", &["name", "count"], &["items"])
+            .method::<((String, u32),), ()>("Store", &["entry"], &[])
+            .method::<(), HashMap<String, Vec<(u32, i64)>>>("Dump", &[], &[])
+            .signal::<(String, bool)>("Changed", &[])
+            .property::<u32>("Version", Access::Read)
+            .annotation("org.freedesktop.DBus.Deprecated", "true")
+            .build();
+        let node = Node {
+            name: None,
+            interfaces: vec![interface],
+            nodes: vec![],
+        };
+        let xml = node.to_xml();
+        assert!(xml.contains(r#"<arg name="count" type="u" direction="in"/>"#));
+        assert!(xml.contains(r#"<arg name="entry" type="(su)" direction="in"/>"#));
+        assert!(xml.contains(r#"<arg type="a{sa(ux)}" direction="out"/>"#));
+        assert!(xml.contains(r#"<arg type="b"/>"#));
+        assert!(xml.contains(r#"<property name="Version" type="u" access="read"/>"#));
+
+        let parsed = Node::from_xml(&xml).unwrap();
+        assert_eq!(parsed, node);
+        let interface = &parsed.interfaces[0];
+        assert_eq!(
+            &interface.methods[0].in_signature(),
+            <(String, u32)>::SIGNATURE
+        );
+        assert_eq!(
+            &interface.methods[0].out_signature(),
+            <Vec<String>>::SIGNATURE
+        );
+        assert_eq!(interface.methods[1].out_signature(), Signature::Unit);
+        assert_eq!(
+            &interface.signals[0].signature(),
+            <(String, bool)>::SIGNATURE
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "1 names given for the 2 arguments of `Frob`")]
+    fn wrong_names() {
+        InterfaceBuilder::new("org.example.Foo").method::<(String, u32), ()>(
+            "Frob",
+            &["name"],
+            &[],
+        );
+    }
+}
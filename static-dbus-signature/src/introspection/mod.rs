@@ -0,0 +1,855 @@
+mod builder;
+pub use builder::InterfaceBuilder;
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::signature::{FieldsSignatures, ParseError, Signature};
+
+const DOCTYPE: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">"#;
+
+/// A node of `org.freedesktop.DBus.Introspectable` data.
+///
+/// The root node of a document usually has no name while child nodes are named relative to their
+/// parent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Node {
+    pub name: Option<String>,
+    pub interfaces: Vec<Interface>,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    pub methods: Vec<Method>,
+    pub signals: Vec<Signal>,
+    pub properties: Vec<Property>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub name: String,
+    pub signature: Signature,
+    pub access: Access,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg {
+    pub name: Option<String>,
+    pub signature: Signature,
+    pub direction: Option<Direction>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Interface {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            methods: vec![],
+            signals: vec![],
+            properties: vec![],
+            annotations: vec![],
+        }
+    }
+}
+
+impl Method {
+    /// The arguments passed to the method.
+    pub fn in_args(&self) -> impl Iterator<Item = &Arg> {
+        // Method arguments are input arguments by default.
+        self.args
+            .iter()
+            .filter(|arg| arg.direction != Some(Direction::Out))
+    }
+
+    /// The arguments returned by the method.
+    pub fn out_args(&self) -> impl Iterator<Item = &Arg> {
+        self.args
+            .iter()
+            .filter(|arg| arg.direction == Some(Direction::Out))
+    }
+
+    /// The signature of the method call body.
+    ///
+    /// Comparable against the `Type::SIGNATURE` of the handler's argument type (a tuple if there
+    /// is more than one argument).
+    pub fn in_signature(&self) -> Signature {
+        args_signature(self.in_args())
+    }
+
+    /// The signature of the method reply body.
+    pub fn out_signature(&self) -> Signature {
+        args_signature(self.out_args())
+    }
+}
+
+impl Signal {
+    /// The signature of the signal body.
+    pub fn signature(&self) -> Signature {
+        args_signature(self.args.iter())
+    }
+}
+
+fn args_signature<'a>(args: impl Iterator<Item = &'a Arg>) -> Signature {
+    let mut signatures: Vec<_> = args.map(|arg| arg.signature.clone()).collect();
+    match signatures.len() {
+        0 => Signature::Unit,
+        1 => signatures.remove(0),
+        _ => Signature::Structure(FieldsSignatures::Dynamic {
+            fields: signatures.into(),
+        }),
+    }
+}
+
+/// The location of an element in an XML document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The byte offset from the start of the document.
+    pub offset: usize,
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The column (in characters), starting at 1.
+    pub column: usize,
+}
+
+impl Location {
+    fn new(xml: &str, offset: usize) -> Self {
+        let offset = offset.min(xml.len());
+        let before = &xml[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        Self {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// Error returned when parsing invalid introspection XML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The document isn't well-formed XML.
+    Xml { location: Location, message: String },
+    /// The root element isn't a `node`.
+    UnexpectedRoot { location: Location, element: String },
+    /// An element lacks a required attribute.
+    MissingAttribute {
+        location: Location,
+        element: &'static str,
+        attribute: &'static str,
+    },
+    /// An attribute has an invalid value.
+    InvalidAttribute {
+        location: Location,
+        attribute: &'static str,
+        value: String,
+    },
+    /// A `type` attribute isn't a valid signature.
+    InvalidSignature {
+        location: Location,
+        signature: String,
+        error: ParseError,
+    },
+}
+
+impl Error {
+    /// Where in the document the error occurred.
+    pub fn location(&self) -> Location {
+        match self {
+            Error::Xml { location, .. }
+            | Error::UnexpectedRoot { location, .. }
+            | Error::MissingAttribute { location, .. }
+            | Error::InvalidAttribute { location, .. }
+            | Error::InvalidSignature { location, .. } => *location,
+        }
+    }
+}
+
+impl Node {
+    /// Parse `org.freedesktop.DBus.Introspectable` XML.
+    ///
+    /// Unknown elements are ignored, along with their children.
+    pub fn from_xml(xml: &str) -> Result<Node, Error> {
+        let mut parser = Parser {
+            reader: Reader::from_str(xml),
+            xml,
+        };
+        parser.reader.config_mut().trim_text(true);
+
+        loop {
+            match parser.next()? {
+                (Event::Start(e), offset) | (Event::Empty(e), offset)
+                    if e.name().as_ref() != b"node" =>
+                {
+                    return Err(Error::UnexpectedRoot {
+                        location: parser.location(offset),
+                        element: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+                    });
+                }
+                (Event::Start(e), offset) => return parser.node(&e, false, offset),
+                (Event::Empty(e), offset) => return parser.node(&e, true, offset),
+                (Event::Eof, offset) => {
+                    return Err(Error::Xml {
+                        location: parser.location(offset),
+                        message: "no root element".to_string(),
+                    })
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Serialize `self` to introspection XML.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        writeln!(xml, "{}", DOCTYPE).unwrap();
+        // Writing to a `String` can't fail.
+        self.write_xml(&mut xml, 0).unwrap();
+
+        xml
+    }
+
+    fn write_xml(&self, w: &mut String, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        write!(w, "{}<node", indent)?;
+        if let Some(name) = &self.name {
+            write!(w, " name=\"{}\"", escape(name))?;
+        }
+        if self.interfaces.is_empty() && self.nodes.is_empty() {
+            return writeln!(w, "/>");
+        }
+        writeln!(w, ">")?;
+
+        for interface in &self.interfaces {
+            writeln!(
+                w,
+                "{}  <interface name=\"{}\">",
+                indent,
+                escape(&interface.name)
+            )?;
+            let indent = "  ".repeat(depth + 2);
+            for method in &interface.methods {
+                write_member(
+                    w,
+                    &indent,
+                    "method",
+                    &method.name,
+                    &method.args,
+                    &method.annotations,
+                )?;
+            }
+            for signal in &interface.signals {
+                write_member(
+                    w,
+                    &indent,
+                    "signal",
+                    &signal.name,
+                    &signal.args,
+                    &signal.annotations,
+                )?;
+            }
+            for property in &interface.properties {
+                let access = match property.access {
+                    Access::Read => "read",
+                    Access::Write => "write",
+                    Access::ReadWrite => "readwrite",
+                };
+                write!(
+                    w,
+                    "{}<property name=\"{}\" type=\"{}\" access=\"{}\"",
+                    indent,
+                    escape(&property.name),
+                    property.signature,
+                    access
+                )?;
+                write_annotations(w, &indent, "property", &property.annotations)?;
+            }
+            for annotation in &interface.annotations {
+                write_annotation(w, &indent, annotation)?;
+            }
+            writeln!(w, "{}  </interface>", "  ".repeat(depth))?;
+        }
+        for node in &self.nodes {
+            node.write_xml(w, depth + 1)?;
+        }
+
+        writeln!(w, "{}</node>", indent)
+    }
+}
+
+fn write_member(
+    w: &mut String,
+    indent: &str,
+    element: &str,
+    name: &str,
+    args: &[Arg],
+    annotations: &[Annotation],
+) -> fmt::Result {
+    write!(w, "{}<{} name=\"{}\"", indent, element, escape(name))?;
+    if args.is_empty() && annotations.is_empty() {
+        return writeln!(w, "/>");
+    }
+    writeln!(w, ">")?;
+
+    let child_indent = format!("{}  ", indent);
+    for arg in args {
+        write!(w, "{}<arg", child_indent)?;
+        if let Some(name) = &arg.name {
+            write!(w, " name=\"{}\"", escape(name))?;
+        }
+        write!(w, " type=\"{}\"", arg.signature)?;
+        match arg.direction {
+            Some(Direction::In) => write!(w, " direction=\"in\"")?,
+            Some(Direction::Out) => write!(w, " direction=\"out\"")?,
+            None => (),
+        }
+        write_annotations(w, &child_indent, "arg", &arg.annotations)?;
+    }
+    for annotation in annotations {
+        write_annotation(w, &child_indent, annotation)?;
+    }
+
+    writeln!(w, "{}</{}>", indent, element)
+}
+
+/// Close an element whose start tag has been written, with `annotations` as its children.
+fn write_annotations(
+    w: &mut String,
+    indent: &str,
+    element: &str,
+    annotations: &[Annotation],
+) -> fmt::Result {
+    if annotations.is_empty() {
+        return writeln!(w, "/>");
+    }
+    writeln!(w, ">")?;
+    for annotation in annotations {
+        write_annotation(w, &format!("{}  ", indent), annotation)?;
+    }
+
+    writeln!(w, "{}</{}>", indent, element)
+}
+
+fn write_annotation(w: &mut String, indent: &str, annotation: &Annotation) -> fmt::Result {
+    writeln!(
+        w,
+        "{}<annotation name=\"{}\" value=\"{}\"/>",
+        indent,
+        escape(&annotation.name),
+        escape(&annotation.value)
+    )
+}
+
+struct Parser<'a> {
+    reader: Reader<&'a [u8]>,
+    xml: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    /// The next event, along with its byte offset.
+    ///
+    /// Only errors need a [`Location`], so its line and column are only computed for them, as
+    /// that means scanning the document up to the offset.
+    fn next(&mut self) -> Result<(Event<'a>, usize), Error> {
+        let event = self.reader.read_event().map_err(|e| Error::Xml {
+            location: self.location(self.reader.error_position() as usize),
+            message: e.to_string(),
+        })?;
+        // The reader is past the tag now, and whitespace before it has been trimmed, so count
+        // back over the tag: its content excludes the `<` and `>`, and the `/` if it's empty.
+        let end = self.reader.buffer_position() as usize;
+        let offset = match &event {
+            Event::Start(e) => end - e.len() - 2,
+            Event::Empty(e) => end - e.len() - 3,
+            _ => end,
+        };
+
+        Ok((event, offset))
+    }
+
+    fn location(&self, offset: usize) -> Location {
+        Location::new(self.xml, offset)
+    }
+
+    /// Parse the children elements of an element, calling `child` for each of them.
+    fn children(
+        &mut self,
+        empty: bool,
+        mut child: impl FnMut(&mut Self, &BytesStart, bool, usize) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if empty {
+            return Ok(());
+        }
+
+        loop {
+            match self.next()? {
+                (Event::Start(e), offset) => child(self, &e, false, offset)?,
+                (Event::Empty(e), offset) => child(self, &e, true, offset)?,
+                (Event::End(_), _) => return Ok(()),
+                (Event::Eof, offset) => {
+                    return Err(Error::Xml {
+                        location: self.location(offset),
+                        message: "unexpected end of document".to_string(),
+                    })
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Skip the children of an unknown element.
+    fn skip(&mut self, empty: bool) -> Result<(), Error> {
+        self.children(empty, |parser, _, empty, _| parser.skip(empty))
+    }
+
+    fn node(&mut self, e: &BytesStart, empty: bool, offset: usize) -> Result<Node, Error> {
+        let mut node = Node {
+            name: self.attribute(e, "name", offset)?,
+            interfaces: vec![],
+            nodes: vec![],
+        };
+        self.children(empty, |parser, e, empty, offset| {
+            match e.name().as_ref() {
+                b"interface" => node.interfaces.push(parser.interface(e, empty, offset)?),
+                b"node" => node.nodes.push(parser.node(e, empty, offset)?),
+                _ => parser.skip(empty)?,
+            }
+
+            Ok(())
+        })?;
+
+        Ok(node)
+    }
+
+    fn interface(
+        &mut self,
+        e: &BytesStart,
+        empty: bool,
+        offset: usize,
+    ) -> Result<Interface, Error> {
+        let mut interface =
+            Interface::new(&self.required_attribute(e, "interface", "name", offset)?);
+        self.children(empty, |parser, e, empty, offset| {
+            match e.name().as_ref() {
+                b"method" => {
+                    let name = parser.required_attribute(e, "method", "name", offset)?;
+                    let (args, annotations) = parser.member(empty)?;
+                    interface.methods.push(Method {
+                        name,
+                        args,
+                        annotations,
+                    });
+                }
+                b"signal" => {
+                    let name = parser.required_attribute(e, "signal", "name", offset)?;
+                    let (args, annotations) = parser.member(empty)?;
+                    interface.signals.push(Signal {
+                        name,
+                        args,
+                        annotations,
+                    });
+                }
+                b"property" => interface
+                    .properties
+                    .push(parser.property(e, empty, offset)?),
+                b"annotation" => interface
+                    .annotations
+                    .push(parser.annotation(e, empty, offset)?),
+                _ => parser.skip(empty)?,
+            }
+
+            Ok(())
+        })?;
+
+        Ok(interface)
+    }
+
+    /// Parse the args and annotations of a method or signal.
+    fn member(&mut self, empty: bool) -> Result<(Vec<Arg>, Vec<Annotation>), Error> {
+        let mut args = vec![];
+        let mut annotations = vec![];
+        self.children(empty, |parser, e, empty, offset| {
+            match e.name().as_ref() {
+                b"arg" => args.push(parser.arg(e, empty, offset)?),
+                b"annotation" => annotations.push(parser.annotation(e, empty, offset)?),
+                _ => parser.skip(empty)?,
+            }
+
+            Ok(())
+        })?;
+
+        Ok((args, annotations))
+    }
+
+    fn arg(&mut self, e: &BytesStart, empty: bool, offset: usize) -> Result<Arg, Error> {
+        let name = self.attribute(e, "name", offset)?;
+        let signature = self.signature(e, "arg", offset)?;
+        let direction = match self.attribute(e, "direction", offset)?.as_deref() {
+            None => None,
+            Some("in") => Some(Direction::In),
+            Some("out") => Some(Direction::Out),
+            Some(value) => {
+                return Err(Error::InvalidAttribute {
+                    location: self.location(offset),
+                    attribute: "direction",
+                    value: value.to_string(),
+                })
+            }
+        };
+
+        Ok(Arg {
+            name,
+            signature,
+            direction,
+            annotations: self.annotations(empty)?,
+        })
+    }
+
+    fn property(&mut self, e: &BytesStart, empty: bool, offset: usize) -> Result<Property, Error> {
+        let name = self.required_attribute(e, "property", "name", offset)?;
+        let signature = self.signature(e, "property", offset)?;
+        let access = match self
+            .required_attribute(e, "property", "access", offset)?
+            .as_str()
+        {
+            "read" => Access::Read,
+            "write" => Access::Write,
+            "readwrite" => Access::ReadWrite,
+            value => {
+                return Err(Error::InvalidAttribute {
+                    location: self.location(offset),
+                    attribute: "access",
+                    value: value.to_string(),
+                })
+            }
+        };
+
+        Ok(Property {
+            name,
+            signature,
+            access,
+            annotations: self.annotations(empty)?,
+        })
+    }
+
+    fn annotations(&mut self, empty: bool) -> Result<Vec<Annotation>, Error> {
+        let mut annotations = vec![];
+        self.children(empty, |parser, e, empty, offset| {
+            match e.name().as_ref() {
+                b"annotation" => annotations.push(parser.annotation(e, empty, offset)?),
+                _ => parser.skip(empty)?,
+            }
+
+            Ok(())
+        })?;
+
+        Ok(annotations)
+    }
+
+    fn annotation(
+        &mut self,
+        e: &BytesStart,
+        empty: bool,
+        offset: usize,
+    ) -> Result<Annotation, Error> {
+        let annotation = Annotation {
+            name: self.required_attribute(e, "annotation", "name", offset)?,
+            value: self.required_attribute(e, "annotation", "value", offset)?,
+        };
+        self.skip(empty)?;
+
+        Ok(annotation)
+    }
+
+    fn signature(
+        &self,
+        e: &BytesStart,
+        element: &'static str,
+        offset: usize,
+    ) -> Result<Signature, Error> {
+        let ty = self.required_attribute(e, element, "type", offset)?;
+        let invalid = |ty: String, error| Error::InvalidSignature {
+            location: self.location(offset),
+            signature: ty,
+            error,
+        };
+
+        let signature: Signature = match ty.parse() {
+            Ok(signature) => signature,
+            Err(error) => return Err(invalid(ty, error)),
+        };
+        // A type is a single complete type, but the empty string parses as the unit type and
+        // `si` as a structure of both types, so point at what follows the first type instead.
+        let len = match &signature {
+            Signature::Unit => Some(0),
+            Signature::Structure(fields) if signature.string_len() != ty.len() => {
+                fields.iter().next().map(Signature::string_len)
+            }
+            _ => None,
+        };
+        match len {
+            Some(len) => {
+                let error = ParseError::new(&ty, &ty[len..]);
+
+                Err(invalid(ty, error))
+            }
+            None => Ok(signature),
+        }
+    }
+
+    fn attribute(
+        &self,
+        e: &BytesStart,
+        name: &'static str,
+        offset: usize,
+    ) -> Result<Option<String>, Error> {
+        let xml_error = |e: &dyn std::error::Error| Error::Xml {
+            location: self.location(offset),
+            message: e.to_string(),
+        };
+
+        e.try_get_attribute(name)
+            .map_err(|e| xml_error(&e))?
+            .map(|a| a.unescape_value().map(|value| value.into_owned()))
+            .transpose()
+            .map_err(|e| xml_error(&e))
+    }
+
+    fn required_attribute(
+        &self,
+        e: &BytesStart,
+        element: &'static str,
+        name: &'static str,
+        offset: usize,
+    ) -> Result<String, Error> {
+        self.attribute(e, name, offset)?
+            .ok_or_else(|| Error::MissingAttribute {
+                location: self.location(offset),
+                element,
+                attribute: name,
+            })
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Error::Xml { location, message } => write!(f, "{}: {}", location, message),
+            Error::UnexpectedRoot { location, element } => write!(
+                f,
+                "{}: expected `node` root element, found `{}`",
+                location, element
+            ),
+            Error::MissingAttribute {
+                location,
+                element,
+                attribute,
+            } => write!(
+                f,
+                "{}: `{}` element lacks `{}` attribute",
+                location, element, attribute
+            ),
+            Error::InvalidAttribute {
+                location,
+                attribute,
+                value,
+            } => write!(
+                f,
+                "{}: invalid `{}` attribute `{}`",
+                location, attribute, value
+            ),
+            Error::InvalidSignature {
+                location,
+                signature,
+                error,
+            } => write!(f, "{}: invalid type `{}`: {}", location, signature, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#type::Type;
+
+    const XML: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node name="/org/example">
+  <!-- A comment -->
+  <interface name="org.freedesktop.DBus.Properties">
+    <method name="Get">
+      <arg type="s" name="interface_name" direction="in"/>
+      <arg type="s" name="property_name" direction="in"/>
+      <arg type="v" name="value" direction="out"/>
+    </method>
+    <method name="GetAll">
+      <arg type="s" name="interface_name" direction="in"/>
+      <arg type="a{sv}" name="properties" direction="out">
+        <annotation name="org.qtproject.QtDBus.QtTypeName.Out0" value="QVariantMap"/>
+      </arg>
+    </method>
+    <signal name="PropertiesChanged">
+      <arg type="s" name="interface_name"/>
+      <arg type="a{sv}" name="changed_properties"/>
+      <arg type="as" name="invalidated_properties"/>
+    </signal>
+    <property name="Version" type="u" access="read">
+      <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="const"/>
+    </property>
+    <property name="Name" type="s" access="readwrite"/>
+    <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+    <unknown><with children="1"/></unknown>
+  </interface>
+  <node name="child"/>
+  <node name="other">
+    <interface name="org.example.Empty"/>
+  </node>
+</node>"#;
+
+    #[test]
+    fn parse() {
+        let node = Node::from_xml(XML).unwrap();
+        assert_eq!(node.name.as_deref(), Some("/org/example"));
+        assert_eq!(node.interfaces.len(), 1);
+        assert_eq!(node.nodes.len(), 2);
+        assert_eq!(node.nodes[0].name.as_deref(), Some("child"));
+        assert_eq!(node.nodes[1].interfaces[0].name, "org.example.Empty");
+
+        let interface = &node.interfaces[0];
+        assert_eq!(interface.methods.len(), 2);
+        assert_eq!(interface.signals.len(), 1);
+        assert_eq!(interface.properties.len(), 2);
+        assert_eq!(interface.annotations.len(), 1);
+
+        let get = &interface.methods[0];
+        assert_eq!(&get.in_signature(), <(String, String)>::SIGNATURE);
+        assert_eq!(get.out_signature(), Signature::Value);
+        let get_all = &interface.methods[1];
+        assert_eq!(get_all.in_signature(), *String::SIGNATURE);
+        assert_eq!(
+            get_all.out_args().next().unwrap().annotations[0].value,
+            "QVariantMap"
+        );
+
+        let signal = &interface.signals[0];
+        assert_eq!(signal.signature(), "sa{sv}as");
+
+        let version = &interface.properties[0];
+        assert_eq!(&version.signature, u32::SIGNATURE);
+        assert_eq!(version.access, Access::Read);
+        assert_eq!(version.annotations[0].value, "const");
+        assert_eq!(interface.properties[1].access, Access::ReadWrite);
+    }
+
+    #[test]
+    fn round_trip() {
+        let node = Node::from_xml(XML).unwrap();
+        let xml = node.to_xml();
+        assert_eq!(Node::from_xml(&xml).unwrap(), node);
+        assert!(xml.contains(r#"<property name="Name" type="s" access="readwrite"/>"#));
+    }
+
+    #[test]
+    fn errors() {
+        let xml =
+            "<node>\n  <interface name=\"a\">\n    <method name=\"M\"><arg type=\"a{\"/></method>";
+        let e = Node::from_xml(xml).unwrap_err();
+        assert!(matches!(e, Error::InvalidSignature { .. }));
+        assert_eq!(e.location().line, 3);
+        assert_eq!(e.location().column, 22);
+        assert_eq!(
+            e.to_string(),
+            "3:22: invalid type `a{`: invalid signature at offset 2"
+        );
+
+        let xml =
+            "<node>\n  <interface name=\"a\">\n    <method name=\"M\">\n      <arg type=\"a{\"/>";
+        let e = Node::from_xml(xml).unwrap_err();
+        assert_eq!((e.location().line, e.location().column), (4, 7));
+        assert_eq!(&xml[e.location().offset..][..4], "<arg");
+
+        let xml =
+            "<node><interface name=\"a\">\n  <property name=\"P\" type=\"si\" access=\"read\"/>";
+        let e = Node::from_xml(xml).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "2:3: invalid type `si`: invalid signature at offset 1"
+        );
+        let xml = "<node><interface name=\"a\"><signal name=\"S\"><arg type=\"\"/>";
+        assert!(matches!(
+            Node::from_xml(xml).unwrap_err(),
+            Error::InvalidSignature { .. }
+        ));
+
+        let xml =
+            "<node><interface name=\"a\"><property name=\"P\" type=\"s\"/></interface></node>";
+        assert!(matches!(
+            Node::from_xml(xml).unwrap_err(),
+            Error::MissingAttribute {
+                attribute: "access",
+                ..
+            }
+        ));
+
+        let xml = "<interface name=\"a\"/>";
+        assert!(matches!(
+            Node::from_xml(xml).unwrap_err(),
+            Error::UnexpectedRoot { .. }
+        ));
+
+        let xml = "<node><interface name=\"a\">";
+        assert!(matches!(
+            Node::from_xml(xml).unwrap_err(),
+            Error::Xml { .. }
+        ));
+    }
+}
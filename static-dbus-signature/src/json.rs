@@ -0,0 +1,625 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use serde_json::{Map, Number, Value as Json};
+
+use crate::dynamic_type::DynamicType;
+use crate::encoding::dbus::is_single_complete_type;
+use crate::signature::{PathSegment, Signature, SignaturePath};
+use crate::value::{is_valid_object_path, Value};
+
+/// Why a JSON value was rejected by [`from_json`], or a value by [`to_json`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The JSON value has the wrong type, e.g. a string where a number was expected.
+    TypeMismatch {
+        expected: &'static str,
+    },
+    /// A number doesn't fit into the integer type or isn't an integer.
+    OutOfRange,
+    InvalidObjectPath,
+    InvalidSignature,
+    /// A structure got the wrong number of fields.
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+    /// A variant isn't a `{"signature": ..., "value": ...}` object.
+    InvalidVariant,
+    /// A dictionary key couldn't be converted.
+    InvalidKey(String),
+    /// A dictionary key isn't of the key type of its dictionary.
+    KeyTypeMismatch,
+}
+
+/// An error converting between JSON and a [`Value`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pointer: String,
+    path: SignaturePath,
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// The JSON pointer (RFC 6901) of the rejected JSON value, or of the JSON value the rejected
+    /// value would have been converted into.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// The path of the signature the rejected value was checked against.
+    pub fn path(&self) -> &SignaturePath {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::TypeMismatch { expected } => write!(f, "expected {}", expected),
+            ErrorKind::OutOfRange => write!(f, "number out of range"),
+            ErrorKind::InvalidObjectPath => write!(f, "invalid object path"),
+            ErrorKind::InvalidSignature => write!(f, "invalid signature"),
+            ErrorKind::WrongLength { expected, found } => {
+                write!(f, "expected {} fields, found {}", expected, found)
+            }
+            ErrorKind::InvalidVariant => write!(
+                f,
+                "expected a variant object with `signature` and `value` members"
+            ),
+            ErrorKind::InvalidKey(key) => write!(f, "invalid dictionary key `{}`", key),
+            ErrorKind::KeyTypeMismatch => write!(f, "dictionary key of the wrong type"),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} at `{}` ({})", self.kind, self.pointer, self.path)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Convert `json` into a [`Value`] of type `signature`.
+///
+/// The JSON representation is the one described by [`Signature::json_schema`].
+pub fn from_json(signature: &Signature, json: &Json) -> Result<Value, Error> {
+    Converter {
+        pointer: String::new(),
+        path: SignaturePath::root(),
+    }
+    .value(signature, json)
+}
+
+/// Convert `value` into its JSON representation.
+///
+/// Non-finite floating point numbers have no JSON representation and become `null`.
+pub fn to_json(value: &Value) -> Result<Json, Error> {
+    Converter {
+        pointer: String::new(),
+        path: SignaturePath::root(),
+    }
+    .json(value)
+}
+
+struct Converter {
+    pointer: String,
+    path: SignaturePath,
+}
+
+impl Converter {
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error {
+            pointer: self.pointer.clone(),
+            path: self.path.clone(),
+            kind,
+        }
+    }
+
+    fn mismatch(&self, expected: &'static str) -> Error {
+        self.error(ErrorKind::TypeMismatch { expected })
+    }
+
+    /// Run `f` with `token` appended to the JSON pointer.
+    fn at<R>(&mut self, token: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        let len = self.pointer.len();
+        self.pointer.push('/');
+        self.pointer
+            .push_str(&token.replace('~', "~0").replace('/', "~1"));
+        let result = f(self);
+        self.pointer.truncate(len);
+
+        result
+    }
+
+    /// Convert `json` as the child at `token`/`segment`.
+    fn child(
+        &mut self,
+        token: &str,
+        segment: PathSegment,
+        signature: &Signature,
+        json: &Json,
+    ) -> Result<Value, Error> {
+        self.at(token, |c| {
+            c.path.push(segment);
+            let value = c.value(signature, json);
+            c.path.pop();
+
+            value
+        })
+    }
+
+    fn value(&mut self, signature: &Signature, json: &Json) -> Result<Value, Error> {
+        let value = match signature {
+            Signature::Unit => match json {
+                Json::Array(items) if items.is_empty() => Value::Unit,
+                _ => return Err(self.mismatch("an empty array")),
+            },
+            Signature::U8 => Value::U8(self.integer(json)?),
+            Signature::Bool => {
+                Value::Bool(json.as_bool().ok_or_else(|| self.mismatch("a boolean"))?)
+            }
+            Signature::I16 => Value::I16(self.integer(json)?),
+            Signature::U16 => Value::U16(self.integer(json)?),
+            Signature::I32 => Value::I32(self.integer(json)?),
+            Signature::U32 => Value::U32(self.integer(json)?),
+            Signature::I64 => Value::I64(self.decimal(json)?),
+            Signature::U64 => Value::U64(self.decimal(json)?),
+            #[cfg(unix)]
+            Signature::Fd => Value::Fd(self.integer(json)?),
+            Signature::F64 => Value::F64(json.as_f64().ok_or_else(|| self.mismatch("a number"))?),
+            Signature::Str => Value::Str(self.string(json)?.to_string()),
+            Signature::ObjectPath => {
+                let path = self.string(json)?;
+                if !is_valid_object_path(path) {
+                    return Err(self.error(ErrorKind::InvalidObjectPath));
+                }
+                Value::ObjectPath(path.to_string())
+            }
+            Signature::Signature => {
+                let signature = self
+                    .string(json)?
+                    .parse()
+                    .map_err(|_| self.error(ErrorKind::InvalidSignature))?;
+                Value::Signature(signature)
+            }
+            Signature::Value => {
+                let object = json.as_object().filter(|object| {
+                    object.len() == 2
+                        && object.contains_key("signature")
+                        && object.contains_key("value")
+                });
+                let object = object.ok_or_else(|| self.error(ErrorKind::InvalidVariant))?;
+                let signature: Signature = self.at("signature", |c| {
+                    let signature = c.string(&object["signature"])?;
+                    if !is_single_complete_type(signature) {
+                        return Err(c.error(ErrorKind::InvalidSignature));
+                    }
+
+                    Ok(signature.parse().unwrap())
+                })?;
+                let value =
+                    self.child("value", PathSegment::Variant, &signature, &object["value"])?;
+
+                Value::Variant(Box::new(value))
+            }
+            Signature::Array(element) => {
+                let items = json.as_array().ok_or_else(|| self.mismatch("an array"))?;
+                let items = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        self.child(&i.to_string(), PathSegment::Element, element, item)
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Value::Array {
+                    element: (**element).clone(),
+                    items,
+                }
+            }
+            Signature::Dict { key, value } if key.is_string_like() => {
+                let object = json.as_object().ok_or_else(|| self.mismatch("an object"))?;
+                let mut entries = Vec::with_capacity(object.len());
+                for (name, v) in object {
+                    let k = self
+                        .child(name, PathSegment::Key, key, &Json::String(name.clone()))
+                        .map_err(|e| Error {
+                            kind: ErrorKind::InvalidKey(name.clone()),
+                            ..e
+                        })?;
+                    let v = self.child(name, PathSegment::Value, value, v)?;
+                    entries.push((k, v));
+                }
+
+                Value::Dict {
+                    key: (**key).clone(),
+                    value: (**value).clone(),
+                    entries,
+                }
+            }
+            Signature::Dict { key, value } => {
+                let pairs = json.as_array().ok_or_else(|| self.mismatch("an array"))?;
+                let mut entries = Vec::with_capacity(pairs.len());
+                for (i, pair) in pairs.iter().enumerate() {
+                    let i = i.to_string();
+                    let pair = match pair.as_array() {
+                        Some(pair) if pair.len() == 2 => pair,
+                        _ => {
+                            return Err(Error {
+                                pointer: format!("{}/{}", self.pointer, i),
+                                ..self.mismatch("a [key, value] pair")
+                            })
+                        }
+                    };
+                    let entry = self.at(&i, |c| {
+                        let k = c.child("0", PathSegment::Key, key, &pair[0])?;
+                        let v = c.child("1", PathSegment::Value, value, &pair[1])?;
+
+                        Ok((k, v))
+                    })?;
+                    entries.push(entry);
+                }
+
+                Value::Dict {
+                    key: (**key).clone(),
+                    value: (**value).clone(),
+                    entries,
+                }
+            }
+            Signature::Structure(fields) => {
+                let items = json.as_array().ok_or_else(|| self.mismatch("an array"))?;
+                let fields: Vec<_> = fields.iter().collect();
+                if items.len() != fields.len() {
+                    return Err(self.error(ErrorKind::WrongLength {
+                        expected: fields.len(),
+                        found: items.len(),
+                    }));
+                }
+                let fields = fields
+                    .into_iter()
+                    .zip(items)
+                    .enumerate()
+                    .map(|(i, (field, item))| {
+                        self.child(&i.to_string(), PathSegment::Field(i), field, item)
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Value::Structure(fields)
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) if matches!(**child, Signature::Maybe(_)) => {
+                let value = match json.as_array().map(Vec::as_slice) {
+                    Some([]) => None,
+                    Some([json]) => {
+                        let value = self.child("0", PathSegment::Maybe, child, json)?;
+                        Some(Box::new(value))
+                    }
+                    _ => return Err(self.mismatch("an array of at most one element")),
+                };
+
+                Value::Maybe {
+                    child: (**child).clone(),
+                    value,
+                }
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => {
+                let value = match json {
+                    Json::Null => None,
+                    _ => {
+                        self.path.push(PathSegment::Maybe);
+                        let value = self.value(child, json);
+                        self.path.pop();
+                        Some(Box::new(value?))
+                    }
+                };
+
+                Value::Maybe {
+                    child: (**child).clone(),
+                    value,
+                }
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn json(&mut self, value: &Value) -> Result<Json, Error> {
+        let json = match value {
+            Value::Unit => Json::Array(vec![]),
+            Value::U8(n) => (*n).into(),
+            Value::Bool(b) => (*b).into(),
+            Value::I16(n) => (*n).into(),
+            Value::U16(n) => (*n).into(),
+            Value::I32(n) => (*n).into(),
+            Value::U32(n) => (*n).into(),
+            Value::I64(n) => n.to_string().into(),
+            Value::U64(n) => n.to_string().into(),
+            Value::F64(n) => Number::from_f64(*n).map_or(Json::Null, Json::Number),
+            #[cfg(unix)]
+            Value::Fd(n) => (*n).into(),
+            Value::Str(s) | Value::ObjectPath(s) => s.as_str().into(),
+            Value::Signature(signature) => signature.to_string().into(),
+            Value::Variant(value) => {
+                let mut object = Map::new();
+                object.insert(
+                    "signature".to_string(),
+                    value.signature().to_string().into(),
+                );
+                let json = self.child_json("value", PathSegment::Variant, value)?;
+                object.insert("value".to_string(), json);
+
+                Json::Object(object)
+            }
+            Value::Array { items, .. } => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| self.child_json(&i.to_string(), PathSegment::Element, item))
+                .collect::<Result<_, _>>()?,
+            Value::Dict { key, entries, .. } if key.is_string_like() => {
+                let mut object = Map::new();
+                for (k, v) in entries {
+                    let k = match k {
+                        Value::Str(s) | Value::ObjectPath(s) => s.clone(),
+                        Value::Signature(signature) => signature.to_string(),
+                        _ => {
+                            self.path.push(PathSegment::Key);
+                            let e = self.error(ErrorKind::KeyTypeMismatch);
+                            self.path.pop();
+
+                            return Err(e);
+                        }
+                    };
+                    let v = self.child_json(&k, PathSegment::Value, v)?;
+                    object.insert(k, v);
+                }
+
+                Json::Object(object)
+            }
+            Value::Dict { entries, .. } => entries
+                .iter()
+                .enumerate()
+                .map(|(i, (k, v))| {
+                    self.at(&i.to_string(), |c| {
+                        let k = c.child_json("0", PathSegment::Key, k)?;
+                        let v = c.child_json("1", PathSegment::Value, v)?;
+
+                        Ok(Json::Array(vec![k, v]))
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            Value::Structure(fields) => fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| self.child_json(&i.to_string(), PathSegment::Field(i), field))
+                .collect::<Result<_, _>>()?,
+            #[cfg(feature = "gvariant")]
+            Value::Maybe { child, value } => {
+                self.path.push(PathSegment::Maybe);
+                let json = match value {
+                    Some(value) if matches!(child, Signature::Maybe(_)) => self
+                        .at("0", |c| c.json(value))
+                        .map(|json| Json::Array(vec![json])),
+                    Some(value) => self.json(value),
+                    None if matches!(child, Signature::Maybe(_)) => Ok(Json::Array(vec![])),
+                    None => Ok(Json::Null),
+                };
+                self.path.pop();
+
+                json?
+            }
+        };
+
+        Ok(json)
+    }
+
+    /// Convert `value` as the child at `token`/`segment`.
+    fn child_json(
+        &mut self,
+        token: &str,
+        segment: PathSegment,
+        value: &Value,
+    ) -> Result<Json, Error> {
+        self.at(token, |c| {
+            c.path.push(segment);
+            let json = c.json(value);
+            c.path.pop();
+
+            json
+        })
+    }
+
+    /// Parse a decimal string, as `x` and `t` values are represented.
+    fn decimal<T: FromStr>(&self, json: &Json) -> Result<T, Error> {
+        let s = json
+            .as_str()
+            .filter(|s| is_decimal(s))
+            .ok_or_else(|| self.mismatch("a decimal string"))?;
+
+        s.parse().map_err(|_| self.error(ErrorKind::OutOfRange))
+    }
+
+    fn integer<T>(&self, json: &Json) -> Result<T, Error>
+    where
+        T: TryFrom<i64> + TryFrom<u64>,
+    {
+        let number = match json {
+            Json::Number(number) => number,
+            _ => return Err(self.mismatch("an integer")),
+        };
+        let converted = match (number.as_i64(), number.as_u64()) {
+            (Some(n), _) => T::try_from(n).ok(),
+            (None, Some(n)) => T::try_from(n).ok(),
+            (None, None) => None,
+        };
+
+        converted.ok_or_else(|| self.error(ErrorKind::OutOfRange))
+    }
+
+    fn string<'j>(&self, json: &'j Json) -> Result<&'j str, Error> {
+        json.as_str().ok_or_else(|| self.mismatch("a string"))
+    }
+}
+
+/// Whether `s` matches the pattern of `x` and `t` values in the JSON schema.
+fn is_decimal(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    fn round_trip(signature: &str, json: Json) {
+        let value = from_json(&sig(signature), &json).unwrap();
+        assert_eq!(to_json(&value).unwrap(), json, "{}", signature);
+    }
+
+    fn error(signature: &str, json: Json) -> (String, String, ErrorKind) {
+        let e = from_json(&sig(signature), &json).unwrap_err();
+
+        (
+            e.pointer().to_string(),
+            e.path().to_string(),
+            e.kind().clone(),
+        )
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip("y", json!(255));
+        round_trip("x", json!(i64::MIN.to_string()));
+        round_trip("t", json!(u64::MAX.to_string()));
+        round_trip("x", json!("0"));
+        round_trip("d", json!(1.5));
+        round_trip("(sbo)", json!(["a", true, "/org/a"]));
+        round_trip(
+            "a{sv}",
+            json!({ "a": { "signature": "au", "value": [1, 2] } }),
+        );
+        round_trip("a{ub}", json!([[1, true], [2, false]]));
+        round_trip("a{ga(ii)}", json!({ "ai": [[1, -1]] }));
+        round_trip("g", json!("a{sv}"));
+        assert_eq!(
+            from_json(&Signature::Unit, &json!([])).unwrap(),
+            Value::Unit
+        );
+        assert_eq!(to_json(&Value::F64(f64::NAN)).unwrap(), Json::Null);
+    }
+
+    #[test]
+    fn rejections() {
+        let range = ErrorKind::OutOfRange;
+        assert_eq!(
+            error("y", json!(256)),
+            ("".into(), "$".into(), range.clone())
+        );
+        assert_eq!(error("n", json!(-32769)).2, range);
+        assert_eq!(error("q", json!(-1)).2, range);
+        assert_eq!(error("i", json!(1.5)).2, range);
+        assert_eq!(error("u", json!(4294967296u64)).2, range);
+        assert_eq!(error("x", json!(u64::MAX.to_string())).2, range);
+        assert_eq!(error("t", json!("-1")).2, range);
+        let decimal = ErrorKind::TypeMismatch {
+            expected: "a decimal string",
+        };
+        assert_eq!(error("t", json!(1)).2, decimal);
+        assert_eq!(error("x", json!("01")).2, decimal);
+        assert_eq!(error("x", json!("+1")).2, decimal);
+        assert_eq!(
+            error("(sai)", json!(["a", [1, "2"]])),
+            (
+                "/1/1".into(),
+                "$.1[]".into(),
+                ErrorKind::TypeMismatch {
+                    expected: "an integer"
+                }
+            )
+        );
+        assert_eq!(
+            error("a{so}", json!({ "a/b": "/x/" })),
+            (
+                "/a~1b".into(),
+                "${value}".into(),
+                ErrorKind::InvalidObjectPath
+            )
+        );
+        assert_eq!(
+            error("a{ou}", json!({ "x": 1 })),
+            (
+                "/x".into(),
+                "${key}".into(),
+                ErrorKind::InvalidKey("x".into())
+            )
+        );
+        assert_eq!(
+            error("a{uu}", json!([[1, 2], [3, -4]])),
+            ("/1/1".into(), "${value}".into(), range.clone())
+        );
+        assert_eq!(
+            error("(ii)", json!([1])).2,
+            ErrorKind::WrongLength {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(error("g", json!("a{")).2, ErrorKind::InvalidSignature);
+        assert_eq!(error("v", json!(1)).2, ErrorKind::InvalidVariant);
+        assert_eq!(
+            error("v", json!({ "signature": "ss", "value": 1 })),
+            ("/signature".into(), "$".into(), ErrorKind::InvalidSignature)
+        );
+        assert_eq!(
+            error("av", json!([{ "signature": "(y)", "value": [300] }])),
+            ("/0/value/0".into(), "$[]<>.0".into(), range)
+        );
+    }
+
+    #[test]
+    fn key_type_mismatch() {
+        let value = Value::Array {
+            element: "a{sy}".parse().unwrap(),
+            items: vec![Value::Dict {
+                key: Signature::Str,
+                value: Signature::U8,
+                entries: vec![(Value::U8(1), Value::U8(2))],
+            }],
+        };
+        let e = to_json(&value).unwrap_err();
+        assert_eq!(
+            (e.pointer(), e.path().to_string().as_str(), e.kind()),
+            ("/0", "$[]{key}", &ErrorKind::KeyTypeMismatch)
+        );
+    }
+
+    #[cfg(feature = "gvariant")]
+    #[test]
+    fn maybe() {
+        round_trip("amy", json!([1, null]));
+        round_trip("ammy", json!([[], [null], [1]]));
+        assert_eq!(
+            error("mmy", json!(null)).2,
+            ErrorKind::TypeMismatch {
+                expected: "an array of at most one element"
+            }
+        );
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod codegen;
+pub mod dynamic_type;
+pub mod encoding;
+#[cfg(feature = "gvariant")]
+pub mod gvariant_text;
+pub mod introspection;
+pub mod json;
+pub mod message;
+pub mod names;
+pub mod signature;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod structure;
+pub mod r#type;
+pub mod value;
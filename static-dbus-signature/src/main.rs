@@ -1,14 +1,7 @@
-#![allow(dead_code, unused)]
+mod cli;
 
-mod dynamic_type;
-mod signature;
-mod structure;
-mod r#type;
+use std::process::ExitCode;
 
-use structure::Structure;
-
-fn main() {
-    //let sig = Structure::new().field::<i32>().field::<&[&[i32]]>();
-
-    //println!("{}", sig.as_str());
+fn main() -> ExitCode {
+    cli::run(std::env::args().skip(1))
 }
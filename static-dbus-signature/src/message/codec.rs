@@ -0,0 +1,99 @@
+use super::{message_len, Error, Message};
+
+/// Frames messages over a byte stream.
+///
+/// Bytes are fed in as they are read, in chunks of any size, and complete messages are taken out
+/// once all of their bytes arrived. Partial messages stay buffered until then.
+#[derive(Debug, Clone, Default)]
+pub struct MessageCodec {
+    buffer: Vec<u8>,
+}
+
+impl MessageCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `bytes` read from the stream.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The number of buffered bytes not yet returned as messages.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The next complete message, or `None` if more bytes are needed.
+    ///
+    /// After an error the stream can't be resynchronized, as message boundaries are unknown.
+    pub fn decode(&mut self) -> Result<Option<Message>, Error> {
+        let len = match message_len(&self.buffer)? {
+            Some(len) if len <= self.buffer.len() => len,
+            _ => return Ok(None),
+        };
+        let message = Message::from_bytes(&self.buffer[..len]);
+        self.buffer.drain(..len);
+
+        message.map(Some)
+    }
+
+    /// Append the encoding of `message` to `bytes`.
+    pub fn encode(&self, message: &Message, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        bytes.extend(message.to_bytes()?);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Endian;
+    use crate::message::MessageBuilder;
+    use crate::names::MemberName;
+    use crate::value::Value;
+
+    #[test]
+    fn incremental() {
+        let mut codec = MessageCodec::new();
+        let mut stream = vec![];
+        for serial in 1..=3 {
+            let message = MessageBuilder::method_call("/a", MemberName::try_from("M").unwrap())
+                .unwrap()
+                .endian(Endian::Big)
+                .build(serial, &Value::Str("x".repeat(serial as usize)))
+                .unwrap();
+            codec.encode(&message, &mut stream).unwrap();
+        }
+
+        // Feed a byte at a time, messages come out once complete.
+        let mut serials = vec![];
+        for (i, byte) in stream.iter().enumerate() {
+            codec.feed(&[*byte]);
+            if let Some(message) = codec.decode().unwrap() {
+                serials.push((i, message.serial()));
+            }
+        }
+        assert_eq!(serials.last(), Some(&(stream.len() - 1, 3)));
+        assert_eq!(
+            serials.iter().map(|&(_, s)| s).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(codec.buffered(), 0);
+
+        // Or all at once.
+        codec.feed(&stream);
+        for serial in 1..=3 {
+            assert_eq!(codec.decode().unwrap().unwrap().serial(), serial);
+        }
+        assert!(codec.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_length() {
+        let mut codec = MessageCodec::new();
+        codec.feed(&[b'l', 1, 0, 1, 0, 0, 0, 0x10, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(matches!(codec.decode(), Err(Error::TooLong(_))));
+    }
+}
@@ -0,0 +1,726 @@
+//! D-Bus messages and their framing.
+//!
+//! A message is a fixed header, an array of header fields and a body, each encoded with
+//! [`crate::encoding::dbus`]. The header is the structure `(yyyyuua(yv))`: the endianness, the
+//! message type, flags, the protocol version, the body length, the serial and the header fields,
+//! after which the body starts at the next multiple of 8.
+//!
+//! The body signature follows the convention of [`InterfaceBuilder`]: a structure is a list of
+//! arguments, `()` means no arguments and any other type is a single argument.
+//!
+//! [`InterfaceBuilder`]: crate::introspection::InterfaceBuilder
+
+mod codec;
+pub use codec::MessageCodec;
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::BitOr;
+
+use crate::dynamic_type::DynamicType;
+use crate::encoding::dbus::{from_bytes, to_bytes};
+use crate::encoding::{self, BodyView, Endian, MAX_DBUS_ARRAY_LEN};
+use crate::names::{
+    BusName, ErrorName, InterfaceName, MemberName, NameError, OwnedBusName, OwnedErrorName,
+    OwnedInterfaceName, OwnedMemberName,
+};
+use crate::signature::{Signature, MAX_DBUS_LEN};
+use crate::value::{is_valid_object_path, Value};
+
+/// The maximum length (in bytes) of a message, including its header.
+pub const MAX_MESSAGE_LEN: usize = 1 << 27;
+
+/// The version of the D-Bus protocol this implements.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// The length of the fixed part of the header, up to and including the length of the header
+/// fields array.
+const PREFIX_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+impl MessageType {
+    fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            1 => Some(MessageType::MethodCall),
+            2 => Some(MessageType::MethodReturn),
+            3 => Some(MessageType::Error),
+            4 => Some(MessageType::Signal),
+            _ => None,
+        }
+    }
+}
+
+/// The flags of a message.
+///
+/// Unknown flags are kept as they are, as the specification requires them to be ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags(u8);
+
+impl Flags {
+    pub const NO_REPLY_EXPECTED: Flags = Flags(0x1);
+    pub const NO_AUTO_START: Flags = Flags(0x2);
+    pub const ALLOW_INTERACTIVE_AUTHORIZATION: Flags = Flags(0x4);
+
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, other: Flags) -> Flags {
+        Flags(self.0 | other.0)
+    }
+}
+
+/// The codes of the header fields.
+mod field {
+    pub const PATH: u8 = 1;
+    pub const INTERFACE: u8 = 2;
+    pub const MEMBER: u8 = 3;
+    pub const ERROR_NAME: u8 = 4;
+    pub const REPLY_SERIAL: u8 = 5;
+    pub const DESTINATION: u8 = 6;
+    pub const SENDER: u8 = 7;
+    pub const SIGNATURE: u8 = 8;
+    pub const UNIX_FDS: u8 = 9;
+}
+
+/// Error returned when building, encoding or decoding messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The header or the body isn't correctly encoded.
+    Encoding(encoding::Error),
+    /// The first byte is neither `l` nor `B`.
+    InvalidEndian(u8),
+    UnknownType(u8),
+    UnsupportedVersion(u8),
+    /// The message is longer than [`MAX_MESSAGE_LEN`].
+    TooLong(usize),
+    /// Serials must not be zero.
+    ZeroSerial,
+    /// A header field required by the message type is missing.
+    MissingField(&'static str),
+    /// A header field has the wrong type, given by its code.
+    InvalidFieldType(u8),
+    InvalidName(NameError),
+    InvalidObjectPath,
+    /// The body signature is invalid or longer than 255 bytes.
+    InvalidBodySignature,
+}
+
+impl From<encoding::Error> for Error {
+    fn from(e: encoding::Error) -> Self {
+        Error::Encoding(e)
+    }
+}
+
+impl From<NameError> for Error {
+    fn from(e: NameError) -> Self {
+        Error::InvalidName(e)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Error::Encoding(e) => e.fmt(f),
+            Error::InvalidEndian(b) => write!(f, "invalid endianness byte {:#04x}", b),
+            Error::UnknownType(t) => write!(f, "unknown message type {}", t),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported protocol version {}", v),
+            Error::TooLong(len) => write!(f, "message of {} bytes is too long", len),
+            Error::ZeroSerial => write!(f, "message serial is zero"),
+            Error::MissingField(name) => write!(f, "missing `{}` header field", name),
+            Error::InvalidFieldType(code) => write!(f, "header field {} has the wrong type", code),
+            Error::InvalidName(e) => e.fmt(f),
+            Error::InvalidObjectPath => write!(f, "invalid object path"),
+            Error::InvalidBodySignature => write!(f, "invalid body signature"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A D-Bus message, with its body kept encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    endian: Endian,
+    message_type: MessageType,
+    flags: Flags,
+    serial: u32,
+    path: Option<String>,
+    interface: Option<OwnedInterfaceName>,
+    member: Option<OwnedMemberName>,
+    error_name: Option<OwnedErrorName>,
+    reply_serial: Option<u32>,
+    destination: Option<OwnedBusName>,
+    sender: Option<OwnedBusName>,
+    body_signature: Signature,
+    unix_fds: Option<u32>,
+    body: Vec<u8>,
+}
+
+impl Message {
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        self.message_type
+    }
+
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn interface(&self) -> Option<&InterfaceName<'static>> {
+        self.interface.as_ref()
+    }
+
+    pub fn member(&self) -> Option<&MemberName<'static>> {
+        self.member.as_ref()
+    }
+
+    pub fn error_name(&self) -> Option<&ErrorName<'static>> {
+        self.error_name.as_ref()
+    }
+
+    pub fn reply_serial(&self) -> Option<u32> {
+        self.reply_serial
+    }
+
+    pub fn destination(&self) -> Option<&BusName<'static>> {
+        self.destination.as_ref()
+    }
+
+    pub fn sender(&self) -> Option<&BusName<'static>> {
+        self.sender.as_ref()
+    }
+
+    pub fn body_signature(&self) -> &Signature {
+        &self.body_signature
+    }
+
+    /// The number of file descriptors passed along with the message.
+    pub fn unix_fds(&self) -> Option<u32> {
+        self.unix_fds
+    }
+
+    /// The encoded body.
+    pub fn body_bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// A lazy view of the body, to read parts of it without decoding it all.
+    pub fn body(&self) -> BodyView<'_> {
+        BodyView::new(self.body_signature.clone(), &self.body, self.endian)
+    }
+
+    pub fn decode_body(&self) -> Result<Value, Error> {
+        Ok(from_bytes(&self.body_signature, &self.body, self.endian)?)
+    }
+
+    /// Encode the whole message.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let prefix = Value::Structure(vec![
+            Value::U8(endian_byte(self.endian)),
+            Value::U8(self.message_type as u8),
+            Value::U8(self.flags.bits()),
+            Value::U8(PROTOCOL_VERSION),
+            Value::U32(self.body.len() as u32),
+            Value::U32(self.serial),
+        ]);
+        let mut bytes = to_bytes(&prefix, self.endian)?;
+
+        // Each `(yv)` entry is 8-byte aligned, so they can be encoded on their own and appended.
+        let mut fields = Vec::new();
+        for (code, value) in self.fields() {
+            fields.resize(fields.len().next_multiple_of(8), 0);
+            let entry = Value::Structure(vec![Value::U8(code), Value::Variant(Box::new(value))]);
+            fields.extend(to_bytes(&entry, self.endian)?);
+        }
+        let signature = body_signature_string(&self.body_signature);
+        if !signature.is_empty() {
+            // Written by hand, as the top-level structure of the arguments has no parentheses.
+            fields.resize(fields.len().next_multiple_of(8), 0);
+            fields.extend([field::SIGNATURE, 1, b'g', 0, signature.len() as u8]);
+            fields.extend(signature.as_bytes());
+            fields.push(0);
+        }
+
+        bytes.extend(self.endian.u32_bytes(fields.len() as u32));
+        bytes.extend(fields);
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+        bytes.extend(&self.body);
+        if bytes.len() > MAX_MESSAGE_LEN {
+            return Err(Error::TooLong(bytes.len()));
+        }
+
+        Ok(bytes)
+    }
+
+    /// Decode a message from `bytes`, which must hold exactly one message.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let len = match message_len(bytes)? {
+            Some(len) if len <= bytes.len() => len,
+            _ => {
+                let e = encoding::Error::new(bytes.len(), encoding::ErrorKind::UnexpectedEnd);
+                return Err(e.into());
+            }
+        };
+        if len < bytes.len() {
+            let e = encoding::Error::new(len, encoding::ErrorKind::TrailingBytes);
+            return Err(e.into());
+        }
+
+        let endian = match bytes[0] {
+            b'l' => Endian::Little,
+            _ => Endian::Big,
+        };
+        let message_type = MessageType::from_u8(bytes[1]).ok_or(Error::UnknownType(bytes[1]))?;
+        if bytes[3] != PROTOCOL_VERSION {
+            return Err(Error::UnsupportedVersion(bytes[3]));
+        }
+        let body_len = endian.read_u32(bytes[4..8].try_into().unwrap()) as usize;
+        let header_len = len - body_len;
+        let fields_end = PREFIX_LEN + endian.read_u32(bytes[12..16].try_into().unwrap()) as usize;
+        if let Some(i) = bytes[fields_end..header_len].iter().position(|&b| b != 0) {
+            let e = encoding::Error::new(fields_end + i, encoding::ErrorKind::NonZeroPadding);
+            return Err(e.into());
+        }
+
+        let mut message = Message {
+            endian,
+            message_type,
+            flags: Flags(bytes[2]),
+            serial: endian.read_u32(bytes[8..12].try_into().unwrap()),
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body_signature: Signature::Unit,
+            unix_fds: None,
+            body: bytes[header_len..].to_vec(),
+        };
+        let header: Signature = "(yyyyuua(yv))".parse().unwrap();
+        let header = BodyView::new(header, &bytes[..fields_end], endian);
+        for entry in header.field(6)?.elements()? {
+            let entry = entry?;
+            let code = entry.field(0)?.as_u8()?;
+            let value = entry.field(1)?.variant()?;
+            message.set_field(code, value)?;
+        }
+        message.check()?;
+
+        Ok(message)
+    }
+
+    fn set_field(&mut self, code: u8, value: BodyView) -> Result<(), Error> {
+        let expected = match code {
+            field::PATH => Signature::ObjectPath,
+            field::REPLY_SERIAL | field::UNIX_FDS => Signature::U32,
+            field::SIGNATURE => Signature::Signature,
+            field::INTERFACE | field::MEMBER | field::ERROR_NAME => Signature::Str,
+            field::DESTINATION | field::SENDER => Signature::Str,
+            // Unknown fields must be ignored.
+            _ => return Ok(()),
+        };
+        if *value.signature() != expected {
+            return Err(Error::InvalidFieldType(code));
+        }
+
+        match code {
+            field::PATH => self.path = Some(value.as_str()?.to_string()),
+            field::INTERFACE => self.interface = Some(value.as_str()?.parse()?),
+            field::MEMBER => self.member = Some(value.as_str()?.parse()?),
+            field::ERROR_NAME => self.error_name = Some(value.as_str()?.parse()?),
+            field::REPLY_SERIAL => self.reply_serial = Some(value.as_u32()?),
+            field::DESTINATION => self.destination = Some(value.as_str()?.parse()?),
+            field::SENDER => self.sender = Some(value.as_str()?.parse()?),
+            field::SIGNATURE => self.body_signature = parse_body_signature(value.as_str()?)?,
+            field::UNIX_FDS => self.unix_fds = Some(value.as_u32()?),
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// The header fields other than the body signature.
+    fn fields(&self) -> Vec<(u8, Value)> {
+        let name = |code, name: Option<&str>| name.map(|s| (code, Value::Str(s.to_string())));
+
+        [
+            self.path
+                .clone()
+                .map(|path| (field::PATH, Value::ObjectPath(path))),
+            name(field::INTERFACE, self.interface.as_deref()),
+            name(field::MEMBER, self.member.as_deref()),
+            name(field::ERROR_NAME, self.error_name.as_deref()),
+            self.reply_serial
+                .map(|serial| (field::REPLY_SERIAL, Value::U32(serial))),
+            name(field::DESTINATION, self.destination.as_deref()),
+            name(field::SENDER, self.sender.as_deref()),
+            self.unix_fds.map(|n| (field::UNIX_FDS, Value::U32(n))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Check the header fields required by the message type.
+    fn check(&self) -> Result<(), Error> {
+        if self.serial == 0 {
+            return Err(Error::ZeroSerial);
+        }
+        let required = match self.message_type {
+            MessageType::MethodCall => &[field::PATH, field::MEMBER][..],
+            MessageType::MethodReturn => &[field::REPLY_SERIAL],
+            MessageType::Error => &[field::ERROR_NAME, field::REPLY_SERIAL],
+            MessageType::Signal => &[field::PATH, field::INTERFACE, field::MEMBER],
+        };
+        for &code in required {
+            let (present, name) = match code {
+                field::PATH => (self.path.is_some(), "path"),
+                field::INTERFACE => (self.interface.is_some(), "interface"),
+                field::MEMBER => (self.member.is_some(), "member"),
+                field::ERROR_NAME => (self.error_name.is_some(), "error name"),
+                _ => (self.reply_serial.is_some(), "reply serial"),
+            };
+            if !present {
+                return Err(Error::MissingField(name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds a [`Message`] of one of the four types.
+#[derive(Debug, Clone)]
+pub struct MessageBuilder {
+    message: Message,
+}
+
+impl MessageBuilder {
+    fn new(message_type: MessageType) -> Self {
+        Self {
+            message: Message {
+                endian: Endian::NATIVE,
+                message_type,
+                flags: Flags::empty(),
+                serial: 0,
+                path: None,
+                interface: None,
+                member: None,
+                error_name: None,
+                reply_serial: None,
+                destination: None,
+                sender: None,
+                body_signature: Signature::Unit,
+                unix_fds: None,
+                body: vec![],
+            },
+        }
+    }
+
+    pub fn method_call(path: &str, member: MemberName) -> Result<Self, Error> {
+        Self::new(MessageType::MethodCall)
+            .path(path)
+            .map(|builder| builder.member(member))
+    }
+
+    pub fn signal(path: &str, interface: InterfaceName, member: MemberName) -> Result<Self, Error> {
+        Self::new(MessageType::Signal)
+            .path(path)
+            .map(|builder| builder.interface(interface).member(member))
+    }
+
+    pub fn method_return(reply_serial: u32) -> Self {
+        Self::new(MessageType::MethodReturn).reply_serial(reply_serial)
+    }
+
+    pub fn error(error_name: ErrorName, reply_serial: u32) -> Self {
+        let mut builder = Self::new(MessageType::Error).reply_serial(reply_serial);
+        builder.message.error_name = Some(error_name.into_owned());
+
+        builder
+    }
+
+    pub fn path(mut self, path: &str) -> Result<Self, Error> {
+        if !is_valid_object_path(path) {
+            return Err(Error::InvalidObjectPath);
+        }
+        self.message.path = Some(path.to_string());
+
+        Ok(self)
+    }
+
+    pub fn interface(mut self, interface: InterfaceName) -> Self {
+        self.message.interface = Some(interface.into_owned());
+
+        self
+    }
+
+    pub fn member(mut self, member: MemberName) -> Self {
+        self.message.member = Some(member.into_owned());
+
+        self
+    }
+
+    pub fn reply_serial(mut self, serial: u32) -> Self {
+        self.message.reply_serial = Some(serial);
+
+        self
+    }
+
+    pub fn destination(mut self, destination: BusName) -> Self {
+        self.message.destination = Some(destination.into_owned());
+
+        self
+    }
+
+    pub fn sender(mut self, sender: BusName) -> Self {
+        self.message.sender = Some(sender.into_owned());
+
+        self
+    }
+
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.message.flags = flags;
+
+        self
+    }
+
+    pub fn unix_fds(mut self, fds: u32) -> Self {
+        self.message.unix_fds = Some(fds);
+
+        self
+    }
+
+    /// Encode the message in `endian` rather than the native byte order.
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.message.endian = endian;
+
+        self
+    }
+
+    /// Build the message with the given serial, encoding `body` as its arguments.
+    pub fn build(mut self, serial: u32, body: &Value) -> Result<Message, Error> {
+        let signature = body.signature();
+        if body_signature_string(&signature).len() > MAX_DBUS_LEN {
+            return Err(Error::InvalidBodySignature);
+        }
+        self.message.serial = serial;
+        self.message.body = to_bytes(body, self.message.endian)?;
+        self.message.body_signature = signature;
+        self.message.check()?;
+
+        Ok(self.message)
+    }
+}
+
+/// The total length of the message starting at `bytes`, or `None` if the fixed part of its header
+/// isn't complete yet.
+pub fn message_len(bytes: &[u8]) -> Result<Option<usize>, Error> {
+    let Some(prefix) = bytes.get(..PREFIX_LEN) else {
+        return Ok(None);
+    };
+    let endian = match prefix[0] {
+        b'l' => Endian::Little,
+        b'B' => Endian::Big,
+        b => return Err(Error::InvalidEndian(b)),
+    };
+
+    let fields_len = endian.read_u32(prefix[12..16].try_into().unwrap()) as usize;
+    if fields_len > MAX_DBUS_ARRAY_LEN {
+        let kind = encoding::ErrorKind::ArrayTooLong(fields_len);
+        return Err(encoding::Error::new(12, kind).into());
+    }
+    let body_len = endian.read_u32(prefix[4..8].try_into().unwrap()) as usize;
+    let len = (PREFIX_LEN + fields_len).next_multiple_of(8) + body_len;
+    if len > MAX_MESSAGE_LEN {
+        return Err(Error::TooLong(len));
+    }
+
+    Ok(Some(len))
+}
+
+fn endian_byte(endian: Endian) -> u8 {
+    match endian {
+        Endian::Little => b'l',
+        Endian::Big => b'B',
+    }
+}
+
+/// The signature of the arguments, without the parentheses of a top-level structure.
+fn body_signature_string(signature: &Signature) -> String {
+    match signature {
+        Signature::Structure(fields) => fields.iter().map(|field| field.to_string()).collect(),
+        signature => signature.to_string(),
+    }
+}
+
+/// The inverse of [`body_signature_string`].
+fn parse_body_signature(s: &str) -> Result<Signature, Error> {
+    let signature: Signature = s.parse().map_err(|_| Error::InvalidBodySignature)?;
+
+    // `(su)` parses to the same structure as `su`, but is a single argument.
+    Ok(match signature {
+        Signature::Structure(_) if signature.string_len() == s.len() => {
+            Signature::Structure(vec![signature].into())
+        }
+        signature => signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello() -> Message {
+        let member = MemberName::try_from("Hello").unwrap();
+
+        MessageBuilder::method_call("/org/freedesktop/DBus", member)
+            .unwrap()
+            .interface(InterfaceName::try_from("org.freedesktop.DBus").unwrap())
+            .destination(BusName::try_from("org.freedesktop.DBus").unwrap())
+            .endian(Endian::Little)
+            .build(1, &Value::Unit)
+            .unwrap()
+    }
+
+    #[test]
+    fn method_call() {
+        let bytes = hello().to_bytes().unwrap();
+        assert_eq!(
+            bytes[..16],
+            [b'l', 1, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0x6d, 0, 0, 0]
+        );
+        assert_eq!(bytes.len(), 128);
+        // The path field.
+        assert_eq!(bytes[16..24], [1, 1, b'o', 0, 21, 0, 0, 0]);
+
+        let message = Message::from_bytes(&bytes).unwrap();
+        assert_eq!(message, hello());
+        assert_eq!(message.member().unwrap(), "Hello");
+        assert_eq!(message.body_signature(), &Signature::Unit);
+    }
+
+    #[test]
+    fn body() {
+        let args = Value::Structure(vec![Value::Str("a".to_string()), Value::U32(7)]);
+        for endian in [Endian::Little, Endian::Big] {
+            let reply = MessageBuilder::method_return(3)
+                .sender(BusName::try_from(":1.42").unwrap())
+                .endian(endian)
+                .build(9, &args)
+                .unwrap();
+            let bytes = reply.to_bytes().unwrap();
+            // Arguments are listed without parentheses.
+            assert!(bytes.windows(4).any(|w| w == b"\x02su\0"));
+            assert_eq!((bytes.len() - reply.body_bytes().len()) % 8, 0);
+
+            let decoded = Message::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.decode_body().unwrap(), args);
+            assert_eq!(decoded.body().field(1).unwrap().as_u32().unwrap(), 7);
+            assert_eq!(decoded.reply_serial(), Some(3));
+            assert!(decoded.sender().unwrap().is_unique());
+        }
+
+        // A single structure argument keeps its parentheses.
+        let arg = Value::Structure(vec![args.clone()]);
+        let signal = MessageBuilder::signal(
+            "/",
+            InterfaceName::try_from("org.example.Iface").unwrap(),
+            MemberName::try_from("Changed").unwrap(),
+        )
+        .unwrap()
+        .build(1, &arg)
+        .unwrap();
+        let bytes = signal.to_bytes().unwrap();
+        assert!(bytes.windows(6).any(|w| w == b"\x04(su)\0"));
+        assert_eq!(
+            Message::from_bytes(&bytes).unwrap().decode_body().unwrap(),
+            arg
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let member = || MemberName::try_from("Ping").unwrap();
+        assert_eq!(
+            MessageBuilder::method_call("not/a/path", member()).unwrap_err(),
+            Error::InvalidObjectPath
+        );
+        assert_eq!(
+            MessageBuilder::method_call("/", member())
+                .unwrap()
+                .build(0, &Value::Unit)
+                .unwrap_err(),
+            Error::ZeroSerial
+        );
+
+        let bytes = hello().to_bytes().unwrap();
+        let mut invalid = bytes.clone();
+        invalid[0] = b'x';
+        assert_eq!(
+            Message::from_bytes(&invalid).unwrap_err(),
+            Error::InvalidEndian(b'x')
+        );
+        let mut invalid = bytes.clone();
+        invalid[3] = 2;
+        assert_eq!(
+            Message::from_bytes(&invalid).unwrap_err(),
+            Error::UnsupportedVersion(2)
+        );
+        // Turn the member field into an unknown one.
+        let member = bytes.windows(4).position(|w| w == [3, 1, b's', 0]).unwrap();
+        let mut invalid = bytes.clone();
+        invalid[member] = 42;
+        assert_eq!(
+            Message::from_bytes(&invalid).unwrap_err(),
+            Error::MissingField("member")
+        );
+        // Make the path field a string.
+        let mut invalid = bytes.clone();
+        invalid[18] = b's';
+        assert_eq!(
+            Message::from_bytes(&invalid).unwrap_err(),
+            Error::InvalidFieldType(1)
+        );
+        assert!(matches!(
+            Message::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::Encoding(_))
+        ));
+    }
+}
@@ -0,0 +1,417 @@
+//! Validated D-Bus names: bus, interface, member, error and property names.
+//!
+//! Each name type wraps a [`Cow<str>`], so it can borrow the string it was validated from or own
+//! it, with an `Owned*` alias for the `'static` form. On the wire they are all plain strings.
+
+use std::borrow::{Borrow, Cow};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::r#type::Type;
+use crate::signature::Signature;
+use crate::value::Value;
+
+/// The maximum length (in bytes) of any D-Bus name.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Error returned when validating an invalid name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameError {
+    name: &'static str,
+    offset: usize,
+    kind: NameErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameErrorKind {
+    Empty,
+    /// The name is longer than [`MAX_NAME_LEN`].
+    TooLong(usize),
+    /// The character isn't allowed in this kind of name.
+    InvalidChar(char),
+    /// Elements separated by `.` must not be empty.
+    EmptyElement,
+    /// The element starts with a digit, which only unique bus names allow.
+    LeadingDigit,
+    /// The name has a single element, but needs at least two.
+    TooFewElements,
+}
+
+impl NameError {
+    fn new(name: &'static str, offset: usize, kind: NameErrorKind) -> Self {
+        Self { name, offset, kind }
+    }
+
+    /// The kind of name that failed validation, e.g. `"interface name"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The byte offset at which the name became invalid.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> NameErrorKind {
+        self.kind
+    }
+}
+
+impl Display for NameErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            NameErrorKind::Empty => write!(f, "empty name"),
+            NameErrorKind::TooLong(len) => {
+                write!(f, "{} bytes long, the maximum is {}", len, MAX_NAME_LEN)
+            }
+            NameErrorKind::InvalidChar(c) => write!(f, "invalid character `{}`", c.escape_debug()),
+            NameErrorKind::EmptyElement => write!(f, "empty element"),
+            NameErrorKind::LeadingDigit => write!(f, "element starts with a digit"),
+            NameErrorKind::TooFewElements => write!(f, "needs at least two elements"),
+        }
+    }
+}
+
+impl Display for NameError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} at offset {}: {}",
+            self.name, self.offset, self.kind
+        )
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// The rules shared by the different kinds of names.
+struct Grammar {
+    name: &'static str,
+    /// Whether the name is made of `.`-separated elements, at least two of them.
+    elements: bool,
+    hyphens: bool,
+    leading_digits: bool,
+}
+
+impl Grammar {
+    fn check(&self, s: &str, start: usize) -> Result<(), NameError> {
+        let error = |offset, kind| Err(NameError::new(self.name, offset, kind));
+        if s.is_empty() {
+            return error(0, NameErrorKind::Empty);
+        }
+        if s.len() > MAX_NAME_LEN {
+            return error(MAX_NAME_LEN, NameErrorKind::TooLong(s.len()));
+        }
+
+        let mut element_start = start;
+        let mut elements = 1;
+        for (i, c) in s.char_indices().skip_while(|&(i, _)| i < start) {
+            match c {
+                '.' if self.elements => {
+                    if i == element_start {
+                        return error(i, NameErrorKind::EmptyElement);
+                    }
+                    element_start = i + 1;
+                    elements += 1;
+                }
+                '0'..='9' if i == element_start && !self.leading_digits => {
+                    return error(i, NameErrorKind::LeadingDigit);
+                }
+                'A'..='Z' | 'a'..='z' | '0'..='9' | '_' => (),
+                '-' if self.hyphens => (),
+                c => return error(i, NameErrorKind::InvalidChar(c)),
+            }
+        }
+        if element_start == s.len() {
+            return error(s.len(), NameErrorKind::EmptyElement);
+        }
+        if self.elements && elements < 2 {
+            return error(s.len(), NameErrorKind::TooFewElements);
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_bus_name(s: &str) -> Result<(), NameError> {
+    let unique = s.starts_with(':');
+
+    Grammar {
+        name: "bus name",
+        elements: true,
+        hyphens: true,
+        leading_digits: unique,
+    }
+    .check(s, unique as usize)
+}
+
+fn validate_interface_name(s: &str) -> Result<(), NameError> {
+    Grammar {
+        name: "interface name",
+        elements: true,
+        hyphens: false,
+        leading_digits: false,
+    }
+    .check(s, 0)
+}
+
+fn validate_member_name(s: &str) -> Result<(), NameError> {
+    Grammar {
+        name: "member name",
+        elements: false,
+        hyphens: false,
+        leading_digits: false,
+    }
+    .check(s, 0)
+}
+
+fn validate_error_name(s: &str) -> Result<(), NameError> {
+    // Error names follow the same rules as interface names.
+    validate_interface_name(s).map_err(|e| NameError {
+        name: "error name",
+        ..e
+    })
+}
+
+fn validate_property_name(s: &str) -> Result<(), NameError> {
+    // Properties are named like methods and signals.
+    validate_member_name(s).map_err(|e| NameError {
+        name: "property name",
+        ..e
+    })
+}
+
+macro_rules! name_types {
+    ($($(#[$doc:meta])* $name:ident, $owned:ident => $validate:ident)+) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $name<'a>(Cow<'a, str>);
+
+            #[doc = concat!("A [`", stringify!($name), "`] that owns its string.")]
+            pub type $owned = $name<'static>;
+
+            impl<'a> $name<'a> {
+                /// Check that `s` is valid, without constructing a name.
+                pub fn validate(s: &str) -> Result<(), NameError> {
+                    $validate(s)
+                }
+
+                pub fn as_str(&self) -> &str {
+                    &self.0
+                }
+
+                /// A name borrowing from `self`.
+                pub fn as_borrowed(&self) -> $name<'_> {
+                    $name(Cow::Borrowed(&self.0))
+                }
+
+                pub fn into_owned(self) -> $owned {
+                    $name(Cow::Owned(self.0.into_owned()))
+                }
+            }
+
+            impl<'a> TryFrom<&'a str> for $name<'a> {
+                type Error = NameError;
+
+                fn try_from(s: &'a str) -> Result<Self, NameError> {
+                    $validate(s)?;
+
+                    Ok(Self(Cow::Borrowed(s)))
+                }
+            }
+
+            impl TryFrom<String> for $owned {
+                type Error = NameError;
+
+                fn try_from(s: String) -> Result<Self, NameError> {
+                    $validate(&s)?;
+
+                    Ok(Self(Cow::Owned(s)))
+                }
+            }
+
+            impl<'a> TryFrom<Cow<'a, str>> for $name<'a> {
+                type Error = NameError;
+
+                fn try_from(s: Cow<'a, str>) -> Result<Self, NameError> {
+                    $validate(&s)?;
+
+                    Ok(Self(s))
+                }
+            }
+
+            impl FromStr for $owned {
+                type Err = NameError;
+
+                fn from_str(s: &str) -> Result<Self, NameError> {
+                    $validate(s)?;
+
+                    Ok(Self(Cow::Owned(s.to_string())))
+                }
+            }
+
+            impl Deref for $name<'_> {
+                type Target = str;
+
+                fn deref(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl AsRef<str> for $name<'_> {
+                fn as_ref(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl Borrow<str> for $name<'_> {
+                fn borrow(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl Display for $name<'_> {
+                fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                    f.write_str(&self.0)
+                }
+            }
+
+            impl PartialEq<str> for $name<'_> {
+                fn eq(&self, other: &str) -> bool {
+                    *self.0 == *other
+                }
+            }
+
+            impl PartialEq<&str> for $name<'_> {
+                fn eq(&self, other: &&str) -> bool {
+                    *self.0 == **other
+                }
+            }
+
+            impl From<$name<'_>> for String {
+                fn from(name: $name<'_>) -> String {
+                    name.0.into_owned()
+                }
+            }
+
+            impl From<$name<'_>> for Value {
+                fn from(name: $name<'_>) -> Value {
+                    Value::Str(name.0.into_owned())
+                }
+            }
+
+            impl Type for $name<'_> {
+                const SIGNATURE: &'static Signature = &Signature::Str;
+            }
+        )+
+    };
+}
+
+name_types! {
+    /// A bus name, either unique (`:1.42`) or well-known (`org.freedesktop.DBus`).
+    BusName, OwnedBusName => validate_bus_name
+    /// An interface name, e.g. `org.freedesktop.DBus.Properties`.
+    InterfaceName, OwnedInterfaceName => validate_interface_name
+    /// The name of a method or signal, e.g. `GetAll`.
+    MemberName, OwnedMemberName => validate_member_name
+    /// An error name, e.g. `org.freedesktop.DBus.Error.Failed`.
+    ErrorName, OwnedErrorName => validate_error_name
+    /// The name of a property, e.g. `Version`.
+    PropertyName, OwnedPropertyName => validate_property_name
+}
+
+impl BusName<'_> {
+    /// Whether this is a unique name assigned by the bus, e.g. `:1.42`.
+    pub fn is_unique(&self) -> bool {
+        self.0.starts_with(':')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic_type::DynamicType;
+
+    fn error(result: Result<(), NameError>) -> (usize, NameErrorKind) {
+        let e = result.unwrap_err();
+
+        (e.offset(), e.kind())
+    }
+
+    #[test]
+    fn bus_names() {
+        for valid in [":1.42", ":a-b.0", "org.freedesktop.DBus", "a-b._c", "a.b1"] {
+            assert!(BusName::validate(valid).is_ok(), "{}", valid);
+        }
+        assert!(BusName::try_from(":1.42").unwrap().is_unique());
+        assert!(!BusName::try_from("org.example").unwrap().is_unique());
+
+        use NameErrorKind::*;
+        assert_eq!(error(BusName::validate("")), (0, Empty));
+        assert_eq!(error(BusName::validate(":")), (1, EmptyElement));
+        assert_eq!(error(BusName::validate(":1")), (2, TooFewElements));
+        assert_eq!(error(BusName::validate("org")), (3, TooFewElements));
+        assert_eq!(error(BusName::validate("org.1x")), (4, LeadingDigit));
+        assert_eq!(error(BusName::validate("org..x")), (4, EmptyElement));
+        assert_eq!(error(BusName::validate(".org.x")), (0, EmptyElement));
+        assert_eq!(error(BusName::validate("org.x.")), (6, EmptyElement));
+        assert_eq!(error(BusName::validate("org.é")), (4, InvalidChar('é')));
+        assert_eq!(error(BusName::validate("a:b.c")), (1, InvalidChar(':')));
+        let long = format!("a.{}", "b".repeat(254));
+        assert_eq!(error(BusName::validate(&long)), (255, TooLong(256)));
+    }
+
+    #[test]
+    fn other_names() {
+        use NameErrorKind::*;
+        assert!(InterfaceName::validate("org.freedesktop.DBus.Properties").is_ok());
+        assert_eq!(
+            error(InterfaceName::validate("org.free-desktop")),
+            (8, InvalidChar('-'))
+        );
+        assert_eq!(error(InterfaceName::validate("a")), (1, TooFewElements));
+        assert!(MemberName::validate("GetAll").is_ok());
+        assert!(MemberName::validate("_private2").is_ok());
+        assert_eq!(
+            error(MemberName::validate("Get.All")),
+            (3, InvalidChar('.'))
+        );
+        assert_eq!(error(MemberName::validate("2Get")), (0, LeadingDigit));
+
+        let e = ErrorName::try_from("Failed").unwrap_err();
+        assert_eq!(e.name(), "error name");
+        assert_eq!(
+            e.to_string(),
+            "invalid error name at offset 6: needs at least two elements"
+        );
+        let e = PropertyName::try_from("").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid property name at offset 0: empty name"
+        );
+    }
+
+    #[test]
+    fn borrowed_and_owned() {
+        let s = String::from("org.example.Iface");
+        let borrowed = InterfaceName::try_from(s.as_str()).unwrap();
+        let owned: OwnedInterfaceName = borrowed.clone().into_owned();
+        drop(s);
+        assert_eq!(owned, "org.example.Iface");
+        assert_eq!(owned.as_borrowed(), owned);
+        assert_eq!(
+            "org.example.Iface".parse::<OwnedInterfaceName>().unwrap(),
+            owned
+        );
+        assert!(OwnedMemberName::try_from("a b".to_string()).is_err());
+
+        assert_eq!(<MemberName>::SIGNATURE, &Signature::Str);
+        assert_eq!(owned.signature(), Signature::Str);
+        assert_eq!(
+            Value::from(owned),
+            Value::Str("org.example.Iface".to_string())
+        );
+    }
+}
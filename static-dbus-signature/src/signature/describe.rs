@@ -0,0 +1,182 @@
+use std::fmt::{self, Write};
+
+use super::Signature;
+
+impl Signature {
+    /// A human-readable explanation of `self`.
+    ///
+    /// For example, `a{sa(ux)}` is described as
+    /// "dictionary from string to array of struct (u32, i64)".
+    pub fn describe(&self) -> String {
+        let mut s = String::with_capacity(self.string_len() * 8);
+        // Writing to a `String` can't fail.
+        write_description(self, &mut s).unwrap();
+
+        s
+    }
+
+    /// The idiomatic Rust type that maps to `self`.
+    ///
+    /// For example, `a{sa(ux)}` is rendered as `HashMap<String, Vec<(u32, i64)>>`. Every type
+    /// rendered has a [`Type`](crate::type::Type) implementation with the same signature as
+    /// `self`, with `o`, `v` and `h` rendered as the [`ObjectPath`](crate::value::ObjectPath),
+    /// [`Variant`](crate::value::Variant) and [`Fd`](crate::value::Fd) types respectively.
+    pub fn rust_type(&self) -> String {
+        let mut s = String::with_capacity(self.string_len() * 8);
+        write_rust_type(self, &mut s).unwrap();
+
+        s
+    }
+}
+
+fn write_description(signature: &Signature, w: &mut impl Write) -> fmt::Result {
+    match signature {
+        Signature::Unit => w.write_str("unit"),
+        Signature::U8 => w.write_str("u8"),
+        Signature::Bool => w.write_str("boolean"),
+        Signature::I16 => w.write_str("i16"),
+        Signature::U16 => w.write_str("u16"),
+        Signature::I32 => w.write_str("i32"),
+        Signature::U32 => w.write_str("u32"),
+        Signature::I64 => w.write_str("i64"),
+        Signature::U64 => w.write_str("u64"),
+        Signature::F64 => w.write_str("double"),
+        Signature::Str => w.write_str("string"),
+        Signature::Signature => w.write_str("signature"),
+        Signature::ObjectPath => w.write_str("object path"),
+        Signature::Value => w.write_str("variant"),
+        #[cfg(unix)]
+        Signature::Fd => w.write_str("file descriptor"),
+        Signature::Array(child) => {
+            w.write_str("array of ")?;
+            write_description(child, w)
+        }
+        Signature::Dict { key, value } => {
+            w.write_str("dictionary from ")?;
+            write_description(key, w)?;
+            w.write_str(" to ")?;
+            write_description(value, w)
+        }
+        Signature::Structure(fields) => {
+            w.write_str("struct (")?;
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_description(field, w)?;
+            }
+            w.write_str(")")
+        }
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => {
+            w.write_str("optional ")?;
+            write_description(child, w)
+        }
+    }
+}
+
+fn write_rust_type(signature: &Signature, w: &mut impl Write) -> fmt::Result {
+    match signature {
+        Signature::Unit => w.write_str("()"),
+        Signature::U8 => w.write_str("u8"),
+        Signature::Bool => w.write_str("bool"),
+        Signature::I16 => w.write_str("i16"),
+        Signature::U16 => w.write_str("u16"),
+        Signature::I32 => w.write_str("i32"),
+        Signature::U32 => w.write_str("u32"),
+        Signature::I64 => w.write_str("i64"),
+        Signature::U64 => w.write_str("u64"),
+        Signature::F64 => w.write_str("f64"),
+        Signature::Str => w.write_str("String"),
+        Signature::Signature => w.write_str("Signature"),
+        Signature::ObjectPath => w.write_str("ObjectPath"),
+        Signature::Value => w.write_str("Variant"),
+        #[cfg(unix)]
+        Signature::Fd => w.write_str("Fd"),
+        Signature::Array(child) => {
+            w.write_str("Vec<")?;
+            write_rust_type(child, w)?;
+            w.write_str(">")
+        }
+        Signature::Dict { key, value } => {
+            w.write_str("HashMap<")?;
+            write_rust_type(key, w)?;
+            w.write_str(", ")?;
+            write_rust_type(value, w)?;
+            w.write_str(">")
+        }
+        Signature::Structure(fields) => {
+            w.write_str("(")?;
+            let mut len = 0;
+            for field in fields.iter() {
+                if len > 0 {
+                    w.write_str(", ")?;
+                }
+                write_rust_type(field, w)?;
+                len += 1;
+            }
+            if len == 1 {
+                // A single-element tuple needs a trailing comma.
+                w.write_str(",")?;
+            }
+            w.write_str(")")
+        }
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => {
+            w.write_str("Option<")?;
+            write_rust_type(child, w)?;
+            w.write_str(">")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::r#type::Type;
+    use crate::value::{Fd, ObjectPath, Variant};
+
+    #[test]
+    fn describe() {
+        let signature: Signature = "a{sa(ux)}".parse().unwrap();
+        assert_eq!(
+            signature.describe(),
+            "dictionary from string to array of struct (u32, i64)"
+        );
+        let signature: Signature = "(oa{sv}(bg))".parse().unwrap();
+        assert_eq!(
+            signature.describe(),
+            "struct (object path, dictionary from string to variant, struct (boolean, signature))"
+        );
+        assert_eq!(Signature::Unit.describe(), "unit");
+    }
+
+    #[test]
+    fn rust_type() {
+        let signature: Signature = "a{sa(ux)}".parse().unwrap();
+        assert_eq!(signature.rust_type(), "HashMap<String, Vec<(u32, i64)>>");
+        assert_eq!(<HashMap<String, Vec<(u32, i64)>>>::SIGNATURE, &signature);
+
+        let signature: Signature = "(x)".parse().unwrap();
+        assert_eq!(signature.rust_type(), "(i64,)");
+        assert_eq!(<(i64,)>::SIGNATURE, &signature);
+
+        let signature: Signature = "a(yqgb)".parse().unwrap();
+        assert_eq!(signature.rust_type(), "Vec<(u8, u16, Signature, bool)>");
+        assert_eq!(<Vec<(u8, u16, Signature, bool)>>::SIGNATURE, &signature);
+
+        let signature: Signature = "(oa{sv}h)".parse().unwrap();
+        assert_eq!(
+            signature.rust_type(),
+            "(ObjectPath, HashMap<String, Variant>, Fd)"
+        );
+        assert_eq!(
+            <(ObjectPath, HashMap<String, Variant>, Fd)>::SIGNATURE,
+            &signature
+        );
+
+        assert_eq!(Signature::Unit.rust_type(), "()");
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{PathSegment, Signature, SignaturePath};
+
+/// Whether a signature change breaks existing peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// Peers built against the old signature can still decode the new one.
+    NonBreaking,
+    /// Peers built against the old signature will fail to decode the new one.
+    Breaking,
+}
+
+/// The kind of change between an old and a new signature at a given path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// A field was appended to the end of a structure.
+    FieldAdded { index: usize, signature: Signature },
+    /// A field was removed from the end of a structure.
+    FieldRemoved { index: usize, signature: Signature },
+    /// An array was turned into a dictionary.
+    ArrayToDict,
+    /// A dictionary was turned into an array.
+    DictToArray,
+    /// Any other change of type.
+    TypeChanged { old: Signature, new: Signature },
+}
+
+/// A single difference found by [`Signature::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub path: SignaturePath,
+    pub kind: DifferenceKind,
+}
+
+impl Difference {
+    /// Whether this difference breaks existing peers.
+    ///
+    /// Appending an argument to the top-level signature is the only non-breaking change: a peer
+    /// that only knows the old arguments still finds them where it expects them, and ignores the
+    /// rest of the body. A field appended to a nested structure shifts everything encoded after
+    /// that structure, e.g. the next element of an array, so it's breaking.
+    ///
+    /// A top-level signature made of a single structure can't be told apart from its fields as
+    /// arguments, so fields appended to it count as arguments.
+    pub fn compatibility(&self) -> Compatibility {
+        match self.kind {
+            DifferenceKind::FieldAdded { .. } if self.path.segments().is_empty() => {
+                Compatibility::NonBreaking
+            }
+            DifferenceKind::FieldAdded { .. }
+            | DifferenceKind::FieldRemoved { .. }
+            | DifferenceKind::ArrayToDict
+            | DifferenceKind::DictToArray
+            | DifferenceKind::TypeChanged { .. } => Compatibility::Breaking,
+        }
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.compatibility() == Compatibility::Breaking
+    }
+}
+
+impl Signature {
+    /// Compare `self`, the old signature, against `new`.
+    ///
+    /// Returns every difference found, ordered by their position in the signature. An empty list
+    /// means both signatures are equal.
+    pub fn diff(&self, new: &Signature) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        let mut path = SignaturePath::root();
+        diff(self, new, &mut path, &mut differences);
+
+        differences
+    }
+
+    /// Whether peers built against `self` can still decode `new`.
+    pub fn is_compatible_with(&self, new: &Signature) -> bool {
+        !self.diff(new).iter().any(Difference::is_breaking)
+    }
+}
+
+fn diff(old: &Signature, new: &Signature, path: &mut SignaturePath, out: &mut Vec<Difference>) {
+    let mut child = |old: &Signature, new: &Signature, segment, out: &mut Vec<Difference>| {
+        path.push(segment);
+        diff(old, new, path, out);
+        path.pop();
+    };
+
+    match (old, new) {
+        (Signature::Array(old), Signature::Array(new)) => {
+            child(old, new, PathSegment::Element, out)
+        }
+        (
+            Signature::Dict {
+                key: old_key,
+                value: old_value,
+            },
+            Signature::Dict {
+                key: new_key,
+                value: new_value,
+            },
+        ) => {
+            child(old_key, new_key, PathSegment::Key, out);
+            child(old_value, new_value, PathSegment::Value, out);
+        }
+        #[cfg(feature = "gvariant")]
+        (Signature::Maybe(old), Signature::Maybe(new)) => child(old, new, PathSegment::Maybe, out),
+        (Signature::Structure(_) | Signature::Unit, Signature::Structure(_))
+        | (Signature::Structure(_), Signature::Unit) => {
+            // A unit signature is an empty list of fields, so going from no arguments to some is
+            // the same as appending fields.
+            diff_fields(fields(old).collect(), fields(new).collect(), path, out)
+        }
+        // At the top level, a single argument is a list of one, so it can get more.
+        (old, new) if path.segments().is_empty() && has_fields(old) != has_fields(new) => {
+            diff_fields(arguments(old), arguments(new), path, out)
+        }
+        (Signature::Array(_), Signature::Dict { .. }) => out.push(Difference {
+            path: path.clone(),
+            kind: DifferenceKind::ArrayToDict,
+        }),
+        (Signature::Dict { .. }, Signature::Array(_)) => out.push(Difference {
+            path: path.clone(),
+            kind: DifferenceKind::DictToArray,
+        }),
+        (old, new) if old == new => (),
+        (old, new) => out.push(Difference {
+            path: path.clone(),
+            kind: DifferenceKind::TypeChanged {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        }),
+    }
+}
+
+fn diff_fields(
+    old: Vec<&Signature>,
+    new: Vec<&Signature>,
+    path: &mut SignaturePath,
+    out: &mut Vec<Difference>,
+) {
+    for (i, (old, new)) in old.iter().zip(&new).enumerate() {
+        path.push(PathSegment::Field(i));
+        diff(old, new, path, out);
+        path.pop();
+    }
+    for (index, &signature) in new.iter().enumerate().skip(old.len()) {
+        out.push(Difference {
+            path: path.clone(),
+            kind: DifferenceKind::FieldAdded {
+                index,
+                signature: signature.clone(),
+            },
+        });
+    }
+    for (index, &signature) in old.iter().enumerate().skip(new.len()) {
+        out.push(Difference {
+            path: path.clone(),
+            kind: DifferenceKind::FieldRemoved {
+                index,
+                signature: signature.clone(),
+            },
+        });
+    }
+}
+
+fn has_fields(signature: &Signature) -> bool {
+    matches!(signature, Signature::Structure(_) | Signature::Unit)
+}
+
+/// The arguments of a top-level signature.
+fn arguments(signature: &Signature) -> Vec<&Signature> {
+    match signature {
+        Signature::Structure(_) | Signature::Unit => fields(signature).collect(),
+        _ => vec![signature],
+    }
+}
+
+fn fields(signature: &Signature) -> impl Iterator<Item = &Signature> {
+    let fields = match signature {
+        Signature::Structure(fields) => Some(fields.iter()),
+        _ => None,
+    };
+
+    fields.into_iter().flatten()
+}
+
+impl Display for Compatibility {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Compatibility::NonBreaking => write!(f, "non-breaking"),
+            Compatibility::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+impl Display for DifferenceKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DifferenceKind::FieldAdded { index, signature } => {
+                write!(f, "field {} `{}` added", index, signature)
+            }
+            DifferenceKind::FieldRemoved { index, signature } => {
+                write!(f, "field {} `{}` removed", index, signature)
+            }
+            DifferenceKind::ArrayToDict => write!(f, "array changed to dictionary"),
+            DifferenceKind::DictToArray => write!(f, "dictionary changed to array"),
+            DifferenceKind::TypeChanged { old, new } => {
+                write!(f, "type changed from `{}` to `{}`", old, new)
+            }
+        }
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.path, self.kind, self.compatibility())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> Vec<Difference> {
+        let old: Signature = old.parse().unwrap();
+        let new: Signature = new.parse().unwrap();
+
+        old.diff(&new)
+    }
+
+    #[test]
+    fn equal() {
+        assert!(diff("a{sa(ux)}", "a{sa(ux)}").is_empty());
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn field_added() {
+        let differences = diff("(s(ii))", "(s(iib))");
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path.to_string(), "$.1");
+        assert_eq!(
+            differences[0].kind,
+            DifferenceKind::FieldAdded {
+                index: 2,
+                signature: Signature::Bool,
+            }
+        );
+        assert!(differences[0].is_breaking());
+
+        let differences = diff("a(ii)", "a(iib)");
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path.to_string(), "$[]");
+        assert!(differences[0].is_breaking());
+        assert!(!"a(ii)"
+            .parse::<Signature>()
+            .unwrap()
+            .is_compatible_with(&"a(iib)".parse().unwrap()));
+
+        let differences = diff("s", "su");
+        assert_eq!(differences.len(), 1);
+        assert!(!differences[0].is_breaking());
+
+        let differences = diff("", "su");
+        assert_eq!(differences.len(), 2);
+        assert!(differences.iter().all(|d| !d.is_breaking()));
+        assert!(""
+            .parse::<Signature>()
+            .unwrap()
+            .is_compatible_with(&"su".parse().unwrap()));
+    }
+
+    #[test]
+    fn breaking() {
+        let differences = diff("(sii)", "(si)");
+        assert_eq!(differences.len(), 1);
+        assert!(matches!(
+            differences[0].kind,
+            DifferenceKind::FieldRemoved { index: 2, .. }
+        ));
+        assert!(differences[0].is_breaking());
+
+        let differences = diff("a{sa(ux)}", "a{sa(tx)}");
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path.to_string(), "${value}[].0");
+        assert_eq!(
+            differences[0].kind,
+            DifferenceKind::TypeChanged {
+                old: Signature::U32,
+                new: Signature::U64,
+            }
+        );
+
+        let differences = diff("(sa(sv))", "(sa{sv})");
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].path.to_string(), "$.1");
+        assert_eq!(differences[0].kind, DifferenceKind::ArrayToDict);
+        assert_eq!(
+            differences[0].to_string(),
+            "$.1: array changed to dictionary (breaking)"
+        );
+
+        let old: Signature = "a{sv}".parse().unwrap();
+        assert!(!old.is_compatible_with(&"a(sv)".parse().unwrap()));
+    }
+}
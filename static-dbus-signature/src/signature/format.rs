@@ -0,0 +1,357 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{PathSegment, Signature, SignaturePath};
+
+/// The maximum length of a signature string in the D-Bus format.
+pub const MAX_DBUS_LEN: usize = 255;
+/// The maximum nesting depth of arrays (and dictionaries) in the D-Bus format.
+pub const MAX_DBUS_ARRAY_DEPTH: usize = 32;
+/// The maximum nesting depth of structures (and dictionary entries) in the D-Bus format.
+pub const MAX_DBUS_STRUCT_DEPTH: usize = 32;
+
+/// The wire formats a signature can describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    DBus,
+    #[cfg(feature = "gvariant")]
+    GVariant,
+}
+
+impl Format {
+    /// All the formats supported by this build.
+    pub const ALL: &'static [Format] = &[
+        Format::DBus,
+        #[cfg(feature = "gvariant")]
+        Format::GVariant,
+    ];
+}
+
+/// Why a signature can't be used as is in a given format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatIssueKind {
+    /// The format has no maybe type.
+    #[cfg(feature = "gvariant")]
+    MaybeUnsupported,
+    /// The signature string is longer than the format allows.
+    TooLong { len: usize, max: usize },
+    /// Arrays are nested deeper than the format allows.
+    ArrayTooDeep { max: usize },
+    /// Structures are nested deeper than the format allows.
+    StructureTooDeep { max: usize },
+    /// Dictionary keys must be basic types.
+    NonBasicDictKey(Signature),
+}
+
+/// A single issue found by [`Signature::check_format`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatIssue {
+    pub path: SignaturePath,
+    pub kind: FormatIssueKind,
+}
+
+impl Signature {
+    /// The alignment (in bytes) of values of this type in the given format.
+    pub fn alignment(&self, format: Format) -> usize {
+        match format {
+            Format::DBus => match self {
+                Signature::Unit | Signature::U8 | Signature::Signature | Signature::Value => 1,
+                Signature::I16 | Signature::U16 => 2,
+                Signature::Bool
+                | Signature::I32
+                | Signature::U32
+                | Signature::Str
+                | Signature::ObjectPath
+                | Signature::Array(_)
+                | Signature::Dict { .. } => 4,
+                #[cfg(unix)]
+                Signature::Fd => 4,
+                Signature::I64 | Signature::U64 | Signature::F64 | Signature::Structure(_) => 8,
+                // Not representable in D-Bus but the alignment of the child is the best guess.
+                #[cfg(feature = "gvariant")]
+                Signature::Maybe(child) => child.alignment(format),
+            },
+            #[cfg(feature = "gvariant")]
+            Format::GVariant => match self {
+                Signature::Unit
+                | Signature::U8
+                | Signature::Bool
+                | Signature::Str
+                | Signature::Signature
+                | Signature::ObjectPath => 1,
+                Signature::I16 | Signature::U16 => 2,
+                Signature::I32 | Signature::U32 => 4,
+                #[cfg(unix)]
+                Signature::Fd => 4,
+                Signature::I64 | Signature::U64 | Signature::F64 | Signature::Value => 8,
+                Signature::Array(child) | Signature::Maybe(child) => child.alignment(format),
+                Signature::Dict { key, value } => {
+                    key.alignment(format).max(value.alignment(format))
+                }
+                Signature::Structure(fields) => fields
+                    .iter()
+                    .map(|field| field.alignment(format))
+                    .max()
+                    .unwrap_or(1),
+            },
+        }
+    }
+
+    /// The encoded size (in bytes) of values of this type in the given format, if all values of
+    /// this type have the same size.
+    ///
+    /// Structures are fixed-size if all their fields are. In the D-Bus format the size doesn't
+    /// include any trailing padding while in the GVariant format it does.
+    pub fn fixed_size(&self, format: Format) -> Option<usize> {
+        match self {
+            Signature::U8 => Some(1),
+            Signature::Bool => match format {
+                Format::DBus => Some(4),
+                #[cfg(feature = "gvariant")]
+                Format::GVariant => Some(1),
+            },
+            Signature::I16 | Signature::U16 => Some(2),
+            Signature::I32 | Signature::U32 => Some(4),
+            #[cfg(unix)]
+            Signature::Fd => Some(4),
+            Signature::I64 | Signature::U64 | Signature::F64 => Some(8),
+            Signature::Unit => match format {
+                Format::DBus => Some(0),
+                // The unit type is encoded as a single zero byte in GVariant.
+                #[cfg(feature = "gvariant")]
+                Format::GVariant => Some(1),
+            },
+            Signature::Structure(fields) => {
+                let mut size = 0;
+                for field in fields.iter() {
+                    size = align(size, field.alignment(format)) + field.fixed_size(format)?;
+                }
+
+                match format {
+                    Format::DBus => Some(size),
+                    #[cfg(feature = "gvariant")]
+                    Format::GVariant => Some(align(size, self.alignment(format)).max(1)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if `self` can be used as is in the given format.
+    ///
+    /// Returns every issue found, ordered by their position in the signature.
+    pub fn check_format(&self, format: Format) -> Vec<FormatIssue> {
+        let mut issues = Vec::new();
+        if format == Format::DBus {
+            let len = self.string_len();
+            if len > MAX_DBUS_LEN {
+                issues.push(FormatIssue {
+                    path: SignaturePath::root(),
+                    kind: FormatIssueKind::TooLong {
+                        len,
+                        max: MAX_DBUS_LEN,
+                    },
+                });
+            }
+        }
+        let mut path = SignaturePath::root();
+        check_format(self, format, &mut path, 0, 0, &mut issues);
+
+        issues
+    }
+}
+
+fn check_format(
+    signature: &Signature,
+    format: Format,
+    path: &mut SignaturePath,
+    array_depth: usize,
+    struct_depth: usize,
+    issues: &mut Vec<FormatIssue>,
+) {
+    let mut issue = |path: &SignaturePath, kind| {
+        issues.push(FormatIssue {
+            path: path.clone(),
+            kind,
+        })
+    };
+    if format == Format::DBus {
+        if array_depth > MAX_DBUS_ARRAY_DEPTH {
+            issue(
+                path,
+                FormatIssueKind::ArrayTooDeep {
+                    max: MAX_DBUS_ARRAY_DEPTH,
+                },
+            );
+            // Everything below would report the same issue.
+            return;
+        }
+        if struct_depth > MAX_DBUS_STRUCT_DEPTH {
+            issue(
+                path,
+                FormatIssueKind::StructureTooDeep {
+                    max: MAX_DBUS_STRUCT_DEPTH,
+                },
+            );
+            return;
+        }
+    }
+
+    match signature {
+        Signature::Array(child) => {
+            path.push(PathSegment::Element);
+            check_format(child, format, path, array_depth + 1, struct_depth, issues);
+            path.pop();
+        }
+        Signature::Dict { key, value } => {
+            if !key.is_basic() {
+                issue(path, FormatIssueKind::NonBasicDictKey((**key).clone()));
+            }
+            // A dictionary is an array of dictionary entries, which count as structures.
+            path.push(PathSegment::Value);
+            check_format(
+                value,
+                format,
+                path,
+                array_depth + 1,
+                struct_depth + 1,
+                issues,
+            );
+            path.pop();
+        }
+        Signature::Structure(fields) => {
+            for (i, field) in fields.iter().enumerate() {
+                path.push(PathSegment::Field(i));
+                check_format(field, format, path, array_depth, struct_depth + 1, issues);
+                path.pop();
+            }
+        }
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => {
+            if format == Format::DBus {
+                issue(path, FormatIssueKind::MaybeUnsupported);
+            }
+            path.push(PathSegment::Maybe);
+            check_format(child, format, path, array_depth, struct_depth, issues);
+            path.pop();
+        }
+        _ => (),
+    }
+}
+
+/// Round `offset` up to the next multiple of `alignment`.
+pub(crate) fn align(offset: usize, alignment: usize) -> usize {
+    offset.next_multiple_of(alignment)
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Format::DBus => write!(f, "D-Bus"),
+            #[cfg(feature = "gvariant")]
+            Format::GVariant => write!(f, "GVariant"),
+        }
+    }
+}
+
+impl Display for FormatIssueKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "gvariant")]
+            FormatIssueKind::MaybeUnsupported => write!(f, "maybe type is not supported"),
+            FormatIssueKind::TooLong { len, max } => {
+                write!(
+                    f,
+                    "signature is {} characters long, maximum is {}",
+                    len, max
+                )
+            }
+            FormatIssueKind::ArrayTooDeep { max } => {
+                write!(f, "arrays nested deeper than {} levels", max)
+            }
+            FormatIssueKind::StructureTooDeep { max } => {
+                write!(f, "structures nested deeper than {} levels", max)
+            }
+            FormatIssueKind::NonBasicDictKey(key) => {
+                write!(f, "dictionary key `{}` is not a basic type", key)
+            }
+        }
+    }
+}
+
+impl Display for FormatIssue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn dbus_layout() {
+        assert_eq!(sig("y").alignment(Format::DBus), 1);
+        assert_eq!(sig("b").fixed_size(Format::DBus), Some(4));
+        assert_eq!(sig("as").alignment(Format::DBus), 4);
+        assert_eq!(sig("as").fixed_size(Format::DBus), None);
+        assert_eq!(sig("(yx)").alignment(Format::DBus), 8);
+        assert_eq!(sig("(yx)").fixed_size(Format::DBus), Some(16));
+        assert_eq!(sig("(xy)").fixed_size(Format::DBus), Some(9));
+        assert_eq!(sig("(ys)").fixed_size(Format::DBus), None);
+    }
+
+    #[cfg(feature = "gvariant")]
+    #[test]
+    fn gvariant_layout() {
+        assert_eq!(sig("b").fixed_size(Format::GVariant), Some(1));
+        assert_eq!(sig("as").alignment(Format::GVariant), 1);
+        assert_eq!(sig("ax").alignment(Format::GVariant), 8);
+        assert_eq!(sig("a{sv}").alignment(Format::GVariant), 8);
+        assert_eq!(sig("(xy)").fixed_size(Format::GVariant), Some(16));
+        assert_eq!(sig("(yy)").fixed_size(Format::GVariant), Some(2));
+        assert_eq!(sig("mi").alignment(Format::GVariant), 4);
+        assert_eq!(sig("mi").fixed_size(Format::GVariant), None);
+        assert_eq!(Signature::Unit.fixed_size(Format::GVariant), Some(1));
+
+        let issues = sig("(sms)").check_format(Format::DBus);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].to_string(), "$.1: maybe type is not supported");
+        assert!(sig("(sms)").check_format(Format::GVariant).is_empty());
+    }
+
+    #[test]
+    fn check_format() {
+        assert!(sig("a{sa(ux)}").check_format(Format::DBus).is_empty());
+
+        let issues = sig("a{(ss)v}").check_format(Format::DBus);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].kind,
+            FormatIssueKind::NonBasicDictKey(sig("(ss)"))
+        );
+
+        let deep = format!("{}y", "a".repeat(33));
+        let issues = sig(&deep).check_format(Format::DBus);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, FormatIssueKind::ArrayTooDeep { max: 32 });
+        assert_eq!(issues[0].path.segments().len(), 33);
+
+        let deep = format!("{}y{}", "(".repeat(33), ")".repeat(33));
+        let issues = sig(&deep).check_format(Format::DBus);
+        assert_eq!(
+            issues[0].kind,
+            FormatIssueKind::StructureTooDeep { max: 32 }
+        );
+
+        let long = format!("({})", "s".repeat(254));
+        let issues = sig(&long).check_format(Format::DBus);
+        assert_eq!(
+            issues[0].kind,
+            FormatIssueKind::TooLong { len: 256, max: 255 }
+        );
+    }
+}
@@ -2,15 +2,24 @@ mod child;
 pub use child::ChildSignature;
 mod fields;
 pub use fields::FieldsSignatures;
+mod path;
+pub use path::{PathSegment, SignaturePath};
+mod diff;
+pub use diff::{Compatibility, Difference, DifferenceKind};
+mod pattern;
+pub use pattern::{Captures, SignaturePattern};
+mod describe;
+mod format;
+pub use format::{
+    Format, FormatIssue, FormatIssueKind, MAX_DBUS_ARRAY_DEPTH, MAX_DBUS_LEN, MAX_DBUS_STRUCT_DEPTH,
+};
+mod schema;
 
 use core::fmt;
 use std::fmt::{Display, Formatter};
-use std::ops::Deref;
 use std::str::FromStr;
-use std::sync::Arc;
 
 use crate::r#type::Type;
-use crate::signature;
 
 #[derive(Debug, Clone)]
 pub enum Signature {
@@ -76,6 +85,52 @@ impl Signature {
             Signature::Maybe(child) => 1 + child.string_len(),
         }
     }
+
+    /// All the basic types.
+    #[cfg(any(feature = "arbitrary", feature = "proptest"))]
+    pub(crate) const BASIC_TYPES: &'static [Signature] = &[
+        Signature::U8,
+        Signature::Bool,
+        Signature::I16,
+        Signature::U16,
+        Signature::I32,
+        Signature::U32,
+        Signature::I64,
+        Signature::U64,
+        Signature::F64,
+        Signature::Str,
+        Signature::Signature,
+        Signature::ObjectPath,
+        #[cfg(unix)]
+        Signature::Fd,
+    ];
+
+    /// Whether `self` is a basic type, i.e. one that can be used as a dictionary key.
+    pub fn is_basic(&self) -> bool {
+        match self {
+            Signature::U8
+            | Signature::Bool
+            | Signature::I16
+            | Signature::U16
+            | Signature::I32
+            | Signature::U32
+            | Signature::I64
+            | Signature::U64
+            | Signature::F64
+            | Signature::Str
+            | Signature::Signature
+            | Signature::ObjectPath => true,
+            #[cfg(unix)]
+            Signature::Fd => true,
+            Signature::Unit
+            | Signature::Value
+            | Signature::Array(_)
+            | Signature::Dict { .. }
+            | Signature::Structure(_) => false,
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(_) => false,
+        }
+    }
 }
 
 impl Display for Signature {
@@ -120,29 +175,61 @@ impl Display for Signature {
 }
 
 impl FromStr for Signature {
-    type Err = ();
+    type Err = ParseError;
 
-    fn from_str(s: &str) -> Result<Self, ()> {
+    fn from_str(s: &str) -> Result<Self, ParseError> {
         parse(s, false)
     }
 }
 
 /// Validate the given signature string.
-pub fn validate(s: &str) -> Result<(), ()> {
+pub fn validate(s: &str) -> Result<(), ParseError> {
     parse(s, true).map(|_| ())
 }
 
+/// Error returned when parsing an invalid signature string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(s: &str, remaining: &str) -> Self {
+        Self {
+            offset: s.len() - remaining.len(),
+        }
+    }
+
+    /// The byte offset of the first invalid character in the parsed string.
+    ///
+    /// Equal to the length of the string if it ended prematurely.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid signature at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// Parse a signature string into a `Signature`.
 ///
 /// When `check_only` is true, the function will not allocate memory for the dynamic types.
 /// Instead it will return dummy values in the parsed Signature.
-fn parse(s: &str, check_only: bool) -> Result<Signature, ()> {
+///
+/// Once the opening character of a container has been seen, the parser commits to it (through
+/// `cut`) so that the error points at the actual offending character rather than the start of the
+/// container.
+fn parse(s: &str, check_only: bool) -> Result<Signature, ParseError> {
     use nom::branch::alt;
-    use nom::bytes::complete::tag;
     use nom::character::complete::char;
-    use nom::combinator::{all_consuming, eof, map};
+    use nom::combinator::{all_consuming, cut, eof, map};
     use nom::multi::{many1, many1_count};
-    use nom::sequence::{delimited, pair, preceded, terminated};
+    use nom::sequence::{pair, preceded, terminated};
 
     let empty = map(eof, |_| Signature::Unit);
 
@@ -179,31 +266,15 @@ fn parse(s: &str, check_only: bool) -> Result<Signature, ()> {
     fn parse_signature(s: &str, check_only: bool) -> nom::IResult<&str, Signature> {
         let parse_with_context = |s| parse_signature(s, check_only);
 
-        let simple_type = alt((
-            map(tag("y"), |_| Signature::U8),
-            map(tag("b"), |_| Signature::Bool),
-            map(tag("n"), |_| Signature::I16),
-            map(tag("q"), |_| Signature::U16),
-            map(tag("i"), |_| Signature::I32),
-            map(tag("u"), |_| Signature::U32),
-            map(tag("x"), |_| Signature::I64),
-            map(tag("t"), |_| Signature::U64),
-            map(tag("d"), |_| Signature::F64),
-            map(tag("s"), |_| Signature::Str),
-            map(tag("g"), |_| Signature::Signature),
-            map(tag("o"), |_| Signature::ObjectPath),
-            map(tag("v"), |_| Signature::Value),
-            #[cfg(unix)]
-            map(tag("h"), |_| Signature::Fd),
-        ));
-
         let dict = map(
             pair(
                 char('a'),
-                delimited(
+                preceded(
                     char('{'),
-                    pair(parse_with_context, parse_with_context),
-                    char('}'),
+                    cut(terminated(
+                        pair(parse_with_context, parse_with_context),
+                        char('}'),
+                    )),
                 ),
             ),
             |(_, (key, value))| {
@@ -221,7 +292,7 @@ fn parse(s: &str, check_only: bool) -> Result<Signature, ()> {
             },
         );
 
-        let array = map(pair(char('a'), parse_with_context), |(_, child)| {
+        let array = map(pair(char('a'), cut(parse_with_context)), |(_, child)| {
             if check_only {
                 return Signature::Array(<()>::SIGNATURE.into());
             }
@@ -229,10 +300,13 @@ fn parse(s: &str, check_only: bool) -> Result<Signature, ()> {
             Signature::Array(child.into())
         });
 
-        let structure = delimited(char('('), |s| many(s, check_only, false), char(')'));
+        let structure = preceded(
+            char('('),
+            cut(terminated(|s| many(s, check_only, false), char(')'))),
+        );
 
         #[cfg(feature = "gvariant")]
-        let maybe = map(pair(char('m'), parse_with_context), |(_, child)| {
+        let maybe = map(pair(char('m'), cut(parse_with_context)), |(_, child)| {
             if check_only {
                 return Signature::Maybe(<()>::SIGNATURE.into());
             }
@@ -251,11 +325,39 @@ fn parse(s: &str, check_only: bool) -> Result<Signature, ()> {
     }
 
     let (_, signature) =
-        all_consuming(alt((empty, |s| many(s, check_only, true))))(s).map_err(|_| ())?;
+        all_consuming(alt((empty, |s| many(s, check_only, true))))(s).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => ParseError::new(s, e.input),
+            nom::Err::Incomplete(_) => ParseError::new(s, ""),
+        })?;
 
     Ok(signature)
 }
 
+/// Parse a single-character (non-container) type code.
+pub(crate) fn simple_type(s: &str) -> nom::IResult<&str, Signature> {
+    use nom::branch::alt;
+    use nom::bytes::complete::tag;
+    use nom::combinator::map;
+
+    alt((
+        map(tag("y"), |_| Signature::U8),
+        map(tag("b"), |_| Signature::Bool),
+        map(tag("n"), |_| Signature::I16),
+        map(tag("q"), |_| Signature::U16),
+        map(tag("i"), |_| Signature::I32),
+        map(tag("u"), |_| Signature::U32),
+        map(tag("x"), |_| Signature::I64),
+        map(tag("t"), |_| Signature::U64),
+        map(tag("d"), |_| Signature::F64),
+        map(tag("s"), |_| Signature::Str),
+        map(tag("g"), |_| Signature::Signature),
+        map(tag("o"), |_| Signature::ObjectPath),
+        map(tag("v"), |_| Signature::Value),
+        #[cfg(unix)]
+        map(tag("h"), |_| Signature::Fd),
+    ))(s)
+}
+
 impl PartialEq for Signature {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -420,7 +522,7 @@ impl PartialOrd for Signature {
             (Signature::Structure(a), Signature::Structure(b)) => a.iter().partial_cmp(b.iter()),
             #[cfg(feature = "gvariant")]
             (Signature::Maybe(a), Signature::Maybe(b)) => a.partial_cmp(b),
-            (a, b) => None,
+            _ => None,
         }
     }
 }
@@ -604,4 +706,32 @@ mod tests {
             "a{yz}"
         );
     }
+
+    #[test]
+    fn error_offsets() {
+        for (signature, offset) in [
+            ("z", 0),
+            ("s/", 1),
+            ("a", 1),
+            ("a{y}", 3),
+            ("a{yz}", 3),
+            ("()", 1),
+            ("(xa(s)", 6),
+            ("xs)", 2),
+            ("(ss(iz))", 5),
+        ] {
+            assert_eq!(
+                validate(signature).unwrap_err().offset(),
+                offset,
+                "{}",
+                signature
+            );
+            assert_eq!(
+                signature.parse::<Signature>().unwrap_err().offset(),
+                offset,
+                "{}",
+                signature
+            );
+        }
+    }
 }
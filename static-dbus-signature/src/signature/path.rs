@@ -0,0 +1,82 @@
+use std::fmt::{self, Display, Formatter};
+
+/// A single step from a container signature into one of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment {
+    /// The field at the given index of a structure.
+    Field(usize),
+    /// The element type of an array.
+    Element,
+    /// The key type of a dictionary.
+    Key,
+    /// The value type of a dictionary.
+    Value,
+    /// The child type of a maybe.
+    #[cfg(feature = "gvariant")]
+    Maybe,
+    /// The type contained in a variant, only known from the value.
+    Variant,
+}
+
+/// The location of a child signature inside a (possibly nested) signature.
+///
+/// Rendered as `$` for the root, followed by `.N` for structure fields, `[]` for array elements,
+/// `{key}`/`{value}` for dictionary entries, `?` for maybe children and `<>` for the contents of
+/// variants, e.g. `$.1{value}[]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignaturePath {
+    segments: Vec<PathSegment>,
+}
+
+impl SignaturePath {
+    /// The path of the root signature.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// The segments of this path, from the root downwards.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// A new path that extends `self` by `segment`.
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut segments = self.segments.clone();
+        segments.push(segment);
+
+        Self { segments }
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            PathSegment::Field(i) => write!(f, ".{}", i),
+            PathSegment::Element => write!(f, "[]"),
+            PathSegment::Key => write!(f, "{{key}}"),
+            PathSegment::Value => write!(f, "{{value}}"),
+            #[cfg(feature = "gvariant")]
+            PathSegment::Maybe => write!(f, "?"),
+            PathSegment::Variant => write!(f, "<>"),
+        }
+    }
+}
+
+impl Display for SignaturePath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.segments {
+            segment.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+use core::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use super::{simple_type, ParseError, Signature};
+
+/// A compiled pattern that can be matched against signatures.
+///
+/// The pattern grammar is the signature grammar with three additions:
+///
+/// * `*` matches any single complete type.
+/// * `?` matches any single basic type (see [`Signature::is_basic`]).
+/// * `|` separates top-level alternatives, e.g. `a{sv}|a{ss}`. The first alternative that matches
+///   wins.
+///
+/// As with signatures, a dictionary is not an array, so `a*` does not match `a{sv}` but `a{**}`
+/// does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignaturePattern {
+    alternatives: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Unit,
+    Exact(Signature),
+    AnyType,
+    AnyBasic,
+    Array(Box<Node>),
+    Dict {
+        key: Box<Node>,
+        value: Box<Node>,
+    },
+    Structure(Vec<Node>),
+    #[cfg(feature = "gvariant")]
+    Maybe(Box<Node>),
+}
+
+/// The signatures bound to the wildcards of a [`SignaturePattern`] by a successful match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'s> {
+    alternative: usize,
+    signatures: Vec<&'s Signature>,
+}
+
+impl SignaturePattern {
+    /// Whether `signature` matches any of the alternatives of this pattern.
+    pub fn matches(&self, signature: &Signature) -> bool {
+        self.captures(signature).is_some()
+    }
+
+    /// Match `signature` and return the signatures bound to each wildcard.
+    ///
+    /// Returns `None` if `signature` doesn't match.
+    pub fn captures<'s>(&self, signature: &'s Signature) -> Option<Captures<'s>> {
+        let mut signatures = Vec::new();
+        for (alternative, node) in self.alternatives.iter().enumerate() {
+            if node.matches(signature, &mut signatures) {
+                return Some(Captures {
+                    alternative,
+                    signatures,
+                });
+            }
+            signatures.clear();
+        }
+
+        None
+    }
+}
+
+impl<'s> Captures<'s> {
+    /// The index of the alternative that matched.
+    pub fn alternative(&self) -> usize {
+        self.alternative
+    }
+
+    /// The signature bound to the wildcard at `index`, counting from the left of the pattern.
+    pub fn get(&self, index: usize) -> Option<&'s Signature> {
+        self.signatures.get(index).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'s Signature> + '_ {
+        self.signatures.iter().copied()
+    }
+}
+
+impl Node {
+    fn matches<'s>(&self, signature: &'s Signature, captures: &mut Vec<&'s Signature>) -> bool {
+        match (self, signature) {
+            (Node::Unit, Signature::Unit) => true,
+            (Node::Exact(expected), signature) => expected == signature,
+            (Node::AnyType, Signature::Unit) => false,
+            (Node::AnyType, signature) => {
+                captures.push(signature);
+
+                true
+            }
+            (Node::AnyBasic, signature) if signature.is_basic() => {
+                captures.push(signature);
+
+                true
+            }
+            (Node::Array(child), Signature::Array(signature)) => child.matches(signature, captures),
+            (
+                Node::Dict { key, value },
+                Signature::Dict {
+                    key: key_signature,
+                    value: value_signature,
+                },
+            ) => key.matches(key_signature, captures) && value.matches(value_signature, captures),
+            (Node::Structure(nodes), Signature::Structure(fields)) => {
+                let mut fields = fields.iter();
+                for node in nodes {
+                    match fields.next() {
+                        Some(field) if node.matches(field, captures) => (),
+                        _ => return false,
+                    }
+                }
+
+                fields.next().is_none()
+            }
+            #[cfg(feature = "gvariant")]
+            (Node::Maybe(child), Signature::Maybe(signature)) => child.matches(signature, captures),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for SignaturePattern {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        use nom::branch::alt;
+        use nom::character::complete::char;
+        use nom::combinator::{all_consuming, cut, map};
+        use nom::multi::{many0, many1, separated_list1};
+        use nom::sequence::{pair, preceded, terminated};
+
+        // Committing to a container once its opening character is seen, like the signature
+        // parser does, so that errors point at the offending character.
+        fn node(s: &str) -> nom::IResult<&str, Node> {
+            let dict = map(
+                preceded(
+                    pair(char('a'), char('{')),
+                    cut(terminated(pair(node, node), char('}'))),
+                ),
+                |(key, value)| Node::Dict {
+                    key: Box::new(key),
+                    value: Box::new(value),
+                },
+            );
+            let array = map(preceded(char('a'), cut(node)), |child| {
+                Node::Array(Box::new(child))
+            });
+            let structure = map(
+                preceded(char('('), cut(terminated(many1(node), char(')')))),
+                Node::Structure,
+            );
+            #[cfg(feature = "gvariant")]
+            let maybe = map(preceded(char('m'), cut(node)), |child| {
+                Node::Maybe(Box::new(child))
+            });
+
+            alt((
+                map(simple_type, Node::Exact),
+                map(char('*'), |_| Node::AnyType),
+                map(char('?'), |_| Node::AnyBasic),
+                dict,
+                array,
+                structure,
+                #[cfg(feature = "gvariant")]
+                maybe,
+            ))(s)
+        }
+
+        // Same as for signatures, multiple top-level types form a structure.
+        let alternative = map(many0(node), |mut nodes| match nodes.len() {
+            0 => Node::Unit,
+            1 => nodes.remove(0),
+            _ => Node::Structure(nodes),
+        });
+
+        let (_, alternatives) =
+            all_consuming(separated_list1(char('|'), alternative))(s).map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => ParseError::new(s, e.input),
+                nom::Err::Incomplete(_) => ParseError::new(s, ""),
+            })?;
+
+        Ok(SignaturePattern { alternatives })
+    }
+}
+
+impl Display for SignaturePattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, node) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            node.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Node::Unit => Ok(()),
+            Node::Exact(signature) => signature.fmt(f),
+            Node::AnyType => write!(f, "*"),
+            Node::AnyBasic => write!(f, "?"),
+            Node::Array(child) => write!(f, "a{}", child),
+            Node::Dict { key, value } => write!(f, "a{{{}{}}}", key, value),
+            Node::Structure(nodes) => {
+                write!(f, "(")?;
+                for node in nodes {
+                    node.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            #[cfg(feature = "gvariant")]
+            Node::Maybe(child) => write!(f, "m{}", child),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn wildcards() {
+        let pattern: SignaturePattern = "a{s*}".parse().unwrap();
+        assert!(pattern.matches(&sig("a{sv}")));
+        assert!(pattern.matches(&sig("a{sa(ux)}")));
+        assert!(!pattern.matches(&sig("a{uv}")));
+        assert!(!pattern.matches(&sig("a(sv)")));
+
+        let pattern: SignaturePattern = "(o?)".parse().unwrap();
+        assert!(pattern.matches(&sig("(os)")));
+        assert!(pattern.matches(&sig("(ou)")));
+        assert!(!pattern.matches(&sig("(ov)")));
+        assert!(!pattern.matches(&sig("(oas)")));
+        assert!(!pattern.matches(&sig("(osu)")));
+
+        let pattern: SignaturePattern = "a*".parse().unwrap();
+        assert!(pattern.matches(&sig("as")));
+        assert!(!pattern.matches(&sig("a{ss}")));
+
+        let pattern: SignaturePattern = "".parse().unwrap();
+        assert!(pattern.matches(&sig("")));
+        assert!(!pattern.matches(&sig("s")));
+    }
+
+    #[test]
+    fn alternation() {
+        let pattern: SignaturePattern = "a{sv}|a{ss}".parse().unwrap();
+        assert!(pattern.matches(&sig("a{sv}")));
+        assert!(pattern.matches(&sig("a{ss}")));
+        assert!(!pattern.matches(&sig("a{su}")));
+        assert_eq!(pattern.captures(&sig("a{ss}")).unwrap().alternative(), 1);
+    }
+
+    #[test]
+    fn captures() {
+        let pattern: SignaturePattern = "s*|(s?)a{?*}".parse().unwrap();
+        let signature = sig("(su)a{oa(ux)}");
+        let captures = pattern.captures(&signature).unwrap();
+        assert_eq!(captures.alternative(), 1);
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(0).unwrap(), &Signature::U32);
+        assert_eq!(captures.get(1).unwrap(), &Signature::ObjectPath);
+        assert_eq!(captures.get(2).unwrap(), "a(ux)");
+        assert!(captures.get(3).is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        for s in ["a{s*}", "(o?)", "a{sv}|a{ss}", "|s", "a(*?)"] {
+            let pattern: SignaturePattern = s.parse().unwrap();
+            assert_eq!(pattern.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for s in ["a", "a{*}", "()", "(*", "z", "*|)", "a{s*"] {
+            assert!(s.parse::<SignaturePattern>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn error_offsets() {
+        for (s, offset) in [
+            ("a{s*", 4),
+            ("a{**x}", 4),
+            ("a{*}", 3),
+            ("(s!)", 2),
+            ("(*", 2),
+            ("a(s)|ak", 6),
+            ("s|a{s(u}", 7),
+        ] {
+            let error = s.parse::<SignaturePattern>().unwrap_err();
+            assert_eq!(error.offset(), offset, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use serde_json::{json, Value as Json};
+
+use super::Signature;
+
+/// The pattern of a valid object path.
+pub(crate) const OBJECT_PATH_PATTERN: &str = "^/([A-Za-z0-9_]+(/[A-Za-z0-9_]+)*)?$";
+/// The pattern of the decimal strings representing `x` values. The range isn't checked.
+pub(crate) const I64_PATTERN: &str = "^-?(0|[1-9][0-9]*)$";
+/// The pattern of the decimal strings representing `t` values. The range isn't checked.
+pub(crate) const U64_PATTERN: &str = "^(0|[1-9][0-9]*)$";
+
+impl Signature {
+    /// A JSON Schema (draft 2020-12) fragment describing the JSON representation of values of
+    /// this type.
+    ///
+    /// The JSON representation is:
+    ///
+    /// * numbers for all numeric types but `x` and `t`, with their range as `minimum` and
+    ///   `maximum`,
+    /// * decimal strings for `x` and `t`, as JSON numbers are commonly parsed as doubles, which
+    ///   only hold integers up to 2^53 exactly,
+    /// * strings for strings, signatures and object paths,
+    /// * objects for dictionaries with string-like (`s`, `o` or `g`) keys and arrays of
+    ///   `[key, value]` pairs for any other dictionary,
+    /// * fixed-length arrays (tuples) for structures and an empty array for the unit type,
+    /// * `{"signature": "...", "value": ...}` objects for variants and
+    /// * the child value or `null` for maybes, except for maybes of maybes, for which `null` would
+    ///   be ambiguous: those are arrays of zero or one child value.
+    pub fn json_schema(&self) -> Json {
+        match self {
+            Signature::Unit => json!({ "type": "array", "maxItems": 0 }),
+            Signature::U8 => integer(u8::MIN as i128, u8::MAX as i128),
+            Signature::Bool => json!({ "type": "boolean" }),
+            Signature::I16 => integer(i16::MIN as i128, i16::MAX as i128),
+            Signature::U16 => integer(u16::MIN as i128, u16::MAX as i128),
+            Signature::I32 => integer(i32::MIN as i128, i32::MAX as i128),
+            Signature::U32 => integer(u32::MIN as i128, u32::MAX as i128),
+            Signature::I64 => json!({ "type": "string", "pattern": I64_PATTERN }),
+            Signature::U64 => json!({ "type": "string", "pattern": U64_PATTERN }),
+            Signature::F64 => json!({ "type": "number" }),
+            Signature::Str | Signature::Signature => json!({ "type": "string" }),
+            Signature::ObjectPath => json!({ "type": "string", "pattern": OBJECT_PATH_PATTERN }),
+            Signature::Value => json!({
+                "type": "object",
+                "properties": {
+                    "signature": { "type": "string" },
+                    "value": {},
+                },
+                "required": ["signature", "value"],
+                "additionalProperties": false,
+            }),
+            #[cfg(unix)]
+            Signature::Fd => integer(0, u32::MAX as i128),
+            Signature::Array(child) => json!({ "type": "array", "items": child.json_schema() }),
+            Signature::Dict { key, value } if key.is_string_like() => json!({
+                "type": "object",
+                "additionalProperties": value.json_schema(),
+            }),
+            Signature::Dict { key, value } => json!({
+                "type": "array",
+                "items": {
+                    "type": "array",
+                    "prefixItems": [key.json_schema(), value.json_schema()],
+                    "items": false,
+                    "minItems": 2,
+                },
+            }),
+            Signature::Structure(fields) => {
+                let fields: Vec<_> = fields.iter().map(Signature::json_schema).collect();
+                let len = fields.len();
+
+                json!({
+                    "type": "array",
+                    "prefixItems": fields,
+                    "items": false,
+                    "minItems": len,
+                })
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) if matches!(**child, Signature::Maybe(_)) => json!({
+                "type": "array",
+                "items": child.json_schema(),
+                "maxItems": 1,
+            }),
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => json!({
+                "anyOf": [child.json_schema(), { "type": "null" }],
+            }),
+        }
+    }
+
+    /// The TypeScript type of the JSON representation of values of this type.
+    ///
+    /// See [`Signature::json_schema`] for the representation.
+    pub fn typescript_type(&self) -> String {
+        match self {
+            Signature::Unit => "[]".to_string(),
+            Signature::U8
+            | Signature::I16
+            | Signature::U16
+            | Signature::I32
+            | Signature::U32
+            | Signature::F64 => "number".to_string(),
+            Signature::I64 | Signature::U64 => "string".to_string(),
+            #[cfg(unix)]
+            Signature::Fd => "number".to_string(),
+            Signature::Bool => "boolean".to_string(),
+            Signature::Str | Signature::Signature | Signature::ObjectPath => "string".to_string(),
+            Signature::Value => "{ signature: string; value: unknown }".to_string(),
+            Signature::Array(child) => format!("{}[]", child.typescript_element_type()),
+            Signature::Dict { key, value } if key.is_string_like() => {
+                format!("{{ [key: string]: {} }}", value.typescript_type())
+            }
+            Signature::Dict { key, value } => {
+                format!("[{}, {}][]", key.typescript_type(), value.typescript_type())
+            }
+            Signature::Structure(fields) => {
+                let fields: Vec<_> = fields.iter().map(Signature::typescript_type).collect();
+
+                format!("[{}]", fields.join(", "))
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) if matches!(**child, Signature::Maybe(_)) => {
+                format!("[] | [{}]", child.typescript_type())
+            }
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(child) => format!("{} | null", child.typescript_type()),
+        }
+    }
+
+    /// The TypeScript type, parenthesized if needed to be used as an array element.
+    fn typescript_element_type(&self) -> String {
+        match self {
+            #[cfg(feature = "gvariant")]
+            Signature::Maybe(_) => format!("({})", self.typescript_type()),
+            _ => self.typescript_type(),
+        }
+    }
+
+    /// Whether values of this type are represented as JSON strings.
+    pub(crate) fn is_string_like(&self) -> bool {
+        matches!(
+            self,
+            Signature::Str | Signature::ObjectPath | Signature::Signature
+        )
+    }
+}
+
+fn integer(minimum: i128, maximum: i128) -> Json {
+    json!({ "type": "integer", "minimum": minimum, "maximum": maximum })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(s: &str) -> Signature {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn json_schema() {
+        assert_eq!(
+            sig("y").json_schema(),
+            json!({ "type": "integer", "minimum": 0, "maximum": 255 })
+        );
+        assert_eq!(
+            sig("t").json_schema(),
+            json!({ "type": "string", "pattern": U64_PATTERN })
+        );
+        assert_eq!(sig("(xt)").typescript_type(), "[string, string]");
+        assert_eq!(
+            sig("a{sv}").json_schema(),
+            json!({
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        "signature": { "type": "string" },
+                        "value": {},
+                    },
+                    "required": ["signature", "value"],
+                    "additionalProperties": false,
+                },
+            })
+        );
+        assert_eq!(
+            sig("a(sb)").json_schema(),
+            json!({
+                "type": "array",
+                "items": {
+                    "type": "array",
+                    "prefixItems": [{ "type": "string" }, { "type": "boolean" }],
+                    "items": false,
+                    "minItems": 2,
+                },
+            })
+        );
+        assert_eq!(
+            sig("a{ub}").json_schema()["items"]["prefixItems"][1],
+            json!({ "type": "boolean" })
+        );
+    }
+
+    #[test]
+    fn typescript_type() {
+        assert_eq!(
+            sig("a{sa(ux)}").typescript_type(),
+            "{ [key: string]: [number, string][] }"
+        );
+        assert_eq!(sig("a{ub}").typescript_type(), "[number, boolean][]");
+        assert_eq!(
+            sig("(ov)").typescript_type(),
+            "[string, { signature: string; value: unknown }]"
+        );
+        assert_eq!(Signature::Unit.typescript_type(), "[]");
+    }
+
+    #[cfg(feature = "gvariant")]
+    #[test]
+    fn maybe() {
+        assert_eq!(sig("ams").typescript_type(), "(string | null)[]");
+        assert_eq!(
+            sig("mb").json_schema(),
+            json!({ "anyOf": [{ "type": "boolean" }, { "type": "null" }] })
+        );
+        assert_eq!(sig("mmb").typescript_type(), "[] | [boolean | null]");
+        assert_eq!(
+            sig("mmb").json_schema(),
+            json!({
+                "type": "array",
+                "items": { "anyOf": [{ "type": "boolean" }, { "type": "null" }] },
+                "maxItems": 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! [`proptest`] strategies for signatures and values conforming to them.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::signature::{Signature, MAX_DBUS_LEN};
+use crate::value::Value;
+
+/// How deep containers are nested in generated signatures.
+///
+/// Along with [`MAX_FIELDS`], this keeps signatures well within the D-Bus limits.
+const MAX_DEPTH: u32 = 4;
+const MAX_FIELDS: usize = 3;
+const MAX_ITEMS: usize = 4;
+/// How deep variants are nested in generated values.
+const MAX_VARIANT_DEPTH: u32 = 2;
+
+/// Valid D-Bus signatures of a single complete type.
+pub fn signature() -> impl Strategy<Value = Signature> {
+    let leaf = prop_oneof![select(Signature::BASIC_TYPES), Just(Signature::Value)];
+
+    leaf.prop_recursive(MAX_DEPTH, 32, MAX_FIELDS as u32, |inner| {
+        prop_oneof![
+            inner
+                .clone()
+                .prop_map(|child| Signature::Array(child.into())),
+            (select(Signature::BASIC_TYPES), inner.clone()).prop_map(|(key, value)| {
+                Signature::Dict {
+                    key: key.into(),
+                    value: value.into(),
+                }
+            }),
+            vec(inner, 1..=MAX_FIELDS).prop_map(|fields| Signature::Structure(fields.into())),
+        ]
+    })
+    .prop_filter("signature too long", |signature| {
+        signature.string_len() <= MAX_DBUS_LEN
+    })
+}
+
+/// Values of type `signature`.
+///
+/// Floating point numbers are finite, so that values compare equal to themselves, and the keys of
+/// dictionaries are unique.
+pub fn value(signature: &Signature) -> BoxedStrategy<Value> {
+    value_at(signature, MAX_VARIANT_DEPTH)
+}
+
+/// A signature along with a value of that type.
+pub fn signature_and_value() -> impl Strategy<Value = (Signature, Value)> {
+    signature().prop_flat_map(|signature| (Just(signature.clone()), value(&signature)))
+}
+
+fn value_at(signature: &Signature, variant_depth: u32) -> BoxedStrategy<Value> {
+    match signature {
+        Signature::Unit => Just(Value::Unit).boxed(),
+        Signature::U8 => any::<u8>().prop_map(Value::U8).boxed(),
+        Signature::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+        Signature::I16 => any::<i16>().prop_map(Value::I16).boxed(),
+        Signature::U16 => any::<u16>().prop_map(Value::U16).boxed(),
+        Signature::I32 => any::<i32>().prop_map(Value::I32).boxed(),
+        Signature::U32 => any::<u32>().prop_map(Value::U32).boxed(),
+        Signature::I64 => any::<i64>().prop_map(Value::I64).boxed(),
+        Signature::U64 => any::<u64>().prop_map(Value::U64).boxed(),
+        #[cfg(unix)]
+        Signature::Fd => any::<u32>().prop_map(Value::Fd).boxed(),
+        Signature::F64 => {
+            use proptest::num::f64::{NEGATIVE, NORMAL, POSITIVE, SUBNORMAL, ZERO};
+
+            (POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO)
+                .prop_map(Value::F64)
+                .boxed()
+        }
+        Signature::Str => "[^\u{0}]{0,16}".prop_map(Value::Str).boxed(),
+        Signature::ObjectPath => "/|(/[A-Za-z0-9_]{1,8}){1,3}"
+            .prop_map(Value::ObjectPath)
+            .boxed(),
+        Signature::Signature => self::signature().prop_map(Value::Signature).boxed(),
+        Signature::Value => {
+            // Only basic types at the innermost level, so that nesting ends.
+            let inner = match variant_depth {
+                0 => select(Signature::BASIC_TYPES).boxed(),
+                _ => self::signature().boxed(),
+            };
+            let depth = variant_depth.saturating_sub(1);
+
+            inner
+                .prop_flat_map(move |signature| value_at(&signature, depth))
+                .prop_map(|value| Value::Variant(Box::new(value)))
+                .boxed()
+        }
+        Signature::Array(element) => {
+            let element = (**element).clone();
+
+            vec(value_at(&element, variant_depth), 0..=MAX_ITEMS)
+                .prop_map(move |items| Value::Array {
+                    element: element.clone(),
+                    items,
+                })
+                .boxed()
+        }
+        Signature::Dict { key, value } => {
+            let (key, value) = ((**key).clone(), (**value).clone());
+            let entry = (
+                value_at(&key, variant_depth),
+                value_at(&value, variant_depth),
+            );
+
+            vec(entry, 0..=MAX_ITEMS)
+                .prop_map(move |mut entries| {
+                    let mut i = 0;
+                    while i < entries.len() {
+                        if entries[..i].iter().any(|(k, _)| *k == entries[i].0) {
+                            entries.remove(i);
+                        } else {
+                            i += 1;
+                        }
+                    }
+
+                    Value::Dict {
+                        key: key.clone(),
+                        value: value.clone(),
+                        entries,
+                    }
+                })
+                .boxed()
+        }
+        Signature::Structure(fields) => fields
+            .iter()
+            .map(|field| value_at(field, variant_depth))
+            .collect::<Vec<_>>()
+            .prop_map(Value::Structure)
+            .boxed(),
+        #[cfg(feature = "gvariant")]
+        Signature::Maybe(child) => {
+            let child = (**child).clone();
+
+            proptest::option::of(value_at(&child, variant_depth))
+                .prop_map(move |value| Value::Maybe {
+                    child: child.clone(),
+                    value: value.map(Box::new),
+                })
+                .boxed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic_type::DynamicType;
+    use crate::encoding::{dbus, validate_bytes, Endian};
+    use crate::json::{from_json, to_json};
+    use crate::signature::Format;
+
+    proptest! {
+        #[test]
+        fn display_from_str_round_trip(signature in signature()) {
+            let s = signature.to_string();
+            prop_assert_eq!(s.parse::<Signature>().unwrap(), signature.clone());
+            prop_assert_eq!(signature.string_len(), s.len());
+        }
+
+        #[test]
+        fn eq_str(signature in signature()) {
+            let s = signature.to_string();
+            prop_assert_eq!(signature, s.as_str());
+        }
+
+        #[test]
+        fn within_dbus_limits(signature in signature()) {
+            prop_assert_eq!(signature.check_format(Format::DBus), vec![]);
+        }
+
+        #[test]
+        fn value_conforms((signature, value) in signature_and_value()) {
+            prop_assert_eq!(value.signature(), signature);
+        }
+
+        #[test]
+        fn dbus_round_trip((signature, value) in signature_and_value()) {
+            for endian in [Endian::Little, Endian::Big] {
+                let bytes = dbus::to_bytes(&value, endian).unwrap();
+                prop_assert!(validate_bytes(&signature, &bytes, endian, Format::DBus).is_ok());
+                prop_assert_eq!(dbus::from_bytes(&signature, &bytes, endian).unwrap(), value.clone());
+            }
+        }
+
+        #[test]
+        fn json_round_trip((signature, value) in signature_and_value()) {
+            // JSON objects don't keep the order of dictionary entries, so compare as JSON.
+            let json = to_json(&value).unwrap();
+            prop_assert_eq!(to_json(&from_json(&signature, &json).unwrap()).unwrap(), json);
+        }
+    }
+
+    #[cfg(feature = "gvariant")]
+    proptest! {
+        #[test]
+        fn gvariant_text_round_trip((_, value) in signature_and_value()) {
+            use crate::gvariant_text::{parse, print};
+
+            prop_assert_eq!(parse(&print(&value), None).unwrap(), value);
+        }
+    }
+}
@@ -2,7 +2,6 @@ use std::sync::Arc;
 
 use crate::{
     dynamic_type::DynamicType,
-    r#type::Type,
     signature::{FieldsSignatures, Signature},
 };
 
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::signature::{ChildSignature, FieldsSignatures, Signature};
 
 pub trait Type {
@@ -17,6 +19,28 @@ impl<T: Type> Type for [T] {
     });
 }
 
+impl<T: Type> Type for Vec<T> {
+    const SIGNATURE: &'static Signature = <[T]>::SIGNATURE;
+}
+
+impl<K: Type, V: Type, S> Type for HashMap<K, V, S> {
+    const SIGNATURE: &'static Signature = &Signature::Dict {
+        key: ChildSignature::Static {
+            child: K::SIGNATURE,
+        },
+        value: ChildSignature::Static {
+            child: V::SIGNATURE,
+        },
+    };
+}
+
+#[cfg(feature = "gvariant")]
+impl<T: Type> Type for Option<T> {
+    const SIGNATURE: &'static Signature = &Signature::Maybe(ChildSignature::Static {
+        child: T::SIGNATURE,
+    });
+}
+
 macro_rules! tuple_impls {
     ($($len:expr => ($($n:tt $name:ident)+))+) => {
         $(
@@ -76,6 +100,8 @@ basic_impls! {
     f32 => F64
     f64 => F64
     str => Str
+    String => Str
+    Signature => Signature
     bool => Bool
     () => Unit
 }
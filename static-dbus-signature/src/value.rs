@@ -0,0 +1,187 @@
+use crate::dynamic_type::DynamicType;
+use crate::r#type::Type;
+use crate::signature::{FieldsSignatures, Signature};
+
+/// A value of any type that can be described by a [`Signature`].
+///
+/// Containers carry the signatures of their children so that empty ones still have a complete
+/// signature.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The value of the unit signature, i.e. nothing.
+    Unit,
+    U8(u8),
+    Bool(bool),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    Signature(Signature),
+    ObjectPath(String),
+    /// A variant, i.e. a value along with its signature.
+    Variant(Box<Value>),
+    /// An index into the file descriptors passed along with the message.
+    #[cfg(unix)]
+    Fd(u32),
+    Array {
+        element: Signature,
+        items: Vec<Value>,
+    },
+    Dict {
+        key: Signature,
+        value: Signature,
+        entries: Vec<(Value, Value)>,
+    },
+    Structure(Vec<Value>),
+    #[cfg(feature = "gvariant")]
+    Maybe {
+        child: Signature,
+        value: Option<Box<Value>>,
+    },
+}
+
+impl DynamicType for Value {
+    fn signature(&self) -> Signature {
+        match self {
+            Value::Unit => Signature::Unit,
+            Value::U8(_) => Signature::U8,
+            Value::Bool(_) => Signature::Bool,
+            Value::I16(_) => Signature::I16,
+            Value::U16(_) => Signature::U16,
+            Value::I32(_) => Signature::I32,
+            Value::U32(_) => Signature::U32,
+            Value::I64(_) => Signature::I64,
+            Value::U64(_) => Signature::U64,
+            Value::F64(_) => Signature::F64,
+            Value::Str(_) => Signature::Str,
+            Value::Signature(_) => Signature::Signature,
+            Value::ObjectPath(_) => Signature::ObjectPath,
+            Value::Variant(_) => Signature::Value,
+            #[cfg(unix)]
+            Value::Fd(_) => Signature::Fd,
+            Value::Array { element, .. } => Signature::Array(element.clone().into()),
+            Value::Dict { key, value, .. } => Signature::Dict {
+                key: key.clone().into(),
+                value: value.clone().into(),
+            },
+            Value::Structure(fields) => Signature::Structure(FieldsSignatures::Dynamic {
+                fields: fields.iter().map(DynamicType::signature).collect(),
+            }),
+            #[cfg(feature = "gvariant")]
+            Value::Maybe { child, .. } => Signature::Maybe(child.clone().into()),
+        }
+    }
+}
+
+/// A valid object path, e.g. `/org/freedesktop/DBus`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectPath(String);
+
+impl ObjectPath {
+    /// Returns `None` if `path` isn't a valid object path.
+    pub fn new(path: impl Into<String>) -> Option<Self> {
+        let path = path.into();
+
+        is_valid_object_path(&path).then_some(ObjectPath(path))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<ObjectPath> for Value {
+    fn from(path: ObjectPath) -> Value {
+        Value::ObjectPath(path.0)
+    }
+}
+
+impl Type for ObjectPath {
+    const SIGNATURE: &'static Signature = &Signature::ObjectPath;
+}
+
+/// A variant, i.e. a value of any type.
+///
+/// [`Value`] has its own signature, so it can't have the signature of a variant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant(pub Value);
+
+impl From<Variant> for Value {
+    fn from(variant: Variant) -> Value {
+        Value::Variant(Box::new(variant.0))
+    }
+}
+
+impl Type for Variant {
+    const SIGNATURE: &'static Signature = &Signature::Value;
+}
+
+/// An index into the file descriptors passed along with a message.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fd(pub u32);
+
+#[cfg(unix)]
+impl From<Fd> for Value {
+    fn from(fd: Fd) -> Value {
+        Value::Fd(fd.0)
+    }
+}
+
+#[cfg(unix)]
+impl Type for Fd {
+    const SIGNATURE: &'static Signature = &Signature::Fd;
+}
+
+/// Whether `path` is a valid D-Bus object path.
+pub(crate) fn is_valid_object_path(path: &str) -> bool {
+    let elements = match path.strip_prefix('/') {
+        Some("") => return true,
+        Some(elements) => elements,
+        None => return false,
+    };
+
+    elements.split('/').all(|element| {
+        !element.is_empty()
+            && element
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature() {
+        let value = Value::Structure(vec![
+            Value::Str("foo".to_string()),
+            Value::Dict {
+                key: Signature::Str,
+                value: Signature::Value,
+                entries: vec![],
+            },
+            Value::Array {
+                element: "(ux)".parse().unwrap(),
+                items: vec![],
+            },
+        ]);
+        assert_eq!(value.signature(), "(sa{sv}a(ux))");
+        assert_eq!(Value::Variant(Box::new(Value::U8(1))).signature(), "v");
+    }
+
+    #[test]
+    fn object_path() {
+        for path in ["/", "/org", "/org/freedesktop/DBus", "/a_1/B"] {
+            assert!(is_valid_object_path(path), "{}", path);
+        }
+        for path in ["", "org", "//", "/org/", "/org//a", "/a-b", "/é"] {
+            assert!(!is_valid_object_path(path), "{}", path);
+        }
+    }
+}
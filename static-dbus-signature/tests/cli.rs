@@ -0,0 +1,126 @@
+use std::process::{Command, Output};
+
+use serde_json::{json, Value as Json};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_static-dbus-signature"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn run_json(args: &[&str]) -> (bool, Json) {
+    let output = run(args);
+    assert_eq!(stderr(&output), "");
+
+    (
+        output.status.success(),
+        serde_json::from_slice(&output.stdout).unwrap(),
+    )
+}
+
+fn invalid(signature: &str, offset: usize) -> Json {
+    json!({
+        "signature": signature,
+        "valid": false,
+        "error": {
+            "offset": offset,
+            "message": format!("invalid signature at offset {}", offset),
+        },
+    })
+}
+
+#[test]
+fn validate() {
+    let output = run(&["validate", "a{sv}", "a{s"]);
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "a{sv}: valid\n");
+    assert_eq!(stderr(&output), "a{s\n   ^ invalid signature at offset 3\n");
+
+    let (success, json) = run_json(&["validate", "--json", "a{sv}", "a{s"]);
+    assert!(!success);
+    assert_eq!(
+        json,
+        json!([{ "signature": "a{sv}", "valid": true }, invalid("a{s", 3)])
+    );
+}
+
+#[test]
+fn explain() {
+    let output = run(&["explain", "ai", "a("]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).starts_with("ai: "));
+    assert!(stdout(&output).contains("Rust type: "));
+    assert_eq!(stderr(&output), "a(\n  ^ invalid signature at offset 2\n");
+
+    let (success, json) = run_json(&["explain", "--json", "ai", "a("]);
+    assert!(!success);
+    assert_eq!(json.as_array().unwrap().len(), 2);
+    assert_eq!(json[0]["signature"], "ai");
+    assert_eq!(json[1], invalid("a(", 2));
+}
+
+#[test]
+fn layout() {
+    let output = run(&["layout", "--format", "dbus", "(yu)", "z"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).starts_with("(yu)\n  D-Bus: alignment 8, "));
+    assert_eq!(stderr(&output), "z\n^ invalid signature at offset 0\n");
+
+    let (success, json) = run_json(&["layout", "--json", "--format", "dbus", "(yu)", "z"]);
+    assert!(!success);
+    assert_eq!(json[0]["signature"], "(yu)");
+    assert_eq!(json[0]["formats"]["dbus"]["alignment"], 8);
+    assert_eq!(json[1], invalid("z", 0));
+}
+
+#[test]
+fn convert() {
+    let output = run(&["convert", "--format", "dbus", "as", "a"]);
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "as: portable to D-Bus\n");
+    assert_eq!(stderr(&output), "a\n ^ invalid signature at offset 1\n");
+
+    let (success, json) = run_json(&["convert", "--json", "--format", "dbus", "as", "a"]);
+    assert!(!success);
+    assert_eq!(
+        json,
+        json!([
+            { "signature": "as", "format": "dbus", "portable": true, "issues": [] },
+            invalid("a", 1),
+        ])
+    );
+}
+
+#[test]
+fn generate() {
+    let output = run(&["generate", "--name", "Pair", "(su)"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("pub struct Pair"));
+    assert_eq!(stderr(&output), "");
+
+    let output = run(&["generate", "(su)", "(s"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("pub struct Signature0"));
+    assert_eq!(stderr(&output), "(s\n  ^ invalid signature at offset 2\n");
+
+    let (success, json) = run_json(&["generate", "--json", "(su)", "(s"]);
+    assert!(!success);
+    assert!(json["code"]
+        .as_str()
+        .unwrap()
+        .contains("pub struct Signature0"));
+    assert_eq!(json["errors"], json!([invalid("(s", 2)]));
+
+    let (success, json) = run_json(&["generate", "--json", "--xml", "/nonexistent.xml"]);
+    assert!(!success);
+    assert_eq!(json["errors"][0]["xml"], "/nonexistent.xml");
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use static_dbus_signature::codegen::Generator;
+use static_dbus_signature::r#type::Type;
+use static_dbus_signature::signature::Signature;
+use static_dbus_signature::value::{ObjectPath, Value, Variant};
+
+#[allow(dead_code)]
+mod generated {
+    include!("fixtures/generated.rs");
+}
+
+use generated::*;
+
+const INTROSPECTION: &str = r#"
+    <node>
+      <interface name="org.freedesktop.DBus.Properties">
+        <method name="Get">
+          <arg type="s" name="interface_name" direction="in"/>
+          <arg type="s" name="propertyName" direction="in"/>
+          <arg type="v" name="value" direction="out"/>
+        </method>
+        <method name="Ping"/>
+        <signal name="PropertiesChanged">
+          <arg type="s" name="interface_name"/>
+          <arg type="a{sv}" name="changed_properties"/>
+          <arg type="as" name="type"/>
+        </signal>
+        <signal name="Renamed">
+          <arg type="s" name="name"/>
+          <arg type="s" name="name"/>
+          <arg type="u" name="type"/>
+          <arg type="u" name="type"/>
+        </signal>
+      </interface>
+    </node>"#;
+
+/// The generator of `fixtures/generated.rs`.
+fn generator() -> Generator {
+    let mut generator = Generator::new();
+    generator.add_signature("Foo", &"(sa(ux)a{sv})".parse().unwrap());
+    generator.add_signature("Names", &"as".parse().unwrap());
+    assert_eq!(
+        generator.add_signature("Names", &"ao".parse().unwrap()),
+        "Names2"
+    );
+    generator.add_signature("Handles", &"a{oh}".parse().unwrap());
+    generator.add_signature("Weights", &"a{dv}".parse().unwrap());
+    generator.add_signature("Formats", &"a{gs}".parse().unwrap());
+    generator.add_introspection(INTROSPECTION).unwrap();
+
+    generator
+}
+
+#[test]
+fn fixture_is_up_to_date() {
+    assert_eq!(
+        generator().generate(),
+        include_str!("fixtures/generated.rs")
+    );
+}
+
+#[test]
+fn signatures() {
+    let signature = |s: &str| s.parse::<Signature>().unwrap();
+
+    assert_eq!(Foo::SIGNATURE, &signature("(sa(ux)a{sv})"));
+    assert_eq!(FooField1Item::SIGNATURE, &signature("(ux)"));
+    assert_eq!(Names::SIGNATURE, &signature("as"));
+    assert_eq!(Names2::SIGNATURE, &signature("ao"));
+    assert_eq!(Handles::SIGNATURE, &signature("a{oh}"));
+    assert_eq!(GetArgs::SIGNATURE, &signature("(ss)"));
+    assert_eq!(GetReply::SIGNATURE, &signature("v"));
+    assert_eq!(PropertiesChangedArgs::SIGNATURE, &signature("(sa{sv}as)"));
+    assert_eq!(Weights::SIGNATURE, &signature("a{dv}"));
+    assert_eq!(Formats::SIGNATURE, &signature("a{gs}"));
+    assert_eq!(RenamedArgs::SIGNATURE, &signature("(ssuu)"));
+}
+
+#[test]
+fn construct() {
+    let foo = Foo {
+        field0: "foo".to_string(),
+        field1: vec![FooField1Item {
+            field0: 1,
+            field1: -1,
+        }],
+        field2: HashMap::from([("bar".to_string(), Variant(Value::U32(42)))]),
+    };
+    assert_eq!(foo.field1[0].field1, -1);
+
+    let path = ObjectPath::new("/org/freedesktop/DBus").unwrap();
+    let names = Names2(vec![path.clone()]);
+    assert_eq!(names.0[0].as_str(), "/org/freedesktop/DBus");
+    assert!(ObjectPath::new("org/freedesktop").is_none());
+
+    let weights = Weights(vec![(0.5, Variant(Value::U32(42)))]);
+    assert_eq!(weights.clone(), weights);
+    let formats = Formats(vec![("a{sv}".parse().unwrap(), "dictionary".to_string())]);
+    assert_eq!(formats.clone(), formats);
+
+    let renamed = RenamedArgs {
+        name: "old".to_string(),
+        name2: "new".to_string(),
+        r#type: 1,
+        type2: 2,
+    };
+    assert_ne!(renamed.name, renamed.name2);
+}
@@ -0,0 +1,103 @@
+// Generated by static-dbus-signature. Do not edit.
+
+use std::collections::HashMap;
+
+use static_dbus_signature::r#type::Type;
+use static_dbus_signature::signature::{ChildSignature, FieldsSignatures, Signature};
+use static_dbus_signature::value::{Fd, ObjectPath, Variant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FooField1Item {
+    pub field0: u32,
+    pub field1: i64,
+}
+
+impl Type for FooField1Item {
+    const SIGNATURE: &'static Signature = &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::U32, &Signature::I64] });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Foo {
+    pub field0: String,
+    pub field1: Vec<FooField1Item>,
+    pub field2: HashMap<String, Variant>,
+}
+
+impl Type for Foo {
+    const SIGNATURE: &'static Signature = &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::Str, &Signature::Array(ChildSignature::Static { child: &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::U32, &Signature::I64] }) }), &Signature::Dict { key: ChildSignature::Static { child: &Signature::Str }, value: ChildSignature::Static { child: &Signature::Value } }] });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Names(pub Vec<String>);
+
+impl Type for Names {
+    const SIGNATURE: &'static Signature = &Signature::Array(ChildSignature::Static { child: &Signature::Str });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Names2(pub Vec<ObjectPath>);
+
+impl Type for Names2 {
+    const SIGNATURE: &'static Signature = &Signature::Array(ChildSignature::Static { child: &Signature::ObjectPath });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handles(pub HashMap<ObjectPath, Fd>);
+
+impl Type for Handles {
+    const SIGNATURE: &'static Signature = &Signature::Dict { key: ChildSignature::Static { child: &Signature::ObjectPath }, value: ChildSignature::Static { child: &Signature::Fd } };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weights(pub Vec<(f64, Variant)>);
+
+impl Type for Weights {
+    const SIGNATURE: &'static Signature = &Signature::Dict { key: ChildSignature::Static { child: &Signature::F64 }, value: ChildSignature::Static { child: &Signature::Value } };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formats(pub Vec<(Signature, String)>);
+
+impl Type for Formats {
+    const SIGNATURE: &'static Signature = &Signature::Dict { key: ChildSignature::Static { child: &Signature::Signature }, value: ChildSignature::Static { child: &Signature::Str } };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetArgs {
+    pub interface_name: String,
+    pub property_name: String,
+}
+
+impl Type for GetArgs {
+    const SIGNATURE: &'static Signature = &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::Str, &Signature::Str] });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetReply(pub Variant);
+
+impl Type for GetReply {
+    const SIGNATURE: &'static Signature = &Signature::Value;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertiesChangedArgs {
+    pub interface_name: String,
+    pub changed_properties: HashMap<String, Variant>,
+    pub r#type: Vec<String>,
+}
+
+impl Type for PropertiesChangedArgs {
+    const SIGNATURE: &'static Signature = &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::Str, &Signature::Dict { key: ChildSignature::Static { child: &Signature::Str }, value: ChildSignature::Static { child: &Signature::Value } }, &Signature::Array(ChildSignature::Static { child: &Signature::Str })] });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenamedArgs {
+    pub name: String,
+    pub name2: String,
+    pub r#type: u32,
+    pub type2: u32,
+}
+
+impl Type for RenamedArgs {
+    const SIGNATURE: &'static Signature = &Signature::Structure(FieldsSignatures::Static { fields: &[&Signature::Str, &Signature::Str, &Signature::U32, &Signature::U32] });
+}
@@ -0,0 +1,72 @@
+use std::task::Poll;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures::future;
+use zruntime::executor::{self, multi, naive};
+
+const TASKS: u64 = 64;
+
+/// Let the other tasks run before continuing.
+async fn yield_now() {
+    let mut yielded = false;
+    future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+/// A CPU-bound task, yielding now and then like a request handler would.
+async fn crunch(seed: u64) -> u64 {
+    let mut x = seed;
+    for _ in 0..100 {
+        for _ in 0..2_000 {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+        }
+        yield_now().await;
+    }
+    x
+}
+
+fn cpu_bound(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cpu_bound");
+
+    group.bench_function("naive", |b| {
+        let mut executor = naive::Executor::new();
+        b.iter(|| {
+            let handles: Vec<_> = (0..TASKS).map(|i| executor.spawn(crunch(i))).collect();
+            executor.run();
+            for handle in handles {
+                executor.block_on(handle).unwrap();
+            }
+        })
+    });
+
+    group.bench_function("multi", |b| {
+        let mut executor = multi::Executor::new();
+        b.iter(|| {
+            // Spawned from a task, so that they start on a worker's own queue and get stolen by
+            // the rest.
+            let spawning = executor.spawn(async {
+                (0..TASKS)
+                    .map(|i| executor::spawn(crunch(i)))
+                    .collect::<Vec<_>>()
+            });
+            let handles = executor.block_on(spawning).unwrap();
+            for handle in handles {
+                executor.block_on(handle).unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, cpu_bound);
+criterion_main!(benches);
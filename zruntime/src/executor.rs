@@ -1 +1,312 @@
+pub mod local;
+pub mod multi;
 pub mod naive;
+mod task;
+
+pub use local::spawn_local;
+pub use task::{panic_message, JoinError, TaskHandle};
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, LocalKey, Thread},
+    time::Instant,
+};
+
+use futures::pin_mut;
+
+use crate::{reactor::Reactor, time};
+
+use task::{joinable, PanicHook};
+
+/// A type-erased task future, as executors store them.
+pub(crate) type BoxedTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The part of an executor that accepts new tasks, from any thread.
+pub(crate) trait Schedule: Send + Sync {
+    /// Queue `task` to be polled, returning a waker that schedules it again.
+    fn schedule(self: Arc<Self>, task: BoxedTask) -> Waker;
+
+    fn panic_hook(&self) -> Option<PanicHook>;
+}
+
+/// A handle to spawn tasks onto an executor, which can be cloned and sent to other threads.
+///
+/// Spawning wakes the executor if it's idle in `run`.
+#[derive(Clone)]
+pub struct Spawner {
+    executor: Arc<dyn Schedule>,
+}
+
+impl Spawner {
+    pub(crate) fn new(executor: Arc<dyn Schedule>) -> Self {
+        Self { executor }
+    }
+
+    pub fn spawn<F>(&self, future: F) -> TaskHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send,
+    {
+        let (future, cell) = joinable(future, self.executor.panic_hook());
+        let task = self.executor.clone().schedule(Box::pin(future));
+
+        TaskHandle::new(cell, task)
+    }
+
+    /// The spawner of the executor running on this thread, if any.
+    pub fn current() -> Option<Spawner> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Spawner>> = const { RefCell::new(None) };
+}
+
+/// Make `spawner` the current one on this thread while `f` runs.
+pub(crate) fn enter<R>(spawner: Spawner, f: impl FnOnce() -> R) -> R {
+    set_current(&CURRENT, spawner, f)
+}
+
+/// Set `current` to `value` while `f` runs, restoring the previous value afterwards, even if `f`
+/// panics.
+pub(crate) fn set_current<T: 'static, R>(
+    current: &'static LocalKey<RefCell<Option<T>>>,
+    value: T,
+    f: impl FnOnce() -> R,
+) -> R {
+    struct Reset<T: 'static> {
+        current: &'static LocalKey<RefCell<Option<T>>>,
+        previous: Option<T>,
+    }
+
+    impl<T> Drop for Reset<T> {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            self.current
+                .with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let previous = current.with(|current| current.borrow_mut().replace(value));
+    let _reset = Reset { current, previous };
+
+    f()
+}
+
+/// Spawn a task onto the executor running the current task.
+///
+/// # Panics
+///
+/// When called outside of an executor's `run` or `block_on`.
+pub fn spawn<F>(future: F) -> TaskHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send,
+{
+    Spawner::current()
+        .expect("`spawn` called outside of an executor")
+        .spawn(future)
+}
+
+/// Poll `f` on this thread until it completes, sleeping while it's pending.
+pub(crate) fn block_on<F: Future>(f: F) -> F::Output {
+    pin_mut!(f);
+
+    let parker = Parker::new();
+    let waker = Waker::from(parker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match f.as_mut().poll(&mut cx) {
+            Poll::Ready(val) => return val,
+            Poll::Pending => parker.park(),
+        }
+    }
+}
+
+/// The tasks ready to be polled on a single thread, in the order they were woken, as queued by
+/// their wakers from any thread.
+pub(crate) struct ReadyQueue<T> {
+    tasks: Mutex<VecDeque<T>>,
+    /// The thread running the tasks, if any, which pushes unpark.
+    runner: Mutex<Option<Arc<Parker>>>,
+}
+
+impl<T> ReadyQueue<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(VecDeque::new()),
+            runner: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn push(&self, task: T) {
+        self.tasks.lock().unwrap().push_back(task);
+        if let Some(runner) = &*self.runner.lock().unwrap() {
+            runner.unpark();
+        }
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        self.tasks.lock().unwrap().pop_front()
+    }
+
+    /// Poll the ready tasks with `poll` while `live` returns true, sleeping while none is ready.
+    ///
+    /// Timers and I/O events are checked before each task, so that they aren't starved if tasks
+    /// are always ready.
+    pub(crate) fn run(&self, live: impl Fn() -> bool, mut poll: impl FnMut(T)) {
+        self.with_runner(|parker| {
+            while live() {
+                poll_events();
+                match self.try_pop() {
+                    Some(task) => poll(task),
+                    None => parker.park(),
+                }
+            }
+        })
+    }
+
+    /// Poll `f` until it completes, polling the ready tasks with `poll` while it's pending.
+    pub(crate) fn block_on<F: Future>(&self, f: F, mut poll: impl FnMut(T)) -> F::Output {
+        self.with_runner(|parker| {
+            pin_mut!(f);
+            let waker = Waker::from(parker.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            loop {
+                if let Poll::Ready(output) = f.as_mut().poll(&mut cx) {
+                    return output;
+                }
+                // Only the tasks ready now, so that `f` gets polled again even if they keep
+                // waking each other.
+                poll_events();
+                let ready = self.tasks.lock().unwrap().len();
+                for _ in 0..ready {
+                    match self.try_pop() {
+                        Some(task) => poll(task),
+                        None => break,
+                    }
+                }
+                // Returns right away if `f` or a task was woken in the meantime.
+                if ready == 0 {
+                    parker.park();
+                }
+            }
+        })
+    }
+
+    /// Run `f` with a parker for this thread, which pushes unpark.
+    fn with_runner<R>(&self, f: impl FnOnce(&Arc<Parker>) -> R) -> R {
+        struct Reset<'a>(&'a Mutex<Option<Arc<Parker>>>);
+
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                *self.0.lock().unwrap() = None;
+            }
+        }
+
+        let parker = Parker::new();
+        *self.runner.lock().unwrap() = Some(parker.clone());
+        let _reset = Reset(&self.runner);
+
+        f(&parker)
+    }
+}
+
+/// Fire expired timers and wake the tasks whose I/O is ready, without blocking.
+///
+/// Executors call this between tasks, so that timers and I/O aren't starved while tasks are
+/// always ready. The reactor is only polled every few calls, as that costs a syscall.
+pub(crate) fn poll_events() {
+    const IO_INTERVAL: u32 = 32;
+
+    thread_local! {
+        static CALLS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    time::timers::fire_expired();
+    let calls = CALLS.get().wrapping_add(1);
+    CALLS.set(calls);
+    if calls.is_multiple_of(IO_INTERVAL) {
+        Reactor::get().poll_now();
+    }
+}
+
+/// Puts the thread that created it to sleep until unparked, firing timers and dispatching I/O
+/// events in the meantime.
+///
+/// This is where executors wait when they have nothing to poll.
+pub(crate) struct Parker {
+    thread: Thread,
+    notified: AtomicBool,
+    /// Whether the thread is waiting for I/O in the reactor, rather than parked.
+    polling: AtomicBool,
+}
+
+impl Parker {
+    pub(crate) fn new() -> Arc<Parker> {
+        Arc::new(Parker {
+            thread: thread::current(),
+            notified: AtomicBool::new(false),
+            polling: AtomicBool::new(false),
+        })
+    }
+
+    /// Sleep until unparked, returning right away if unparked already since the last park.
+    ///
+    /// Timers are fired before sleeping, and the sleep ends at the nearest deadline so that they
+    /// can fire on time. The sleep is spent waiting for I/O, unless another thread already is.
+    pub(crate) fn park(&self) {
+        let reactor = Reactor::get();
+
+        // Waits can also end spuriously, hence the flag.
+        while !self.notified.swap(false, Ordering::SeqCst) {
+            let timeout = time::timers::fire_expired()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let Some(mut poller) = reactor.try_lock() else {
+                reactor.idle(timeout);
+                continue;
+            };
+
+            // `unpark` interrupts `epoll_wait` from now on, so the flag only needs to be checked
+            // once more.
+            self.polling.store(true, Ordering::SeqCst);
+            if !self.notified.load(Ordering::SeqCst) {
+                poller.wait(timeout).expect("failed to wait for I/O events");
+            }
+            self.polling.store(false, Ordering::SeqCst);
+        }
+    }
+
+    pub(crate) fn unpark(&self) {
+        // Setting the flag before checking how the thread waits means a wake that races with
+        // `park` isn't lost: either `park` sees the flag, or this sees that it's polling.
+        self.notified.store(true, Ordering::SeqCst);
+        if self.polling.load(Ordering::SeqCst) {
+            Reactor::get().notify();
+        } else {
+            // The thread's park token makes `thread::park` return even if it's not parked yet.
+            self.thread.unpark();
+        }
+    }
+}
+
+impl Wake for Parker {
+    fn wake(self: Arc<Self>) {
+        self.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.unpark();
+    }
+}
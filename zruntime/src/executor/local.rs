@@ -0,0 +1,271 @@
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Wake, Waker},
+};
+
+use super::{
+    set_current,
+    task::{joinable, PanicHook},
+    ReadyQueue, TaskHandle,
+};
+
+type LocalTask = Pin<Box<dyn Future<Output = ()>>>;
+
+/// A single-threaded executor for futures that aren't `Send`, e.g. holding `Rc` or `RefCell`.
+///
+/// The executor isn't `Send` itself, so it can't be moved to another thread, and its tasks are
+/// only ever polled on the thread that created it. Their wakers can still be used from anywhere.
+pub struct LocalExecutor {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    /// The tasks that haven't completed, by id. A task is taken out while it's polled.
+    tasks: RefCell<HashMap<usize, (LocalTask, Arc<TaskWaker>)>>,
+    next_id: Cell<usize>,
+    /// The number of tasks that haven't completed, including one being polled.
+    live: Cell<usize>,
+    /// The ids of the tasks ready to be polled, which wakers push from any thread.
+    queue: Arc<ReadyQueue<usize>>,
+    panic_hook: RefCell<Option<PanicHook>>,
+}
+
+struct TaskWaker {
+    id: usize,
+    /// Whether the task is in the ready queue, so that it's only queued once.
+    scheduled: AtomicBool,
+    queue: Arc<ReadyQueue<usize>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            self.queue.push(self.id);
+        }
+    }
+}
+
+impl LocalExecutor {
+    pub fn new() -> LocalExecutor {
+        LocalExecutor {
+            inner: Rc::new(Inner {
+                tasks: RefCell::new(HashMap::new()),
+                next_id: Cell::new(0),
+                live: Cell::new(0),
+                queue: Arc::new(ReadyQueue::new()),
+                panic_hook: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Call `hook` with the payload of every panic in tasks spawned from now on, e.g. to log them.
+    pub fn set_panic_hook<H>(&mut self, hook: H)
+    where
+        H: Fn(&(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        *self.inner.panic_hook.borrow_mut() = Some(Arc::new(hook));
+    }
+
+    /// A handle to spawn tasks from tasks of this executor.
+    pub fn spawner(&self) -> LocalSpawner {
+        LocalSpawner {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Poll `f` on this thread until it completes, running the spawned tasks while it's pending.
+    pub fn block_on<F>(&mut self, f: F) -> F::Output
+    where
+        F: Future,
+    {
+        enter(self.spawner(), || {
+            self.inner.queue.block_on(f, |id| self.inner.poll(id))
+        })
+    }
+
+    pub fn spawn_local<F>(&mut self, future: F) -> TaskHandle<F::Output>
+    where
+        F: Future + 'static,
+    {
+        self.spawner().spawn_local(future)
+    }
+
+    /// Run the spawned tasks until they have all completed.
+    ///
+    /// Tasks can spawn more tasks with [`spawn_local`].
+    pub fn run(&mut self) {
+        enter(self.spawner(), || {
+            self.inner
+                .queue
+                .run(|| self.inner.live.get() > 0, |id| self.inner.poll(id))
+        });
+    }
+}
+
+impl Default for LocalExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inner {
+    fn poll(&self, id: usize) {
+        // Already completed, e.g. woken after being aborted.
+        let Some((mut future, waker)) = self.tasks.borrow_mut().remove(&id) else {
+            return;
+        };
+
+        waker.scheduled.store(false, Ordering::Release);
+        let cx_waker = Waker::from(waker.clone());
+        // The task is polled outside of the borrow, so that it can spawn more tasks.
+        if future
+            .as_mut()
+            .poll(&mut Context::from_waker(&cx_waker))
+            .is_ready()
+        {
+            self.live.set(self.live.get() - 1);
+        } else {
+            self.tasks.borrow_mut().insert(id, (future, waker));
+        }
+    }
+}
+
+/// A handle to spawn `!Send` tasks onto a [`LocalExecutor`], from its own thread.
+#[derive(Clone)]
+pub struct LocalSpawner {
+    inner: Rc<Inner>,
+}
+
+impl LocalSpawner {
+    pub fn spawn_local<F>(&self, future: F) -> TaskHandle<F::Output>
+    where
+        F: Future + 'static,
+    {
+        let (future, cell) = joinable(future, self.inner.panic_hook.borrow().clone());
+        let id = self.inner.next_id.get();
+        self.inner.next_id.set(id + 1);
+        let waker = Arc::new(TaskWaker {
+            id,
+            scheduled: AtomicBool::new(true),
+            queue: self.inner.queue.clone(),
+        });
+        self.inner
+            .tasks
+            .borrow_mut()
+            .insert(id, (Box::pin(future), waker.clone()));
+        self.inner.live.set(self.inner.live.get() + 1);
+        self.inner.queue.push(id);
+
+        TaskHandle::new(cell, Waker::from(waker))
+    }
+
+    /// The spawner of the local executor running on this thread, if any.
+    pub fn current() -> Option<LocalSpawner> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<LocalSpawner>> = const { RefCell::new(None) };
+}
+
+/// Make `spawner` the current one on this thread while `f` runs.
+fn enter<R>(spawner: LocalSpawner, f: impl FnOnce() -> R) -> R {
+    set_current(&CURRENT, spawner, f)
+}
+
+/// Spawn a `!Send` task onto the local executor running the current task.
+///
+/// # Panics
+///
+/// When called outside of a [`LocalExecutor`]'s `run` or `block_on`.
+pub fn spawn_local<F>(future: F) -> TaskHandle<F::Output>
+where
+    F: Future + 'static,
+{
+    LocalSpawner::current()
+        .expect("`spawn_local` called outside of a local executor")
+        .spawn_local(future)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::AtomicUsize, thread, time::Duration};
+
+    use futures::channel::oneshot;
+
+    use super::*;
+    use crate::executor::JoinError;
+
+    #[test]
+    fn shared_state() {
+        let mut local = LocalExecutor::new();
+        let counter = Rc::new(RefCell::new(0));
+        for _ in 0..3 {
+            let counter = counter.clone();
+            local
+                .spawn_local(async move {
+                    let inner = counter.clone();
+                    spawn_local(async move { *inner.borrow_mut() += 1 })
+                        .await
+                        .unwrap();
+                    *counter.borrow_mut() += 10;
+                })
+                .detach();
+        }
+        local.run();
+
+        assert_eq!(*counter.borrow(), 33);
+    }
+
+    #[test]
+    fn block_on_runs_spawned_tasks() {
+        let mut local = LocalExecutor::new();
+        let (sender, receiver) = oneshot::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.send(2).unwrap();
+        });
+
+        let sum = local.block_on(async move {
+            let spawned = spawn_local(async { Rc::new(1) });
+
+            *spawned.await.unwrap() + receiver.await.unwrap()
+        });
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn panic() {
+        let mut local = LocalExecutor::new();
+        let panics = Arc::new(AtomicUsize::new(0));
+        let hook_panics = panics.clone();
+        local.set_panic_hook(move |_| {
+            hook_panics.fetch_add(1, Ordering::Relaxed);
+        });
+        let panicking = local.spawn_local(async move {
+            let _rc = Rc::new(());
+            panic!("local oops");
+        });
+        local.run();
+
+        assert!(matches!(
+            local.block_on(panicking),
+            Err(JoinError::Panic(_))
+        ));
+        assert_eq!(panics.load(Ordering::Relaxed), 1);
+    }
+}
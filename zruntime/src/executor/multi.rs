@@ -0,0 +1,331 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    task::Waker,
+    thread::{self, JoinHandle},
+};
+
+use super::{
+    block_on, enter, poll_events,
+    task::{PanicHook, Queue, Task},
+    BoxedTask, Parker, Schedule, Spawner, TaskHandle,
+};
+
+/// A multi-threaded executor, running tasks on a fixed pool of worker threads.
+///
+/// Each worker has its own run queue, where the tasks it wakes or spawns go, and takes tasks from
+/// a global injector queue when it runs out, which is where tasks from other threads go. Workers
+/// with nothing to do steal half of another worker's queue, and sleep when there's nothing to
+/// steal either, until a task is pushed or a timer fires.
+pub struct Executor {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Executor {
+    /// An executor with a worker per available CPU.
+    pub fn new() -> Executor {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+        Self::with_threads(threads)
+    }
+
+    pub fn with_threads(threads: usize) -> Executor {
+        assert!(threads > 0, "an executor needs at least one worker");
+        let shared = Arc::new(Shared {
+            injector: Mutex::new(VecDeque::new()),
+            locals: (0..threads).map(|_| Mutex::new(VecDeque::new())).collect(),
+            sleepers: Mutex::new(Vec::new()),
+            live: AtomicUsize::new(0),
+            done: Mutex::new(()),
+            all_done: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            panic_hook: Mutex::new(None),
+        });
+        let workers = (0..threads)
+            .map(|index| {
+                let shared = shared.clone();
+                thread::Builder::new()
+                    .name(format!("zruntime-worker-{}", index))
+                    .spawn(move || Worker { shared, index }.run())
+                    .unwrap()
+            })
+            .collect();
+
+        Executor { shared, workers }
+    }
+
+    /// A handle to spawn tasks from other tasks or threads.
+    pub fn spawner(&self) -> Spawner {
+        Spawner::new(self.shared.clone())
+    }
+
+    /// Call `hook` with the payload of every panic in tasks spawned from now on, e.g. to log them.
+    pub fn set_panic_hook<H>(&mut self, hook: H)
+    where
+        H: Fn(&(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        *self.shared.panic_hook.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Run `f` on the calling thread, while the workers keep running the spawned tasks.
+    pub fn block_on<F>(&mut self, f: F) -> F::Output
+    where
+        F: Future,
+    {
+        enter(self.spawner(), || block_on(f))
+    }
+
+    /// Spawn a task, which starts running right away on one of the workers.
+    pub fn spawn<F>(&mut self, future: F) -> TaskHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send,
+    {
+        self.spawner().spawn(future)
+    }
+
+    /// Wait until all the spawned tasks have completed.
+    pub fn run(&mut self) {
+        let mut done = self.shared.done.lock().unwrap();
+        while self.shared.live.load(Ordering::Acquire) > 0 {
+            done = self.shared.all_done.wait(done).unwrap();
+        }
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Executor {
+    /// Stop the workers, dropping the tasks that didn't complete.
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        for sleeper in self.shared.sleepers.lock().unwrap().drain(..) {
+            sleeper.unpark();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        self.shared.injector.lock().unwrap().clear();
+        for local in &self.shared.locals {
+            local.lock().unwrap().clear();
+        }
+    }
+}
+
+struct Shared {
+    /// Tasks spawned or woken from outside of the workers.
+    injector: Mutex<VecDeque<Arc<Task<Shared>>>>,
+    /// The run queue of each worker.
+    locals: Vec<Mutex<VecDeque<Arc<Task<Shared>>>>>,
+    /// The sleeping workers.
+    sleepers: Mutex<Vec<Arc<Parker>>>,
+    /// The number of tasks that haven't completed yet.
+    live: AtomicUsize,
+    done: Mutex<()>,
+    all_done: Condvar,
+    shutdown: AtomicBool,
+    panic_hook: Mutex<Option<PanicHook>>,
+}
+
+thread_local! {
+    /// The pool and index of the worker running on this thread.
+    static WORKER: Cell<Option<(*const Shared, usize)>> = const { Cell::new(None) };
+}
+
+impl Shared {
+    fn has_work(&self) -> bool {
+        !self.injector.lock().unwrap().is_empty()
+            || self
+                .locals
+                .iter()
+                .any(|local| !local.lock().unwrap().is_empty())
+    }
+}
+
+impl Schedule for Shared {
+    fn schedule(self: Arc<Self>, future: BoxedTask) -> Waker {
+        self.live.fetch_add(1, Ordering::Relaxed);
+        let task = Task::new(future, self.clone());
+        self.push(task.clone());
+
+        Waker::from(task)
+    }
+
+    fn panic_hook(&self) -> Option<PanicHook> {
+        self.panic_hook.lock().unwrap().clone()
+    }
+}
+
+impl Queue for Shared {
+    /// Queue a task on the current worker if it belongs to this pool, or on the injector.
+    fn push(&self, task: Arc<Task<Self>>) {
+        match WORKER.get() {
+            Some((shared, index)) if std::ptr::eq(shared, self) => {
+                self.locals[index].lock().unwrap().push_back(task);
+            }
+            _ => self.injector.lock().unwrap().push_back(task),
+        }
+
+        // Taking the lock means a worker about to sleep either sees the task or gets woken.
+        if let Some(sleeper) = self.sleepers.lock().unwrap().pop() {
+            sleeper.unpark();
+        }
+    }
+
+    fn task_done(&self) {
+        if self.live.fetch_sub(1, Ordering::AcqRel) == 1 {
+            drop(self.done.lock().unwrap());
+            self.all_done.notify_all();
+        }
+    }
+}
+
+struct Worker {
+    shared: Arc<Shared>,
+    index: usize,
+}
+
+impl Worker {
+    fn run(self) {
+        WORKER.set(Some((Arc::as_ptr(&self.shared), self.index)));
+        let parker = Parker::new();
+
+        enter(Spawner::new(self.shared.clone()), || {
+            while !self.shared.shutdown.load(Ordering::Acquire) {
+                match self.next_task() {
+                    Some(task) => task.poll(),
+                    None => self.sleep(&parker),
+                }
+            }
+        });
+    }
+
+    /// The next task to poll, checking timers and I/O events first so that busy workers don't
+    /// starve them.
+    fn next_task(&self) -> Option<Arc<Task<Shared>>> {
+        poll_events();
+        let local = &self.shared.locals[self.index];
+        if let Some(task) = local.lock().unwrap().pop_front() {
+            return Some(task);
+        }
+        if let Some(task) = self.shared.injector.lock().unwrap().pop_front() {
+            return Some(task);
+        }
+
+        self.steal()
+    }
+
+    /// Take half of the tasks of the first other worker that has some.
+    fn steal(&self) -> Option<Arc<Task<Shared>>> {
+        let count = self.shared.locals.len();
+        for victim in (1..count).map(|i| (self.index + i) % count) {
+            let mut stolen = {
+                let mut victim = self.shared.locals[victim].lock().unwrap();
+                let len = victim.len();
+                victim.split_off(len / 2)
+            };
+            if let Some(task) = stolen.pop_front() {
+                self.shared.locals[self.index]
+                    .lock()
+                    .unwrap()
+                    .extend(stolen);
+                return Some(task);
+            }
+        }
+
+        None
+    }
+
+    fn sleep(&self, parker: &Arc<Parker>) {
+        {
+            let mut sleepers = self.shared.sleepers.lock().unwrap();
+            // Checked under the lock, so that a task pushed since can't go unnoticed.
+            if self.shared.has_work() || self.shared.shutdown.load(Ordering::Acquire) {
+                return;
+            }
+            sleepers.push(parker.clone());
+        }
+        parker.park();
+        // Still listed if the wake didn't come from a push, e.g. from a stale unpark.
+        self.shared
+            .sleepers
+            .lock()
+            .unwrap()
+            .retain(|sleeper| !Arc::ptr_eq(sleeper, parker));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::executor::{spawn, JoinError};
+
+    #[test]
+    fn spawn_and_block_on() {
+        let mut pool = Executor::with_threads(4);
+        let spawner = pool.spawner();
+        let sum = pool.block_on(async move {
+            let handles: Vec<_> = (0..10u64)
+                .map(|i| spawner.spawn(async move { i * i }))
+                .collect();
+            let mut sum = 0;
+            for handle in handles {
+                sum += handle.await.unwrap();
+            }
+            sum
+        });
+        assert_eq!(sum, 285);
+    }
+
+    #[test]
+    fn work_stealing() {
+        let mut pool = Executor::with_threads(4);
+        // Spawned from a worker, so that they all start on its own queue.
+        let spawning = pool.spawn(async {
+            (0..100)
+                .map(|_| {
+                    spawn(async move {
+                        thread::sleep(Duration::from_millis(1));
+                        thread::current().name().unwrap().to_owned()
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let handles = pool.block_on(spawning).unwrap();
+        pool.run();
+
+        let mut workers: Vec<_> = handles
+            .into_iter()
+            .map(|handle| pool.block_on(handle).unwrap())
+            .collect();
+        workers.sort();
+        workers.dedup();
+        assert!(workers.len() > 1, "only ran on {:?}", workers);
+    }
+
+    #[test]
+    fn panic() {
+        let mut pool = Executor::with_threads(2);
+        let panicking = pool.spawn(async {
+            panic!("pool oops");
+        });
+        let fine = pool.spawn(async { 1 });
+        pool.run();
+
+        assert!(matches!(pool.block_on(panicking), Err(JoinError::Panic(_))));
+        assert_eq!(pool.block_on(fine).unwrap(), 1);
+    }
+}
@@ -1,68 +1,221 @@
 use std::{
+    any::Any,
     future::Future,
-    pin::Pin,
-    sync::mpsc::{channel, Receiver},
-    task::{Context, Poll},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::Waker,
 };
 
-use futures::pin_mut;
+use super::{
+    enter,
+    task::{PanicHook, Queue, Task},
+    BoxedTask, ReadyQueue, Schedule, Spawner, TaskHandle,
+};
 
 pub struct Executor {
-    tasks: Vec<Task>,
+    shared: Arc<Shared>,
 }
 
 impl Executor {
     pub fn new() -> Executor {
-        Executor { tasks: Vec::new() }
+        Executor {
+            shared: Arc::new(Shared {
+                queue: ReadyQueue::new(),
+                live: AtomicUsize::new(0),
+                panic_hook: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// A handle to spawn tasks from other tasks or threads.
+    pub fn spawner(&self) -> Spawner {
+        Spawner::new(self.shared.clone())
     }
 
+    /// Call `hook` with the payload of every panic in tasks spawned from now on, e.g. to log them.
+    ///
+    /// Panicking tasks don't stop the executor either way, their handles resolve to
+    /// [`JoinError::Panic`](super::JoinError::Panic).
+    pub fn set_panic_hook<H>(&mut self, hook: H)
+    where
+        H: Fn(&(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        *self.shared.panic_hook.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Poll `f` on this thread until it completes, running the spawned tasks while it's pending.
+    ///
+    /// Tasks that haven't completed by the time `f` does are left for the next `run` or
+    /// `block_on`.
     pub fn block_on<F>(&mut self, f: F) -> F::Output
     where
         F: Future,
     {
-        pin_mut!(f);
-
-        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
-
-        loop {
-            match Pin::new(&mut f).poll(&mut cx) {
-                Poll::Ready(val) => return val,
-                Poll::Pending => {}
-            }
-        }
+        enter(self.spawner(), || self.shared.queue.block_on(f, Task::poll))
     }
 
     pub fn spawn<F>(&mut self, future: F) -> TaskHandle<F::Output>
     where
         F: Future + Send + 'static,
+        F::Output: Send,
     {
-        let (sender, receiver) = channel();
-        let future = Box::pin(async move {
-            let res = future.await;
-            sender.send(res).unwrap();
-        });
-        self.tasks.push(Task { future });
-
-        TaskHandle { receiver }
+        self.spawner().spawn(future)
     }
 
+    /// Run the spawned tasks until they have all completed.
+    ///
+    /// Tasks are polled in the order they became ready, and only polled again once woken. The
+    /// thread sleeps while no task is ready, until a task is woken or a timer fires. Tasks can
+    /// spawn more tasks with [`spawn`](super::spawn).
     pub fn run(&mut self) {
-        while let Some(task) = self.tasks.pop() {
-            self.block_on(task.future);
-        }
+        enter(self.spawner(), || {
+            self.shared
+                .queue
+                .run(|| self.shared.live.load(Ordering::Acquire) > 0, Task::poll)
+        });
     }
 }
 
-pub struct TaskHandle<Ret> {
-    receiver: Receiver<Ret>,
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<Ret> TaskHandle<Ret> {
-    pub fn join(self) -> Ret {
-        self.receiver.recv().unwrap()
+struct Shared {
+    queue: ReadyQueue<Arc<Task<Shared>>>,
+    /// The number of tasks that haven't completed yet.
+    live: AtomicUsize,
+    panic_hook: Mutex<Option<PanicHook>>,
+}
+
+impl Schedule for Shared {
+    fn schedule(self: Arc<Self>, future: BoxedTask) -> Waker {
+        self.live.fetch_add(1, Ordering::Relaxed);
+        let task = Task::new(future, self.clone());
+        self.queue.push(task.clone());
+
+        Waker::from(task)
+    }
+
+    fn panic_hook(&self) -> Option<PanicHook> {
+        self.panic_hook.lock().unwrap().clone()
     }
 }
 
-struct Task {
-    future: Pin<Box<dyn Future<Output = ()>>>,
+impl Queue for Shared {
+    fn push(&self, task: Arc<Task<Self>>) {
+        self.queue.push(task);
+    }
+
+    fn task_done(&self) {
+        self.live.fetch_sub(1, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use futures::{
+        channel::{mpsc, oneshot},
+        SinkExt, StreamExt,
+    };
+
+    use super::*;
+    use crate::executor::{panic_message, spawn, JoinError};
+
+    #[test]
+    fn block_on_runs_spawned_tasks() {
+        let mut executor = Executor::new();
+        let queued = executor.spawn(async { 1 });
+
+        let sum = executor.block_on(async move {
+            let spawned = spawn(async { 2 });
+
+            queued.await.unwrap() + spawned.await.unwrap()
+        });
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn woken_from_another_thread() {
+        let mut executor = Executor::new();
+        let (sender, receiver) = oneshot::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            sender.send("hello").unwrap();
+        });
+        assert_eq!(executor.block_on(receiver).unwrap(), "hello");
+    }
+
+    #[test]
+    fn tasks_waiting_on_each_other() {
+        let mut executor = Executor::new();
+        let (mut ping_sender, mut ping_receiver) = mpsc::channel::<u32>(0);
+        let (mut pong_sender, mut pong_receiver) = mpsc::channel::<u32>(0);
+        let ping = executor.spawn(async move {
+            let mut pongs = Vec::new();
+            for i in 0..3 {
+                ping_sender.send(i).await.unwrap();
+                pongs.push(pong_receiver.next().await.unwrap());
+            }
+            pongs
+        });
+        executor
+            .spawn(async move {
+                while let Some(ping) = ping_receiver.next().await {
+                    pong_sender.send(ping * 10).await.unwrap();
+                }
+            })
+            .detach();
+        executor.run();
+
+        assert_eq!(executor.block_on(ping).unwrap(), [0, 10, 20]);
+    }
+
+    #[test]
+    fn spawn_from_tasks_and_threads() {
+        let mut executor = Executor::new();
+        let server = executor.spawn(async {
+            let children: Vec<_> = (0..3).map(|i| spawn(async move { i * i })).collect();
+            let mut sum = 0;
+            for child in children {
+                sum += child.await.unwrap();
+            }
+            sum
+        });
+        let spawner = executor.spawner();
+        let remote = thread::spawn(move || spawner.spawn(async { 7 }))
+            .join()
+            .unwrap();
+        executor.run();
+
+        assert!(remote.is_finished());
+        assert_eq!(executor.block_on(server).unwrap(), 5);
+        assert_eq!(executor.block_on(remote).unwrap(), 7);
+    }
+
+    #[test]
+    fn panic_hook() {
+        let mut executor = Executor::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        executor.set_panic_hook(move |payload| {
+            let message = panic_message(payload).unwrap_or("?").to_owned();
+            sender.lock().unwrap().send(message).unwrap();
+        });
+        let panicking = executor.spawn(async { panic!("oops") });
+        let fine = executor.spawn(async { 1 });
+        executor.run();
+
+        assert!(matches!(
+            executor.block_on(panicking),
+            Err(JoinError::Panic(_))
+        ));
+        assert_eq!(executor.block_on(fine).unwrap(), 1);
+        assert_eq!(receiver.try_recv().unwrap(), "oops");
+    }
 }
@@ -0,0 +1,429 @@
+use std::{
+    any::Any,
+    cell::UnsafeCell,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use super::BoxedTask;
+
+/// Called with the payload of each panic in a task, before the panic is reported to its handle.
+pub type PanicHook = Arc<dyn Fn(&(dyn Any + Send)) + Send + Sync>;
+
+/// Why a task didn't produce its output.
+#[derive(Debug)]
+pub enum JoinError {
+    /// The task was aborted before it completed.
+    Cancelled,
+    /// The task panicked, with the given payload.
+    Panic(Box<dyn Any + Send>),
+}
+
+impl Display for JoinError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            JoinError::Cancelled => write!(f, "task was cancelled"),
+            JoinError::Panic(payload) => match panic_message(&**payload) {
+                Some(message) => write!(f, "task panicked: {}", message),
+                None => write!(f, "task panicked"),
+            },
+        }
+    }
+}
+
+impl Error for JoinError {}
+
+/// The message of a panic payload, if it's a string as with `panic!`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+/// State shared between a task and its handle.
+pub(crate) struct TaskCell<T> {
+    state: Mutex<CellState<T>>,
+    aborted: AtomicBool,
+}
+
+struct CellState<T> {
+    /// Set when the task finishes, and taken by the handle.
+    output: Option<Result<T, JoinError>>,
+    finished: bool,
+    /// The task waiting on the handle.
+    waker: Option<Waker>,
+}
+
+impl<T> TaskCell<T> {
+    fn finish(&self, output: Result<T, JoinError>) {
+        let mut state = self.state.lock().unwrap();
+        state.output = Some(output);
+        state.finished = true;
+        let waker = state.waker.take();
+        drop(state);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Wrap `future` so that its output ends up in the returned cell, and it stops at its next poll
+/// once aborted.
+///
+/// Panics are caught and end up in the cell too, after being passed to `panic_hook`.
+pub(crate) fn joinable<F>(
+    future: F,
+    panic_hook: Option<PanicHook>,
+) -> (Joinable<F>, Arc<TaskCell<F::Output>>)
+where
+    F: Future,
+{
+    let cell = Arc::new(TaskCell {
+        state: Mutex::new(CellState {
+            output: None,
+            finished: false,
+            waker: None,
+        }),
+        aborted: AtomicBool::new(false),
+    });
+    let future = Joinable {
+        future: Box::pin(future),
+        cell: cell.clone(),
+        panic_hook,
+    };
+
+    (future, cell)
+}
+
+pub(crate) struct Joinable<F: Future> {
+    future: Pin<Box<F>>,
+    cell: Arc<TaskCell<F::Output>>,
+    panic_hook: Option<PanicHook>,
+}
+
+impl<F: Future> Future for Joinable<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.cell.aborted.load(Ordering::Acquire) {
+            self.cell.finish(Err(JoinError::Cancelled));
+            return Poll::Ready(());
+        }
+
+        // The future is dropped right after a panic, so it can't be observed in a broken state.
+        let poll = panic::catch_unwind(AssertUnwindSafe(|| self.future.as_mut().poll(cx)));
+        let output = match poll {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(output)) => Ok(output),
+            Err(payload) => {
+                if let Some(hook) = &self.panic_hook {
+                    hook(&*payload);
+                }
+                Err(JoinError::Panic(payload))
+            }
+        };
+        self.cell.finish(output);
+
+        Poll::Ready(())
+    }
+}
+
+/// Where a [`Task`] goes when it's woken: the run queue of its executor.
+pub(crate) trait Queue: Send + Sync + 'static {
+    fn push(&self, task: Arc<Task<Self>>);
+
+    /// Called once the task has completed.
+    fn task_done(&self);
+}
+
+/// A spawned task of an executor whose tasks can be polled from any thread.
+///
+/// The state makes sure the task is queued at most once and polled by one thread at a time, so
+/// a thread stealing a task never waits on another one polling it.
+pub(crate) struct Task<Q: Queue + ?Sized> {
+    /// Only accessed by the thread that moved the state to `RUNNING`, or on drop.
+    future: UnsafeCell<Option<BoxedTask>>,
+    state: AtomicU8,
+    queue: Arc<Q>,
+}
+
+/// Waiting to be woken.
+const IDLE: u8 = 0;
+/// In the run queue.
+const SCHEDULED: u8 = 1;
+/// Being polled.
+const RUNNING: u8 = 2;
+/// Woken while being polled, so queued again once the poll returns.
+const NOTIFIED: u8 = 3;
+const COMPLETE: u8 = 4;
+
+// SAFETY: the future is only accessed by the one thread polling the task, see `Task::poll`.
+unsafe impl<Q: Queue + ?Sized> Sync for Task<Q> {}
+
+impl<Q: Queue + ?Sized> Task<Q> {
+    /// A task that's about to be pushed to `queue`.
+    pub(crate) fn new(future: BoxedTask, queue: Arc<Q>) -> Arc<Self> {
+        Arc::new(Task {
+            future: UnsafeCell::new(Some(future)),
+            state: AtomicU8::new(SCHEDULED),
+            queue,
+        })
+    }
+
+    /// Poll the task, once it's been taken off the run queue.
+    pub(crate) fn poll(self: Arc<Self>) {
+        // The task is queued at most once, so no other thread can be polling it.
+        self.state.store(RUNNING, Ordering::Release);
+
+        // SAFETY: the state is `RUNNING`, which no other thread moves it out of.
+        let future = unsafe { &mut *self.future.get() };
+        let Some(f) = future.as_mut() else {
+            return;
+        };
+        let waker = Waker::from(self.clone());
+        if f.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+            *future = None;
+            self.state.store(COMPLETE, Ordering::Release);
+            self.queue.task_done();
+            return;
+        }
+
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // Woken during the poll.
+            self.state.store(SCHEDULED, Ordering::Release);
+            self.queue.clone().push(self);
+        }
+    }
+}
+
+impl<Q: Queue + ?Sized> Wake for Task<Q> {
+    fn wake(self: Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let next = match state {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return,
+            };
+            match self
+                .state
+                .compare_exchange_weak(state, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) if next == SCHEDULED => return self.queue.clone().push(self),
+                Ok(_) => return,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.clone().wake();
+    }
+}
+
+/// A handle to a spawned task, which resolves to the task's output.
+///
+/// Dropping the handle aborts the task, like [`TaskHandle::abort`]; use [`TaskHandle::detach`]
+/// to let the task run to completion without anyone waiting for it.
+#[must_use = "dropping a TaskHandle aborts the task"]
+pub struct TaskHandle<T> {
+    cell: Arc<TaskCell<T>>,
+    /// Wakes the task itself, so that it notices it was aborted.
+    task: Waker,
+    abort_on_drop: bool,
+}
+
+impl<T> TaskHandle<T> {
+    pub(crate) fn new(cell: Arc<TaskCell<T>>, task: Waker) -> Self {
+        Self {
+            cell,
+            task,
+            abort_on_drop: true,
+        }
+    }
+
+    /// Cancel the task at its next poll, dropping its future.
+    ///
+    /// The handle then resolves to [`JoinError::Cancelled`], unless the task already finished.
+    pub fn abort(&self) {
+        // Waking a completed task would queue it for nothing.
+        if self.is_finished() {
+            return;
+        }
+        self.cell.aborted.store(true, Ordering::Release);
+        self.task.wake_by_ref();
+    }
+
+    /// Let the task keep running after the handle is gone.
+    pub fn detach(mut self) {
+        self.abort_on_drop = false;
+    }
+
+    /// Whether the task has completed or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.cell.state.lock().unwrap().finished
+    }
+}
+
+impl<T> Future for TaskHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.cell.state.lock().unwrap();
+        if let Some(output) = state.output.take() {
+            return Poll::Ready(output);
+        }
+        assert!(!state.finished, "`TaskHandle` polled after completion");
+        state.waker = Some(cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for TaskHandle<T> {
+    fn drop(&mut self) {
+        if self.abort_on_drop {
+            self.abort();
+        }
+    }
+}
+
+impl<T> Debug for TaskHandle<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("TaskHandle")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use futures::future;
+
+    use super::*;
+    use crate::executor::naive::Executor;
+
+    #[test]
+    fn abort() {
+        let mut executor = Executor::new();
+        let pending = executor.spawn(future::pending::<()>());
+        let done = executor.spawn(async { 1 });
+        // Gives both tasks a poll.
+        let mut yielded = false;
+        executor.block_on(future::poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }));
+        assert!(done.is_finished());
+        pending.abort();
+        // Too late to cancel it.
+        done.abort();
+        executor.run();
+
+        assert!(matches!(
+            executor.block_on(pending),
+            Err(JoinError::Cancelled)
+        ));
+        assert_eq!(executor.block_on(done).unwrap(), 1);
+    }
+
+    #[test]
+    fn panic() {
+        let mut executor = Executor::new();
+        let handle = executor.spawn(async { panic!("oops") });
+        executor.run();
+
+        let error = executor.block_on(handle).unwrap_err();
+        assert_eq!(error.to_string(), "task panicked: oops");
+    }
+
+    #[test]
+    fn awaited_from_another_task() {
+        let mut executor = Executor::new();
+        let stuck = executor.spawn(future::pending::<()>());
+        stuck.abort();
+        let waiter =
+            executor.spawn(async move { matches!(stuck.await, Err(JoinError::Cancelled)) });
+        executor.run();
+
+        assert!(waiter.is_finished());
+        assert!(executor.block_on(waiter).unwrap());
+    }
+
+    #[test]
+    fn detach() {
+        let mut executor = Executor::new();
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        executor
+            .spawn(async move { sender.send(1).unwrap() })
+            .detach();
+        executor.run();
+
+        assert_eq!(executor.block_on(receiver).unwrap(), 1);
+    }
+
+    #[derive(Default)]
+    struct Tasks {
+        queued: Mutex<Vec<Arc<Task<Tasks>>>>,
+        done: AtomicBool,
+    }
+
+    impl Queue for Tasks {
+        fn push(&self, task: Arc<Task<Self>>) {
+            self.queued.lock().unwrap().push(task);
+        }
+
+        fn task_done(&self) {
+            self.done.store(true, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn wake_while_running() {
+        let tasks = Arc::new(Tasks::default());
+        let mut polls = 0;
+        let task = Task::new(
+            Box::pin(future::poll_fn(move |cx| {
+                polls += 1;
+                if polls == 2 {
+                    return Poll::Ready(());
+                }
+                cx.waker().wake_by_ref();
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })),
+            tasks.clone(),
+        );
+        task.poll();
+        // Queued again once the poll returned, and only once.
+        let queued = mem::take(&mut *tasks.queued.lock().unwrap());
+        assert_eq!(queued.len(), 1);
+        let task = queued.into_iter().next().unwrap();
+        let waker = Waker::from(task.clone());
+        task.poll();
+        assert!(tasks.done.load(Ordering::Acquire));
+
+        waker.wake();
+        assert!(tasks.queued.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+//! Non-blocking I/O on file descriptors, driven by the reactor.
+
+use std::{
+    future::poll_fn,
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, RawFd},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+use crate::reactor::{cvt, Interest, Reactor, Source};
+
+/// An I/O object in non-blocking mode, whose operations wait for readiness instead of blocking,
+/// e.g. a socket or a pipe.
+///
+/// Readiness is tracked per direction, so a task can read while another writes, through
+/// `&Async<T>`, but two tasks shouldn't wait to read, or to write, at the same time.
+pub struct Async<T: AsRawFd> {
+    source: Arc<Source>,
+    /// Only `None` once taken by `into_inner`.
+    io: Option<T>,
+}
+
+impl<T: AsRawFd> Async<T> {
+    /// Put `io` in non-blocking mode and register it with the reactor.
+    pub fn new(io: T) -> io::Result<Async<T>> {
+        set_nonblocking(io.as_raw_fd())?;
+        let source = Reactor::get().register(io.as_raw_fd())?;
+
+        Ok(Async {
+            source,
+            io: Some(io),
+        })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        self.io.as_ref().unwrap()
+    }
+
+    /// Deregister the I/O object, which stays in non-blocking mode.
+    pub fn into_inner(mut self) -> io::Result<T> {
+        Reactor::get().deregister(&self.source)?;
+
+        Ok(self.io.take().unwrap())
+    }
+
+    /// Wait until the I/O object is readable, according to the reactor.
+    pub async fn readable(&self) -> io::Result<()> {
+        poll_fn(|cx| self.source.poll_ready(Interest::Read, cx)).await;
+
+        Ok(())
+    }
+
+    /// Wait until the I/O object is writable, according to the reactor.
+    pub async fn writable(&self) -> io::Result<()> {
+        poll_fn(|cx| self.source.poll_ready(Interest::Write, cx)).await;
+
+        Ok(())
+    }
+
+    /// Run a non-blocking read operation until it doesn't fail with `WouldBlock`, waiting for
+    /// readability in between.
+    pub async fn read_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| poll_io(&self.source, Interest::Read, cx, || op(self.get_ref()))).await
+    }
+
+    /// Run a non-blocking write operation until it doesn't fail with `WouldBlock`, waiting for
+    /// writability in between.
+    pub async fn write_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| poll_io(&self.source, Interest::Write, cx, || op(self.get_ref()))).await
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for Async<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
+/// The I/O object is never pinned.
+impl<T: AsRawFd> Unpin for Async<T> {}
+
+impl<T: AsRawFd> Drop for Async<T> {
+    fn drop(&mut self) {
+        // Before the file descriptor gets closed, as epoll would then keep it in the interest list
+        // if it was duplicated.
+        if self.io.is_some() {
+            let _ = Reactor::get().deregister(&self.source);
+        }
+    }
+}
+
+/// Run `op` until it doesn't fail with `WouldBlock`, registering `cx` to be woken once it could
+/// succeed.
+fn poll_io<R>(
+    source: &Source,
+    interest: Interest,
+    cx: &mut Context<'_>,
+    mut op: impl FnMut() -> io::Result<R>,
+) -> Poll<io::Result<R>> {
+    loop {
+        let tick = ready!(source.poll_ready(interest, cx));
+        match op() {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => source.clear_ready(interest, tick),
+            result => return Poll::Ready(result),
+        }
+    }
+}
+
+impl<T: AsRawFd + Read> AsyncRead for Async<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let Async { source, io } = self.get_mut();
+
+        poll_io(source, Interest::Read, cx, || {
+            io.as_mut().unwrap().read(buf)
+        })
+    }
+}
+
+impl<T: AsRawFd> AsyncRead for &Async<T>
+where
+    for<'a> &'a T: Read,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_io(&self.source, Interest::Read, cx, || {
+            self.get_ref().read(buf)
+        })
+    }
+}
+
+impl<T: AsRawFd + Write> AsyncWrite for Async<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let Async { source, io } = self.get_mut();
+
+        poll_io(source, Interest::Write, cx, || {
+            io.as_mut().unwrap().write(buf)
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Async { source, io } = self.get_mut();
+
+        poll_io(source, Interest::Write, cx, || io.as_mut().unwrap().flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl<T: AsRawFd> AsyncWrite for &Async<T>
+where
+    for<'a> &'a T: Write,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        poll_io(&self.source, Interest::Write, cx, || {
+            self.get_ref().write(buf)
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        poll_io(&self.source, Interest::Write, cx, || self.get_ref().flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = cvt(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+    if flags & libc::O_NONBLOCK == 0 {
+        cvt(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::{executor::multi, time::timeout};
+
+    #[test]
+    fn pipe() {
+        let mut pool = multi::Executor::with_threads(2);
+        let (reader, writer) = io::pipe().unwrap();
+        let mut reader = Async::new(reader).unwrap();
+        let mut writer = Async::new(writer).unwrap();
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| i as u8).collect();
+        let mut expected = data.clone();
+        expected.push(b'!');
+        pool.spawn(async move {
+            // Much more than a pipe holds, so the writer waits for the reader several times.
+            writer.write_all(&data).await.unwrap();
+            writer
+                .write_with(|mut pipe| pipe.write(b"!"))
+                .await
+                .unwrap();
+        })
+        .detach();
+
+        let received = pool.block_on(async move {
+            let mut received = Vec::new();
+            reader.read_to_end(&mut received).await.unwrap();
+            received
+        });
+        assert!(received == expected);
+    }
+
+    #[test]
+    fn readiness() {
+        let mut pool = multi::Executor::with_threads(2);
+        let (quiet, noisy) = io::pipe().unwrap();
+        let quiet = Async::new(quiet).unwrap();
+        let noisy = Async::new(noisy).unwrap();
+        let waited = pool.block_on(timeout(Duration::from_millis(20), quiet.readable()));
+        assert!(waited.is_err());
+
+        pool.block_on(noisy.writable()).unwrap();
+        let noisy = noisy.into_inner().unwrap();
+        (&noisy).write_all(b"ping").unwrap();
+        let mut buf = [0; 8];
+        let read = pool.block_on(quiet.read_with(|mut pipe| pipe.read(&mut buf)));
+        assert_eq!(&buf[..read.unwrap()], b"ping");
+    }
+}
@@ -0,0 +1,5 @@
+pub mod executor;
+pub mod io;
+pub mod net;
+mod reactor;
+pub mod time;
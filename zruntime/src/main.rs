@@ -4,9 +4,7 @@ use std::{
     task::{Context, Poll},
 };
 
-mod executor;
-
-use executor::naive;
+use zruntime::executor::naive;
 
 struct MyFuture(u32);
 
@@ -37,5 +35,5 @@ fn main() {
 
     executor.run();
 
-    handle.join();
+    executor.block_on(handle).unwrap();
 }
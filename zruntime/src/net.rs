@@ -0,0 +1,9 @@
+//! Asynchronous TCP, UDP and Unix domain sockets.
+
+mod tcp;
+mod udp;
+mod unix;
+
+pub use tcp::{TcpListener, TcpStream};
+pub use udp::UdpSocket;
+pub use unix::{UCred, UnixDatagram, UnixListener, UnixStream};
@@ -0,0 +1,227 @@
+use std::{
+    io, mem,
+    net::{self, Shutdown, SocketAddr, ToSocketAddrs},
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+use crate::{io::Async, reactor::cvt};
+
+/// A TCP socket accepting connections.
+pub struct TcpListener(Async<net::TcpListener>);
+
+impl TcpListener {
+    /// Listen on `addr`, e.g. `"127.0.0.1:0"` for a port picked by the system.
+    ///
+    /// Resolving `addr` blocks, if it's a host name.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<TcpListener> {
+        Async::new(net::TcpListener::bind(addr)?).map(TcpListener)
+    }
+
+    /// Wait for a connection, returning it with the address of the peer.
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = self.0.read_with(|listener| listener.accept()).await?;
+
+        Ok((TcpStream(Async::new(stream)?), addr))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// A TCP connection, read and written through `AsyncRead` and `AsyncWrite`.
+///
+/// Closing it with `AsyncWrite` shuts down the writing half, so that the peer reads the end of
+/// the stream.
+pub struct TcpStream(Async<net::TcpStream>);
+
+impl TcpStream {
+    /// Connect to `addr`, waiting for the connection to be established.
+    pub async fn connect(addr: SocketAddr) -> io::Result<TcpStream> {
+        let domain = match addr {
+            SocketAddr::V4(_) => libc::AF_INET,
+            SocketAddr::V6(_) => libc::AF_INET6,
+        };
+        let flags = libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+        let fd = cvt(unsafe { libc::socket(domain, flags, 0) })?;
+        // Owned right away, so that it's closed on errors.
+        let socket = unsafe { net::TcpStream::from_raw_fd(fd) };
+
+        let (addr, len) = sockaddr(&addr);
+        let connect =
+            unsafe { libc::connect(fd, (&addr as *const libc::sockaddr_storage).cast(), len) };
+        match cvt(connect) {
+            Err(e) if e.raw_os_error() != Some(libc::EINPROGRESS) => return Err(e),
+            _ => (),
+        }
+
+        // Writable once connected, or once connecting failed.
+        let stream = Async::new(socket)?;
+        stream.writable().await?;
+        match stream.get_ref().take_error()? {
+            Some(e) => Err(e),
+            None => Ok(TcpStream(stream)),
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().peer_addr()
+    }
+
+    /// Shut down the reading half, the writing half or both, which doesn't need to wait.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.get_ref().shutdown(how)
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncRead for &TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncWrite for &TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+/// `addr` as a C socket address, with its length.
+fn sockaddr(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = unsafe {
+                &mut *(&mut storage as *mut libc::sockaddr_storage).cast::<libc::sockaddr_in>()
+            };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from_ne_bytes(addr.ip().octets());
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = unsafe {
+                &mut *(&mut storage as *mut libc::sockaddr_storage).cast::<libc::sockaddr_in6>()
+            };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_flowinfo = addr.flowinfo();
+            sin6.sin6_addr.s6_addr = addr.ip().octets();
+            sin6.sin6_scope_id = addr.scope_id();
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+
+    (storage, len as libc::socklen_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::executor::multi;
+
+    #[test]
+    fn echo() {
+        let mut pool = multi::Executor::with_threads(2);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = pool.spawn(async move {
+            let (stream, peer) = listener.accept().await.unwrap();
+            assert_eq!(peer, stream.peer_addr().unwrap());
+            let mut writer = &stream;
+            futures::io::copy(&stream, &mut writer).await.unwrap();
+            writer.close().await.unwrap();
+        });
+
+        let echoed = pool.block_on(async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            assert_eq!(stream.local_addr().unwrap().ip(), addr.ip());
+            stream.write_all(b"hello over TCP").await.unwrap();
+            stream.close().await.unwrap();
+            let mut echoed = String::new();
+            stream.read_to_string(&mut echoed).await.unwrap();
+            echoed
+        });
+        pool.block_on(server).unwrap();
+        assert_eq!(echoed, "hello over TCP");
+    }
+
+    #[test]
+    fn connection_refused() {
+        let mut pool = multi::Executor::with_threads(2);
+        // Closed right away, so nothing listens on the port.
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let error = pool.block_on(TcpStream::connect(closed)).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+}
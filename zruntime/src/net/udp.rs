@@ -0,0 +1,83 @@
+use std::{
+    io,
+    net::{self, SocketAddr, ToSocketAddrs},
+    os::fd::{AsRawFd, RawFd},
+};
+
+use crate::io::Async;
+
+/// A UDP socket, sending and receiving datagrams.
+pub struct UdpSocket(Async<net::UdpSocket>);
+
+impl UdpSocket {
+    /// Bind to `addr`, e.g. `"127.0.0.1:0"` for a port picked by the system.
+    ///
+    /// Resolving `addr` blocks, if it's a host name.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<UdpSocket> {
+        Async::new(net::UdpSocket::bind(addr)?).map(UdpSocket)
+    }
+
+    /// Only send to and receive from `addr`, with [`UdpSocket::send`] and [`UdpSocket::recv`].
+    pub fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        self.0.get_ref().connect(addr)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+
+    pub async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        self.0
+            .write_with(|socket| socket.send_to(buf, target))
+            .await
+    }
+
+    /// Wait for a datagram, returning its size and the address it came from.
+    ///
+    /// The rest of a datagram that doesn't fit in `buf` is lost.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.0.read_with(|socket| socket.recv_from(buf)).await
+    }
+
+    /// Send to the connected address.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_with(|socket| socket.send(buf)).await
+    }
+
+    /// Wait for a datagram from the connected address.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_with(|socket| socket.recv(buf)).await
+    }
+}
+
+impl AsRawFd for UdpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::naive;
+
+    #[test]
+    fn datagrams() {
+        let mut executor = naive::Executor::new();
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut buf = [0; 16];
+
+        executor.block_on(async {
+            a.send_to(b"ping", b.local_addr().unwrap()).await.unwrap();
+            let (len, from) = b.recv_from(&mut buf).await.unwrap();
+            assert_eq!((&buf[..len], from), (&b"ping"[..], a.local_addr().unwrap()));
+
+            b.connect(from).unwrap();
+            a.connect(b.local_addr().unwrap()).unwrap();
+            b.send(b"pong").await.unwrap();
+            let len = a.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"pong");
+        });
+    }
+}
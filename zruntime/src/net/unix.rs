@@ -0,0 +1,637 @@
+use std::{
+    io, mem,
+    net::Shutdown,
+    os::{
+        fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+        unix::{
+            ffi::OsStrExt,
+            net::{self, SocketAddr},
+        },
+    },
+    path::Path,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+use crate::{io::Async, reactor::cvt, time::sleep};
+
+/// The most file descriptors the kernel passes in one message.
+const MAX_FDS: usize = 253;
+
+/// The longest wait between attempts to connect to a listener with a full backlog.
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The credentials of the process at the other end of a Unix socket, as of when it connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UCred {
+    pub pid: libc::pid_t,
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+}
+
+/// A Unix stream socket accepting connections.
+pub struct UnixListener(Async<net::UnixListener>);
+
+impl UnixListener {
+    /// Listen on a new socket file at `path`, which must not exist yet.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixListener> {
+        Async::new(net::UnixListener::bind(path)?).map(UnixListener)
+    }
+
+    /// Wait for a connection, returning it with the address of the peer.
+    pub async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        let (stream, addr) = self.0.read_with(|listener| listener.accept()).await?;
+
+        Ok((UnixStream(Async::new(stream)?), addr))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+}
+
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+/// A Unix stream connection, read and written through `AsyncRead` and `AsyncWrite`.
+///
+/// Closing it with `AsyncWrite` shuts down the writing half, so that the peer reads the end of
+/// the stream.
+pub struct UnixStream(Async<net::UnixStream>);
+
+impl UnixStream {
+    /// Connect to the listener at `path`.
+    ///
+    /// Connecting to a local socket doesn't wait for the listener to accept, only for room in its
+    /// backlog if it's full.
+    pub async fn connect(path: impl AsRef<Path>) -> io::Result<UnixStream> {
+        let (addr, len) = sockaddr_un(path.as_ref())?;
+        let flags = libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+        let fd = cvt(unsafe { libc::socket(libc::AF_UNIX, flags, 0) })?;
+        // Owned right away, so that it's closed on errors.
+        let stream = Async::new(unsafe { net::UnixStream::from_raw_fd(fd) })?;
+
+        let mut backoff = Duration::from_millis(1);
+        loop {
+            let connect =
+                unsafe { libc::connect(fd, (&addr as *const libc::sockaddr_un).cast(), len) };
+            let Err(e) = cvt(connect) else {
+                break;
+            };
+            match e.raw_os_error() {
+                // Writable once connected, or once connecting failed.
+                Some(libc::EINPROGRESS) => {
+                    stream.writable().await?;
+                    match stream.get_ref().take_error()? {
+                        Some(e) => return Err(e),
+                        None => break,
+                    }
+                }
+                // The backlog of the listener is full. Unlike a TCP socket, the socket is left
+                // unconnected and reported writable, and nothing signals when there's room again,
+                // so try again a bit later.
+                Some(libc::EAGAIN) => {
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                }
+                _ => return Err(e),
+            }
+        }
+
+        Ok(UnixStream(stream))
+    }
+
+    /// A pair of connected sockets, e.g. to talk to a child process.
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = net::UnixStream::pair()?;
+
+        Ok((UnixStream(Async::new(a)?), UnixStream(Async::new(b)?)))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().peer_addr()
+    }
+
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        peer_cred(self.as_raw_fd())
+    }
+
+    /// Shut down the reading half, the writing half or both, which doesn't need to wait.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.get_ref().shutdown(how)
+    }
+
+    /// Write `buf` along with duplicates of `fds`, returning how much of `buf` was written.
+    ///
+    /// The file descriptors go along the first byte written, so `buf` must not be empty.
+    pub async fn send_fds(&self, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+        self.0
+            .write_with(|socket| send_with_fds(socket.as_raw_fd(), buf, fds))
+            .await
+    }
+
+    /// Read into `buf`, appending the file descriptors that came with the data to `fds`.
+    pub async fn recv_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        self.0
+            .read_with(|socket| recv_with_fds(socket.as_raw_fd(), buf, fds))
+            .await
+    }
+}
+
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncRead for &UnixStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncWrite for &UnixStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+/// A Unix datagram socket.
+pub struct UnixDatagram(Async<net::UnixDatagram>);
+
+impl UnixDatagram {
+    /// Bind to a new socket file at `path`, which must not exist yet.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<UnixDatagram> {
+        Async::new(net::UnixDatagram::bind(path)?).map(UnixDatagram)
+    }
+
+    /// A socket without an address, which can send but not be sent to unless connected.
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        Async::new(net::UnixDatagram::unbound()?).map(UnixDatagram)
+    }
+
+    /// A pair of connected sockets.
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = net::UnixDatagram::pair()?;
+
+        Ok((UnixDatagram(Async::new(a)?), UnixDatagram(Async::new(b)?)))
+    }
+
+    /// Only send to and receive from the socket at `path`, with [`UnixDatagram::send`] and
+    /// [`UnixDatagram::recv`].
+    pub fn connect(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.0.get_ref().connect(path)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+
+    /// The credentials of the peer, for sockets from [`UnixDatagram::pair`].
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        peer_cred(self.as_raw_fd())
+    }
+
+    pub async fn send_to(&self, buf: &[u8], path: impl AsRef<Path>) -> io::Result<usize> {
+        let path = path.as_ref();
+
+        self.0.write_with(|socket| socket.send_to(buf, path)).await
+    }
+
+    /// Wait for a datagram, returning its size and the address it came from.
+    ///
+    /// The rest of a datagram that doesn't fit in `buf` is lost.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.0.read_with(|socket| socket.recv_from(buf)).await
+    }
+
+    /// Send to the connected socket.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_with(|socket| socket.send(buf)).await
+    }
+
+    /// Wait for a datagram from the connected socket.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_with(|socket| socket.recv(buf)).await
+    }
+
+    /// Send `buf` as a datagram along with duplicates of `fds`, to the connected socket.
+    pub async fn send_fds(&self, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+        self.0
+            .write_with(|socket| send_with_fds(socket.as_raw_fd(), buf, fds))
+            .await
+    }
+
+    /// Wait for a datagram, appending the file descriptors that came with it to `fds`.
+    pub async fn recv_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        self.0
+            .read_with(|socket| recv_with_fds(socket.as_raw_fd(), buf, fds))
+            .await
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut cred: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    cvt(unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    })?;
+
+    Ok(UCred {
+        pid: cred.pid,
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+/// `path` as a C Unix socket address, with its length.
+fn sockaddr_un(path: &Path) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    let mut addr: libc::sockaddr_un = unsafe { mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+    let bytes = path.as_os_str().as_bytes();
+    if bytes.contains(&0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "socket paths must not contain nul bytes",
+        ));
+    }
+    // Room is left for the nul terminator.
+    if bytes.len() >= addr.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "socket paths must be shorter than {} bytes",
+                addr.sun_path.len()
+            ),
+        ));
+    }
+    for (dst, src) in addr.sun_path.iter_mut().zip(bytes) {
+        *dst = *src as libc::c_char;
+    }
+    let len = mem::offset_of!(libc::sockaddr_un, sun_path) + bytes.len() + 1;
+
+    Ok((addr, len as libc::socklen_t))
+}
+
+/// A buffer for control messages, aligned like their headers.
+fn control_buffer(fds: usize) -> Vec<u64> {
+    let len = unsafe { libc::CMSG_SPACE((fds * mem::size_of::<RawFd>()) as u32) } as usize;
+
+    vec![0; len.div_ceil(mem::size_of::<u64>())]
+}
+
+/// Send `buf` with an `SCM_RIGHTS` message carrying `fds`.
+fn send_with_fds(socket: RawFd, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+    if fds.len() > MAX_FDS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("can't send more than {} file descriptors at once", MAX_FDS),
+        ));
+    }
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut control = control_buffer(fds.len());
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = mem::size_of_val(control.as_slice()) as _;
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN((fds.len() * mem::size_of::<RawFd>()) as u32) as _;
+            let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+            for (i, fd) in fds.iter().enumerate() {
+                ptr::write_unaligned(data.add(i), fd.as_raw_fd());
+            }
+        }
+    }
+
+    let sent = unsafe { libc::sendmsg(socket, &msg, libc::MSG_NOSIGNAL) };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(sent as usize)
+}
+
+/// Receive into `buf`, taking ownership of the file descriptors of `SCM_RIGHTS` messages.
+fn recv_with_fds(socket: RawFd, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    let mut control = control_buffer(MAX_FDS);
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = mem::size_of_val(control.as_slice()) as _;
+
+    // Close-on-exec, so that the descriptors don't leak into child processes.
+    let received = unsafe { libc::recvmsg(socket, &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Owned before checking for truncation, so that the ones that did arrive get closed.
+    let mut received_fds = Vec::new();
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                for i in 0..len / mem::size_of::<RawFd>() {
+                    received_fds.push(OwnedFd::from_raw_fd(ptr::read_unaligned(data.add(i))));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(io::Error::other(
+            "control messages were truncated, losing file descriptors",
+        ));
+    }
+    fds.append(&mut received_fds);
+
+    Ok(received as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write, os::fd::AsFd, path::PathBuf};
+
+    use futures::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::executor::multi;
+
+    /// A socket path unique to this process and test, removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> TempPath {
+            let file = format!("zruntime-{}-{}.sock", std::process::id(), name);
+
+            TempPath(std::env::temp_dir().join(file))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn fd_passing_and_peer_cred() {
+        let mut pool = multi::Executor::with_threads(2);
+        let path = TempPath::new("stream");
+        let listener = UnixListener::bind(&path.0).unwrap();
+        assert_eq!(
+            listener.local_addr().unwrap().as_pathname(),
+            Some(path.0.as_path())
+        );
+        let server = pool.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let cred = stream.peer_cred().unwrap();
+            assert_eq!(cred.pid as u32, std::process::id());
+            assert_eq!((cred.uid, cred.gid), unsafe {
+                (libc::getuid(), libc::getgid())
+            });
+
+            let (reader, writer) = io::pipe().unwrap();
+            stream.send_fds(b"fd", &[writer.as_fd()]).await.unwrap();
+            drop(writer);
+            let mut received = String::new();
+            Async::new(reader)
+                .unwrap()
+                .read_to_string(&mut received)
+                .await
+                .unwrap();
+            received
+        });
+
+        pool.block_on(async {
+            let stream = UnixStream::connect(&path.0).await.unwrap();
+            assert_eq!(
+                stream.peer_addr().unwrap().as_pathname(),
+                Some(path.0.as_path())
+            );
+            assert!(stream.local_addr().unwrap().is_unnamed());
+            let mut buf = [0; 2];
+            let mut fds = Vec::new();
+            stream.recv_fds(&mut buf, &mut fds).await.unwrap();
+            assert_eq!((&buf, fds.len()), (b"fd", 1));
+            let mut pipe = File::from(fds.pop().unwrap());
+            pipe.write_all(b"hello through a passed fd").unwrap();
+            stream.shutdown(Shutdown::Both).unwrap();
+        });
+        assert_eq!(pool.block_on(server).unwrap(), "hello through a passed fd");
+    }
+
+    #[test]
+    fn pair() {
+        let mut pool = multi::Executor::with_threads(2);
+        let (mut a, mut b) = UnixStream::pair().unwrap();
+        let message = pool.block_on(async {
+            a.write_all(b"hello over a socket pair").await.unwrap();
+            a.close().await.unwrap();
+            let mut message = String::new();
+            b.read_to_string(&mut message).await.unwrap();
+            message
+        });
+        assert_eq!(message, "hello over a socket pair");
+    }
+
+    #[test]
+    fn datagrams() {
+        let mut pool = multi::Executor::with_threads(2);
+        let path = TempPath::new("dgram");
+        let server = UnixDatagram::bind(&path.0).unwrap();
+        let client = UnixDatagram::unbound().unwrap();
+        let from = pool.block_on(async {
+            let mut buf = [0; 16];
+            client.send_to(b"hello", &path.0).await.unwrap();
+            let (len, from) = server.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"hello");
+            client.connect(&path.0).unwrap();
+            client.send(b"again").await.unwrap();
+            let len = server.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"again");
+            from
+        });
+        assert!(from.is_unnamed());
+        assert!(client.local_addr().unwrap().is_unnamed());
+    }
+
+    #[test]
+    fn datagram_fd_passing() {
+        let mut pool = multi::Executor::with_threads(2);
+        let (a, b) = UnixDatagram::pair().unwrap();
+        assert_eq!(a.peer_cred().unwrap().pid as u32, std::process::id());
+        let (reader, writer) = io::pipe().unwrap();
+        let mut reader = Async::new(reader).unwrap();
+
+        let received = pool.block_on(async {
+            a.send_fds(b"take this", &[writer.as_fd()]).await.unwrap();
+            drop(writer);
+            let mut buf = [0; 16];
+            let mut fds = Vec::new();
+            let len = b.recv_fds(&mut buf, &mut fds).await.unwrap();
+            assert_eq!(&buf[..len], b"take this");
+            File::from(fds.pop().unwrap())
+                .write_all(b"datagram fd")
+                .unwrap();
+            let mut received = String::new();
+            reader.read_to_string(&mut received).await.unwrap();
+            received
+        });
+        assert_eq!(received, "datagram fd");
+    }
+
+    #[test]
+    fn connect_to_full_backlog() {
+        let mut pool = multi::Executor::with_threads(2);
+        let path = TempPath::new("backlog");
+        let listener = net::UnixListener::bind(&path.0).unwrap();
+        // Room for a single pending connection.
+        cvt(unsafe { libc::listen(listener.as_raw_fd(), 0) }).unwrap();
+        let listener = UnixListener(Async::new(listener).unwrap());
+
+        let connected = pool.block_on(async {
+            let first = UnixStream::connect(&path.0).await.unwrap();
+            let second = crate::executor::spawn(UnixStream::connect(path.0.clone()));
+            sleep(Duration::from_millis(20)).await;
+            assert!(!second.is_finished());
+
+            listener.accept().await.unwrap();
+            let second = second.await.unwrap().unwrap();
+            [first.peer_addr().unwrap(), second.peer_addr().unwrap()]
+        });
+        for addr in connected {
+            assert_eq!(addr.as_pathname(), Some(path.0.as_path()));
+        }
+    }
+
+    #[test]
+    fn connect_errors() {
+        let mut pool = multi::Executor::with_threads(2);
+        let path = TempPath::new("missing");
+        let error = pool.block_on(UnixStream::connect(&path.0)).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        // The socket file is left behind by a closed listener.
+        drop(UnixListener::bind(&path.0).unwrap());
+        let error = pool.block_on(UnixStream::connect(&path.0)).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+
+        let long = std::env::temp_dir().join("x".repeat(200));
+        let error = pool.block_on(UnixStream::connect(long)).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn truncated_fds() {
+        let mut pool = multi::Executor::with_threads(2);
+        let (a, b) = UnixDatagram::pair().unwrap();
+        // The credentials take up room that the file descriptors then miss.
+        let on: libc::c_int = 1;
+        cvt(unsafe {
+            libc::setsockopt(
+                b.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PASSCRED,
+                (&on as *const libc::c_int).cast(),
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        })
+        .unwrap();
+        let (_reader, writer) = io::pipe().unwrap();
+
+        pool.block_on(async {
+            let fds = vec![writer.as_fd(); MAX_FDS];
+            a.send_fds(b"many", &fds).await.unwrap();
+            let mut buf = [0; 16];
+            let mut fds = Vec::new();
+            let error = b.recv_fds(&mut buf, &mut fds).await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::Other);
+            assert!(fds.is_empty());
+        });
+    }
+}
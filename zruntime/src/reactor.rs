@@ -0,0 +1,356 @@
+//! The I/O reactor, waking tasks when their file descriptors become ready.
+
+use std::{
+    collections::HashMap,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    task::{Context, Poll, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+/// The epoll instance of the process, shared by all the executors.
+///
+/// Executors wait for I/O in their park step: one parked thread at a time waits in `epoll_wait`,
+/// with the nearest timer deadline as the timeout, while the others sleep until it leaves and
+/// hands over to one of them.
+pub(crate) struct Reactor {
+    epoll: OwnedFd,
+    /// An eventfd, written to interrupt `epoll_wait`.
+    notifier: OwnedFd,
+    sources: Mutex<Sources>,
+    /// Held by the thread waiting in `epoll_wait`, along with its event buffer.
+    events: Mutex<Vec<libc::epoll_event>>,
+    /// The threads parked while another one waits in `epoll_wait`.
+    idle: Mutex<Vec<Thread>>,
+}
+
+struct Sources {
+    by_key: HashMap<u64, Arc<Source>>,
+    next_key: u64,
+}
+
+/// The epoll key of the notifier, which no source ever gets.
+const NOTIFIER_KEY: u64 = u64::MAX;
+
+impl Reactor {
+    pub(crate) fn get() -> &'static Reactor {
+        static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+        REACTOR.get_or_init(|| Reactor::new().expect("failed to create the epoll reactor"))
+    }
+
+    fn new() -> io::Result<Reactor> {
+        let epoll = unsafe { OwnedFd::from_raw_fd(cvt(libc::epoll_create1(libc::EPOLL_CLOEXEC))?) };
+        let notifier = unsafe {
+            OwnedFd::from_raw_fd(cvt(libc::eventfd(
+                0,
+                libc::EFD_CLOEXEC | libc::EFD_NONBLOCK,
+            ))?)
+        };
+        // Level-triggered, so that a notification sent before `epoll_wait` isn't missed.
+        epoll_ctl(
+            &epoll,
+            libc::EPOLL_CTL_ADD,
+            notifier.as_raw_fd(),
+            libc::EPOLLIN as u32,
+            NOTIFIER_KEY,
+        )?;
+
+        Ok(Reactor {
+            epoll,
+            notifier,
+            sources: Mutex::new(Sources {
+                by_key: HashMap::new(),
+                next_key: 0,
+            }),
+            events: Mutex::new(Vec::with_capacity(1024)),
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Start watching `fd`, which must be in non-blocking mode.
+    pub(crate) fn register(&self, fd: RawFd) -> io::Result<Arc<Source>> {
+        let mut sources = self.sources.lock().unwrap();
+        let key = sources.next_key;
+        sources.next_key += 1;
+        let source = Arc::new(Source {
+            fd,
+            key,
+            directions: Mutex::new([Direction::default(), Direction::default()]),
+        });
+
+        // Edge-triggered, so that a ready source doesn't come back at every `epoll_wait`: it
+        // stays ready until an operation would block.
+        let interest = libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLET;
+        epoll_ctl(&self.epoll, libc::EPOLL_CTL_ADD, fd, interest as u32, key)?;
+        sources.by_key.insert(key, source.clone());
+
+        Ok(source)
+    }
+
+    pub(crate) fn deregister(&self, source: &Source) -> io::Result<()> {
+        self.sources.lock().unwrap().by_key.remove(&source.key);
+
+        epoll_ctl(&self.epoll, libc::EPOLL_CTL_DEL, source.fd, 0, 0)
+    }
+
+    /// Interrupt the thread waiting in `epoll_wait`, or its next wait if there's none.
+    pub(crate) fn notify(&self) {
+        let one = 1u64.to_ne_bytes();
+        // Can only fail if the counter is about to overflow, which means it's non-zero anyway.
+        unsafe { libc::write(self.notifier.as_raw_fd(), one.as_ptr().cast(), one.len()) };
+    }
+
+    /// Take the right to wait in `epoll_wait`, unless another thread has it.
+    pub(crate) fn try_lock(&self) -> Option<Poller<'_>> {
+        let events = self.events.try_lock().ok()?;
+
+        Some(Poller {
+            reactor: self,
+            events: Some(events),
+        })
+    }
+
+    /// Sleep while another thread waits in `epoll_wait`, until it hands over, `timeout` elapses, or
+    /// the thread is unparked.
+    pub(crate) fn idle(&self, timeout: Option<Duration>) {
+        let current = thread::current();
+        self.idle.lock().unwrap().push(current.clone());
+
+        // Checked after being listed, so that a poller leaving in the meantime hands over to us.
+        if self.events.try_lock().is_err() {
+            match timeout {
+                Some(timeout) => thread::park_timeout(timeout),
+                None => thread::park(),
+            }
+        }
+
+        let mut idle = self.idle.lock().unwrap();
+        if let Some(i) = idle.iter().position(|t| t.id() == current.id()) {
+            idle.swap_remove(i);
+        }
+    }
+
+    /// Wake the tasks whose I/O is ready, without blocking, unless another thread is waiting for
+    /// events already.
+    pub(crate) fn poll_now(&self) {
+        if let Some(mut poller) = self.try_lock() {
+            poller
+                .wait(Some(Duration::ZERO))
+                .expect("failed to poll for I/O events");
+        }
+    }
+
+    fn dispatch(&self, events: &[libc::epoll_event]) {
+        let mut wakers = Vec::new();
+        let sources = self.sources.lock().unwrap();
+        for event in events {
+            let (key, flags) = (event.u64, event.events as libc::c_int);
+            if key == NOTIFIER_KEY {
+                let mut count = [0; 8];
+                unsafe { libc::read(self.notifier.as_raw_fd(), count.as_mut_ptr().cast(), 8) };
+                continue;
+            }
+            // Deregistered since.
+            let Some(source) = sources.by_key.get(&key) else {
+                continue;
+            };
+
+            let mut directions = source.directions.lock().unwrap();
+            let read = libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLHUP | libc::EPOLLERR;
+            if flags & read != 0 {
+                directions[Interest::Read as usize].set_ready(&mut wakers);
+            }
+            if flags & (libc::EPOLLOUT | libc::EPOLLHUP | libc::EPOLLERR) != 0 {
+                directions[Interest::Write as usize].set_ready(&mut wakers);
+            }
+        }
+        drop(sources);
+
+        // Woken outside of the locks, as tasks can be polled right away by other threads.
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// The right to wait in `epoll_wait`, handed over to an idle thread when dropped.
+pub(crate) struct Poller<'a> {
+    reactor: &'a Reactor,
+    /// Only `None` while dropped.
+    events: Option<MutexGuard<'a, Vec<libc::epoll_event>>>,
+}
+
+impl Poller<'_> {
+    /// Wait for I/O events for up to `timeout`, or until notified, and wake the tasks waiting for
+    /// them.
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        // Rounded up, so that timers are due when `epoll_wait` returns, rather than a bit early.
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout.as_nanos().div_ceil(1_000_000).min(i32::MAX as u128) as i32
+        });
+        let events = self.events.as_mut().unwrap();
+        events.clear();
+        let count = unsafe {
+            libc::epoll_wait(
+                self.reactor.epoll.as_raw_fd(),
+                events.as_mut_ptr(),
+                events.capacity() as i32,
+                timeout,
+            )
+        };
+        match cvt(count) {
+            Ok(count) => unsafe { events.set_len(count as usize) },
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(()),
+            Err(e) => return Err(e),
+        }
+
+        self.reactor.dispatch(events);
+
+        Ok(())
+    }
+}
+
+impl Drop for Poller<'_> {
+    fn drop(&mut self) {
+        // Released first, so that the idle thread can take over once woken.
+        self.events = None;
+        if let Some(idle) = self.reactor.idle.lock().unwrap().pop() {
+            idle.unpark();
+        }
+    }
+}
+
+/// A file descriptor registered with the reactor.
+pub(crate) struct Source {
+    fd: RawFd,
+    key: u64,
+    directions: Mutex<[Direction; 2]>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Interest {
+    Read = 0,
+    Write = 1,
+}
+
+/// The readiness of a source in one direction, and the task waiting for it.
+#[derive(Default)]
+struct Direction {
+    /// Whether an event arrived since the last operation that would block.
+    ready: bool,
+    /// The number of events so far, so that clearing the readiness doesn't lose an event that
+    /// arrived during the operation.
+    tick: u64,
+    waker: Option<Waker>,
+}
+
+impl Direction {
+    fn set_ready(&mut self, wakers: &mut Vec<Waker>) {
+        self.ready = true;
+        self.tick += 1;
+        wakers.extend(self.waker.take());
+    }
+}
+
+impl Source {
+    /// Ready once epoll reported readiness since the last [`Source::clear_ready`], returning the
+    /// tick to clear it with.
+    ///
+    /// Only the task of the latest call gets woken, so there should be one task per direction.
+    pub(crate) fn poll_ready(&self, interest: Interest, cx: &mut Context<'_>) -> Poll<u64> {
+        let mut directions = self.directions.lock().unwrap();
+        let direction = &mut directions[interest as usize];
+        if direction.ready {
+            return Poll::Ready(direction.tick);
+        }
+        match &mut direction.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => direction.waker = Some(cx.waker().clone()),
+        }
+
+        Poll::Pending
+    }
+
+    /// Mark the source as not ready, after an operation would block, unless an event arrived since
+    /// `tick`.
+    pub(crate) fn clear_ready(&self, interest: Interest, tick: u64) {
+        let mut directions = self.directions.lock().unwrap();
+        let direction = &mut directions[interest as usize];
+        if direction.tick == tick {
+            direction.ready = false;
+        }
+    }
+}
+
+fn epoll_ctl(epoll: &OwnedFd, op: libc::c_int, fd: RawFd, events: u32, key: u64) -> io::Result<()> {
+    let mut event = libc::epoll_event { events, u64: key };
+    cvt(unsafe { libc::epoll_ctl(epoll.as_raw_fd(), op, fd, &mut event) })?;
+
+    Ok(())
+}
+
+/// Turn the `-1` of a failed libc call into the `errno` error.
+pub(crate) fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        io::Write,
+        sync::atomic::{AtomicBool, Ordering},
+        task::Wake,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn readiness() {
+        let mut fds = [0; 2];
+        cvt(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) }).unwrap();
+        let (reader, mut writer) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        let reactor = Reactor::get();
+        let source = reactor.register(reader.as_raw_fd()).unwrap();
+        let flag = Arc::new(Flag::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Dispatched by whichever thread polls the reactor.
+        reactor.poll_now();
+        assert!(source.poll_ready(Interest::Read, &mut cx).is_pending());
+        writer.write_all(b"ready").unwrap();
+        while !flag.0.load(Ordering::Acquire) {
+            reactor.poll_now();
+            thread::yield_now();
+        }
+        let Poll::Ready(tick) = source.poll_ready(Interest::Read, &mut cx) else {
+            panic!("not ready after the wake");
+        };
+
+        // An event since the tick keeps the source ready.
+        source.clear_ready(Interest::Read, tick - 1);
+        assert!(source.poll_ready(Interest::Read, &mut cx).is_ready());
+        source.clear_ready(Interest::Read, tick);
+        assert!(source.poll_ready(Interest::Read, &mut cx).is_pending());
+
+        reactor.deregister(&source).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Waiting for a point in time, without blocking the thread.
+//!
+//! Timers fire while an executor of this crate is running or blocked in `block_on`.
+
+pub(crate) mod timers;
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use timers::TimerKey;
+
+/// Stands in for deadlines too far away for an `Instant`, e.g. with `Duration::MAX`.
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// `instant + duration`, or a deadline in the far future if that overflows.
+fn deadline_after(instant: Instant, duration: Duration) -> Instant {
+    instant
+        .checked_add(duration)
+        .unwrap_or_else(|| instant + FAR_FUTURE)
+}
+
+/// Wait until `duration` has elapsed.
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(deadline_after(Instant::now(), duration))
+}
+
+/// Wait until `deadline` is reached.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        timer: None,
+    }
+}
+
+/// A future completing at a deadline, from [`sleep`] or [`sleep_until`].
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Instant,
+    /// Registered at the first poll, and removed when completed or dropped.
+    timer: Option<TimerKey>,
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Wait until `deadline` instead, even if the sleep already completed.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.cancel();
+        self.deadline = deadline;
+    }
+
+    fn cancel(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timers::deregister(&timer);
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            self.cancel();
+            return Poll::Ready(());
+        }
+
+        match &self.timer {
+            Some(timer) if timers::update(timer, cx.waker()) => (),
+            // Not registered yet, or fired a bit early.
+            _ => self.timer = Some(timers::register(self.deadline, cx.waker().clone())),
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Run `future`, giving up once `duration` has elapsed.
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future: Box::pin(future),
+        sleep: sleep(duration),
+    }
+}
+
+/// A future with a time limit, from [`timeout`].
+pub struct Timeout<F: Future> {
+    future: Pin<Box<F>>,
+    sleep: Sleep,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The future gets a chance to complete even if polled late.
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        ready!(Pin::new(&mut self.sleep).poll(cx));
+
+        Poll::Ready(Err(Elapsed))
+    }
+}
+
+/// The error of a [`Timeout`] whose future didn't complete in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl Error for Elapsed {}
+
+/// Ticks every `period`, starting right away.
+///
+/// # Panics
+///
+/// When `period` is zero.
+pub fn interval(period: Duration) -> Interval {
+    assert!(!period.is_zero(), "`period` must be non-zero");
+
+    Interval {
+        sleep: sleep_until(Instant::now()),
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+    }
+}
+
+/// What an [`Interval`] does when a tick is late by more than a period, e.g. because the task
+/// ticking it was busy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// Tick right away until caught up, keeping the original schedule.
+    #[default]
+    Burst,
+    /// Tick a full period after the late tick, shifting the schedule.
+    Delay,
+    /// Drop the missed ticks, and tick at the next point of the original schedule.
+    Skip,
+}
+
+/// Ticks at a fixed period, from [`interval`].
+#[derive(Debug)]
+pub struct Interval {
+    /// Completes at the next tick.
+    sleep: Sleep,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+}
+
+impl Interval {
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+
+    /// Wait for the next tick, returning when it was scheduled.
+    pub async fn tick(&mut self) -> Instant {
+        poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        ready!(Pin::new(&mut self.sleep).poll(cx));
+
+        let tick = self.sleep.deadline();
+        let now = Instant::now();
+        let mut next = deadline_after(tick, self.period);
+        if now > next {
+            next = match self.missed_tick_behavior {
+                MissedTickBehavior::Burst => next,
+                MissedTickBehavior::Delay => deadline_after(now, self.period),
+                MissedTickBehavior::Skip => {
+                    // At most a period past `now`, so it can't overflow a `u128`.
+                    let periods = (now - tick).as_nanos() / self.period.as_nanos() + 1;
+                    let nanos = self.period.as_nanos() * periods;
+                    let skipped = Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX));
+                    deadline_after(tick, skipped)
+                }
+            };
+        }
+        self.sleep.reset(next);
+
+        Poll::Ready(tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use futures::future;
+
+    use super::*;
+    use crate::executor::{multi, naive};
+
+    #[test]
+    fn sleep_and_timeout() {
+        let mut executor = naive::Executor::new();
+        let start = Instant::now();
+        executor.block_on(sleep(Duration::from_millis(50)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let late = executor.block_on(timeout(Duration::from_millis(20), future::pending::<()>()));
+        assert_eq!(late.unwrap_err().to_string(), "deadline has elapsed");
+        let on_time = executor.block_on(timeout(Duration::from_secs(1), async { 42 }));
+        assert_eq!(on_time.unwrap(), 42);
+    }
+
+    #[test]
+    fn large_durations() {
+        let mut executor = naive::Executor::new();
+        let start = Instant::now();
+        assert!(sleep(Duration::MAX).deadline() >= start + Duration::from_secs(86400 * 365));
+        let on_time = executor.block_on(timeout(Duration::MAX, async { 42 }));
+        assert_eq!(on_time.unwrap(), 42);
+
+        let mut ticker = interval(Duration::MAX);
+        let first = executor.block_on(ticker.tick());
+        assert!(first <= Instant::now());
+    }
+
+    #[test]
+    fn reset() {
+        let mut executor = naive::Executor::new();
+        let start = Instant::now();
+        let mut rearmed = sleep_until(start + Duration::from_secs(60));
+        rearmed.reset(start + Duration::from_millis(10));
+        let deadline = rearmed.deadline();
+        executor.block_on(rearmed);
+        assert!(Instant::now() >= deadline);
+        assert!(start.elapsed() < Duration::from_secs(60));
+    }
+
+    /// The ticks after the first one, relative to it, when the ticking task is busy for three
+    /// periods and a bit.
+    fn missed_ticks(behavior: MissedTickBehavior) -> Vec<Duration> {
+        let mut executor = naive::Executor::new();
+        executor.block_on(async move {
+            let mut ticker = interval(Duration::from_millis(20));
+            ticker.set_missed_tick_behavior(behavior);
+            let start = ticker.tick().await;
+            thread::sleep(ticker.period() * 3 + Duration::from_millis(5));
+            let mut ticks = Vec::new();
+            for _ in 0..3 {
+                ticks.push(ticker.tick().await - start);
+            }
+            ticks
+        })
+    }
+
+    #[test]
+    fn missed_tick_behavior() {
+        let millis = Duration::from_millis;
+        assert_eq!(
+            missed_ticks(MissedTickBehavior::Burst),
+            [millis(20), millis(40), millis(60)]
+        );
+        assert_eq!(
+            missed_ticks(MissedTickBehavior::Skip),
+            [millis(20), millis(80), millis(100)]
+        );
+
+        let delayed = missed_ticks(MissedTickBehavior::Delay);
+        assert_eq!(delayed[0], millis(20));
+        assert!(delayed[1] >= millis(85));
+        assert_eq!(delayed[2] - delayed[1], millis(20));
+    }
+
+    #[test]
+    fn many_timers() {
+        let mut pool = multi::Executor::with_threads(4);
+        let start = Instant::now();
+        let sleepers: Vec<_> = (0..10_000u64)
+            .map(|i| pool.spawn(sleep(Duration::from_millis(i % 100))))
+            .collect();
+        pool.run();
+        for sleeper in sleepers {
+            pool.block_on(sleeper).unwrap();
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(99));
+        assert!(elapsed < Duration::from_secs(1));
+    }
+}
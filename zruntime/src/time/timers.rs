@@ -0,0 +1,198 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    task::Waker,
+    time::{Duration, Instant},
+};
+
+/// Identifies a registered timer. Ordered by deadline, with the id breaking ties.
+pub(crate) type TimerKey = (Instant, u64);
+
+/// The pending timers of the process, ordered by deadline.
+///
+/// Registering, updating and removing a timer is logarithmic in the number of timers, and the next
+/// deadline is always the first entry, so thousands of timers don't slow the executors down.
+struct Timers {
+    entries: BTreeMap<TimerKey, Waker>,
+    next_id: u64,
+}
+
+static TIMERS: Mutex<Timers> = Mutex::new(Timers {
+    entries: BTreeMap::new(),
+    next_id: 0,
+});
+
+/// The nearest deadline, in nanoseconds since `base()`, or `NO_DEADLINE`.
+///
+/// Kept out of the lock, so that executors can check for expired timers between every task.
+static NEXT: AtomicU64 = AtomicU64::new(NO_DEADLINE);
+const NO_DEADLINE: u64 = u64::MAX;
+
+fn base() -> Instant {
+    static BASE: OnceLock<Instant> = OnceLock::new();
+
+    *BASE.get_or_init(Instant::now)
+}
+
+impl Timers {
+    /// Publish the nearest deadline after the entries changed, and return it.
+    fn update_next(&self) -> Option<Instant> {
+        let next = self.entries.keys().next().map(|(deadline, _)| *deadline);
+        let nanos = next.map_or(NO_DEADLINE, |deadline| {
+            let nanos = deadline.saturating_duration_since(base()).as_nanos();
+            u64::try_from(nanos).unwrap_or(u64::MAX)
+        });
+        NEXT.store(nanos, Ordering::Release);
+
+        next
+    }
+}
+
+/// Have `waker` woken once `deadline` is reached.
+pub(crate) fn register(deadline: Instant, waker: Waker) -> TimerKey {
+    let mut timers = TIMERS.lock().unwrap();
+    let key = (deadline, timers.next_id);
+    timers.next_id += 1;
+    timers.entries.insert(key, waker);
+    timers.update_next();
+
+    key
+}
+
+/// Replace the waker of a timer, returning `false` if it already fired.
+pub(crate) fn update(key: &TimerKey, waker: &Waker) -> bool {
+    let mut timers = TIMERS.lock().unwrap();
+    match timers.entries.get_mut(key) {
+        Some(registered) => {
+            if !registered.will_wake(waker) {
+                registered.clone_from(waker);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+pub(crate) fn deregister(key: &TimerKey) {
+    let mut timers = TIMERS.lock().unwrap();
+    if timers.entries.remove(key).is_some() {
+        timers.update_next();
+    }
+}
+
+/// Wake the timers whose deadline passed, returning the nearest deadline of the others.
+///
+/// Executors call this before parking, to know for how long they can sleep, and between tasks.
+pub(crate) fn fire_expired() -> Option<Instant> {
+    let next = NEXT.load(Ordering::Acquire);
+    if next == NO_DEADLINE {
+        return None;
+    }
+    let next = base() + Duration::from_nanos(next);
+    let now = Instant::now();
+    if now < next {
+        return Some(next);
+    }
+
+    let mut expired = Vec::new();
+    let mut timers = TIMERS.lock().unwrap();
+    while let Some(entry) = timers.entries.first_entry() {
+        if entry.key().0 > now {
+            break;
+        }
+        expired.push(entry.remove());
+    }
+    let next = timers.update_next();
+    drop(timers);
+
+    // Woken outside of the lock, as waking a task can register timers too.
+    for waker in expired {
+        waker.wake();
+    }
+
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        task::Wake,
+        thread,
+    };
+
+    use super::*;
+
+    /// Records the order timers fire in.
+    struct Fired {
+        order: Arc<Mutex<Vec<u32>>>,
+        id: u32,
+    }
+
+    impl Wake for Fired {
+        fn wake(self: Arc<Self>) {
+            self.order.lock().unwrap().push(self.id);
+        }
+    }
+
+    fn waker(order: &Arc<Mutex<Vec<u32>>>, id: u32) -> Waker {
+        Waker::from(Arc::new(Fired {
+            order: order.clone(),
+            id,
+        }))
+    }
+
+    /// Fire timers until `count` of them were recorded in `order`.
+    fn wait_for(order: &Mutex<Vec<u32>>, count: usize) {
+        while order.lock().unwrap().len() < count {
+            if let Some(next) = fire_expired() {
+                thread::sleep(next.saturating_duration_since(Instant::now()));
+            }
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let now = Instant::now();
+        for (id, millis) in [(3, 30), (1, 10), (2, 20)] {
+            register(now + Duration::from_millis(millis), waker(&order, id));
+        }
+        // Same deadline, fired in registration order.
+        register(now + Duration::from_millis(30), waker(&order, 4));
+
+        wait_for(&order, 4);
+        assert_eq!(*order.lock().unwrap(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cancellation() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let now = Instant::now();
+        let cancelled = register(now + Duration::from_millis(10), waker(&order, 1));
+        let updated = register(now + Duration::from_millis(10), waker(&order, 2));
+        let fired = register(now + Duration::from_millis(20), waker(&order, 3));
+        deregister(&cancelled);
+        assert!(update(&updated, &waker(&order, 4)));
+
+        wait_for(&order, 2);
+        assert_eq!(*order.lock().unwrap(), [4, 3]);
+        assert!(!update(&fired, &waker(&order, 5)));
+    }
+
+    #[test]
+    fn far_deadline() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        // Further away than nanoseconds fit in a `u64`.
+        let key = register(
+            Instant::now() + Duration::from_secs(1 << 40),
+            waker(&order, 1),
+        );
+        fire_expired();
+        deregister(&key);
+        assert!(order.lock().unwrap().is_empty());
+    }
+}